@@ -0,0 +1,197 @@
+use crate::blockchain::get_all_chains_for;
+use crate::config::Settings;
+use crate::merkle::{leaf_hash, merkle_root};
+use crate::signing::{sign, verify};
+use ed25519_dalek::VerifyingKey;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChainHead {
+    pub account: String,
+    pub symbol: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<u64>,
+    pub index: u64,
+    pub hash: String,
+}
+
+impl ChainHead {
+    fn leaf(&self) -> [u8; 32] {
+        leaf_hash(format!("{}:{}:{}:{}", self.account, self.symbol, self.index, self.hash).as_bytes())
+    }
+}
+
+// One line of the anchor file. `previous` is the SHA-256 of the line before ("0" for the first),
+// so lines can't be dropped or reordered, and `signature` covers "<timestamp>:<previous>:<root>".
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AnchorRecord {
+    pub timestamp: u64,
+    pub previous: String,
+    pub root: String,
+    pub heads: Vec<ChainHead>,
+    pub signature: String,
+}
+
+impl AnchorRecord {
+    fn message(&self) -> String {
+        format!("{}:{}:{}", self.timestamp, self.previous, self.root)
+    }
+}
+
+struct LastAnchor {
+    digest: String,
+    root: String,
+}
+
+// Tail of the anchor file, read once and then kept up to date by `write_anchor`.
+static LAST_ANCHOR: Lazy<Mutex<Option<LastAnchor>>> = Lazy::new(|| Mutex::new(None));
+
+fn digest(line: &str) -> String {
+    hex::encode(Sha256::digest(line.as_bytes()))
+}
+
+fn root_of(heads: &[ChainHead]) -> String {
+    let leaves: Vec<[u8; 32]> = heads.iter().map(ChainHead::leaf).collect();
+    hex::encode(merkle_root(&leaves))
+}
+
+// Last block of every chain, open and archived, of every account.
+pub fn chain_heads() -> Vec<ChainHead> {
+    let mut heads: Vec<ChainHead> = Settings::current()
+        .account_names()
+        .into_iter()
+        .flat_map(|account| {
+            get_all_chains_for(&account).into_iter().filter_map(move |chain| {
+                let last = chain.blocks.last()?;
+                Some(ChainHead {
+                    account: account.clone(),
+                    symbol: chain.symbol,
+                    archived_at: chain.archived_at,
+                    index: last.index,
+                    hash: last.hash.clone(),
+                })
+            })
+        })
+        .collect();
+    heads.sort_by(|a, b| {
+        (&a.account, &a.symbol, a.archived_at.unwrap_or(u64::MAX))
+            .cmp(&(&b.account, &b.symbol, b.archived_at.unwrap_or(u64::MAX)))
+    });
+    heads
+}
+
+fn read_last_anchor(path: &str) -> Result<Option<LastAnchor>, String> {
+    if !Path::new(path).exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let Some(line) = content.lines().rev().find(|l| !l.trim().is_empty()) else {
+        return Ok(None);
+    };
+    let record: AnchorRecord =
+        serde_json::from_str(line).map_err(|e| format!("Last anchor in {} is unreadable: {}", path, e))?;
+    Ok(Some(LastAnchor {
+        digest: digest(line),
+        root: record.root,
+    }))
+}
+
+// Appends a signed record of the current chain heads, unless there are none or nothing changed
+// since the last record.
+pub fn write_anchor(path: &str) -> Result<Option<AnchorRecord>, String> {
+    let heads = chain_heads();
+    if heads.is_empty() {
+        return Ok(None);
+    }
+    let root = root_of(&heads);
+
+    let mut last = LAST_ANCHOR.lock().unwrap();
+    if last.is_none() {
+        *last = read_last_anchor(path)?;
+    }
+    if last.as_ref().is_some_and(|l| l.root == root) {
+        return Ok(None);
+    }
+
+    let mut record = AnchorRecord {
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        previous: last.as_ref().map(|l| l.digest.clone()).unwrap_or_else(|| "0".to_string()),
+        root,
+        heads,
+        signature: String::new(),
+    };
+    record.signature = sign(record.message().as_bytes());
+
+    let line = serde_json::to_string(&record).map_err(|e| e.to_string())?;
+    if let Some(dir) = Path::new(path).parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", line))
+        .map_err(|e| format!("Failed to append to {}: {}", path, e))?;
+
+    *last = Some(LastAnchor {
+        digest: digest(&line),
+        root: record.root.clone(),
+    });
+    Ok(Some(record))
+}
+
+// Checks a whole anchor file: links between lines, each root against its heads and each signature.
+// Returns the number of records.
+pub fn verify_anchors(content: &str, key: &VerifyingKey) -> Result<usize, String> {
+    let mut previous = "0".to_string();
+    let mut count = 0;
+
+    for (number, line) in content.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        let number = number + 1;
+        let record: AnchorRecord =
+            serde_json::from_str(line).map_err(|e| format!("Line {}: unreadable record: {}", number, e))?;
+
+        if record.previous != previous {
+            return Err(format!("Line {}: does not follow the previous record", number));
+        }
+        if root_of(&record.heads) != record.root {
+            return Err(format!("Line {}: root does not match its chain heads", number));
+        }
+        if !verify(key, record.message().as_bytes(), &record.signature) {
+            return Err(format!("Line {}: bad signature", number));
+        }
+
+        previous = digest(line);
+        count += 1;
+    }
+    Ok(count)
+}
+
+pub fn spawn_anchor_writer() {
+    let settings = Settings::current().blockchain.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(settings.anchor_interval_secs));
+        // The first tick fires immediately, before any chain exists.
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            match write_anchor(&settings.anchor_file) {
+                Ok(Some(record)) => println!(
+                    "[blockchain] Anchor {} written ({} chains)",
+                    record.root,
+                    record.heads.len()
+                ),
+                Ok(None) => {}
+                Err(e) => eprintln!("[blockchain] Failed to write anchor: {}", e),
+            }
+        }
+    });
+}
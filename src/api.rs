@@ -1,33 +1,94 @@
 use actix_web::{get, post, put, web, HttpResponse, Responder};
 use crate::balance::get_futures_balance;
 use crate::config::Settings;
-use crate::dto::{OpenOrderRequest, SymbolRequest};
-use crate::leverage::set_leverage;
-use crate::order::{close_all_positions, execute_future_order};
+use crate::credential::has_binance_credentials;
+use crate::dto::{
+    AccountQuery, AccountSummary, BlockPage, BlockQuery, ChainExportQuery, ChainSummary, BreadthPoint, EventsQuery, HistoryQuery, SymbolHistoryPoint,
+    ZoneDistributionPoint, AssistantReply, BalanceResponse, ChainValidityResponse,
+    ErrorResponse, FundingPnlResponse, LeverageStateResponse, LiquidationRisk, MonitorQuery, OpenOrderRequest,
+    LeverageRequest, OrderResponse, ProofQuery, PublicKeyResponse, SymbolRequest, Trade, TradeMonitorPage,
+};
+use crate::error::AppError;
+use crate::leverage::{get_leverage_states, set_leverage_with_value, LeverageResponse};
+use crate::order::{close_all_positions, execute_future_order, reconcile_orders};
+use crate::order_state::{get_order_records, manual_client_order_id, OrderRecord};
+use crate::executor::{get_executor_statuses, run_serialized, ExecutorStatus};
+use crate::chain_export::{export_chains, import_chains, ChainExport, ImportResponse};
+use crate::checkpoint::{get_checkpoints, inclusion_proof, Checkpoint, InclusionProof};
 use crate::schedule::get_scheduler;
-use crate::blockchain::{get_blockchain_for, get_last_trade_for, get_all_symbols, BLOCKCHAIN};
+use crate::signing::public_key;
+use crate::blockchain::{
+    get_blockchain_for, get_current_blockchain_symbols, get_last_trade_for, is_blockchain_valid,
+    record_manual_override, TradeBlock,
+};
 use crate::spy::spy_cryptos;
-use crate::monitor::monitor_cryptos;
+use crate::monitor::{last_snapshot, monitor_cryptos, page_snapshot, store_snapshot};
+use crate::monitor_table::render;
 use crate::open_ai::send_to_assistant;
+use crate::auth::{Operator, Trader, Viewer};
+use crate::settings_service::{account_settings, apply_patch, redacted, SettingsUpdateResponse};
+use crate::liquidation::get_liquidation_risks;
+use crate::funding::get_funding_accruals;
+use crate::events::{subscribe, Event, Topic};
+use crate::chain_query::{chain_summaries, query_blocks};
+use crate::history::{
+    breadth_history, load_snapshots, symbol_history, zone_distribution_history, MonitorSnapshotRecord,
+};
+
+type ApiResult = Result<HttpResponse, AppError>;
+
+// Effective settings for `?account=`; without it the first configured account is used.
+#[utoipa::path(
+    tag = "accounts",
+    responses((status = 200, description = "Configured accounts", body = [AccountSummary]))
+)]
+#[get("/accounts")]
+pub async fn get_accounts(_caller: Viewer) -> impl Responder {
+    let accounts: Vec<AccountSummary> = Settings::current()
+        .all_accounts()
+        .into_iter()
+        .map(|settings| AccountSummary {
+            open_chains: get_current_blockchain_symbols(&settings.binance.account),
+            credentials_configured: has_binance_credentials(&settings.binance.account),
+            name: settings.binance.account,
+            symbols: settings.cryptos,
+            money: settings.money,
+            leverage: settings.binance.leverage,
+            boost_leverage: settings.binance.boost_leverage,
+            limit_operations: settings.limit_operations,
+        })
+        .collect();
+    HttpResponse::Ok().json(accounts)
+}
 
-use std::fmt::Write;
-
+#[utoipa::path(
+    tag = "scheduler",
+    responses((status = 200, description = "Scheduler started", body = String))
+)]
 #[post("/trades/start")]
-pub async fn post_trades_start() -> impl Responder {
+pub async fn post_trades_start(_caller: Operator) -> impl Responder {
     let scheduler = get_scheduler();
     let mut scheduler = scheduler.lock().unwrap();
     scheduler.start();
     HttpResponse::Ok().body("Timer started")
 }
 
+#[utoipa::path(
+    tag = "scheduler",
+    responses((status = 200, description = "Scheduler stopped", body = String))
+)]
 #[post("/trades/stop")]
-pub async fn post_trades_stop() -> impl Responder {
+pub async fn post_trades_stop(_caller: Operator) -> impl Responder {
     let scheduler = get_scheduler();
     let mut scheduler = scheduler.lock().unwrap();
     scheduler.stop();
     HttpResponse::Ok().body("Timer stopped")
 }
 
+#[utoipa::path(
+    tag = "scheduler",
+    responses((status = 200, description = "`status: UP` or `status: DOWN`", body = String))
+)]
 #[get("/trades/health-check")]
 pub async fn get_trades_health_check() -> impl Responder {
     let scheduler = get_scheduler();
@@ -36,189 +97,649 @@ pub async fn get_trades_health_check() -> impl Responder {
     HttpResponse::Ok().body(format!("status: {}", status))
 }
 
+#[utoipa::path(
+    tag = "chains",
+    params(("symbol" = String, Path, description = "Symbol, e.g. BTCUSDT"), AccountQuery),
+    responses(
+        (status = 200, description = "Blocks of the symbol's chain", body = [TradeBlock]),
+        (status = 404, description = "Unknown account or no chain for the symbol", body = ErrorResponse)
+    )
+)]
 #[get("/trades/chains/{symbol}")]
-pub async fn get_trades_chain_by_symbol(path: web::Path<String>) -> impl Responder {
+pub async fn get_trades_chain_by_symbol(
+    path: web::Path<String>,
+    query: web::Query<AccountQuery>,
+    _caller: Viewer,
+) -> ApiResult {
+    let settings = account_settings(query.account.as_deref())?;
     let symbol = path.into_inner();
-    match get_blockchain_for(&symbol) {
-        Some(chain) => HttpResponse::Ok().json(chain),
-        None => HttpResponse::NotFound().body(format!("Nenhuma blockchain encontrada para {}", symbol)),
-    }
+    get_blockchain_for(&settings.binance.account, &symbol)
+        .map(|chain| HttpResponse::Ok().json(chain))
+        .ok_or_else(|| AppError::NotFound(format!("No chain found for {}", symbol)))
 }
 
+#[utoipa::path(
+    tag = "chains",
+    params(("symbol" = String, Path, description = "Symbol, e.g. BTCUSDT"), AccountQuery),
+    responses(
+        (status = 200, description = "Trade of the last block", body = Trade),
+        (status = 404, description = "Unknown account or no chain for the symbol", body = ErrorResponse)
+    )
+)]
 #[get("/trades/chains/{symbol}/last")]
-pub async fn get_last_trade_by_symbol(path: web::Path<String>) -> impl Responder {
+pub async fn get_last_trade_by_symbol(
+    path: web::Path<String>,
+    query: web::Query<AccountQuery>,
+    _caller: Viewer,
+) -> ApiResult {
+    let settings = account_settings(query.account.as_deref())?;
     let symbol = path.into_inner();
-    match get_last_trade_for(&symbol) {
-        Some(trade) => HttpResponse::Ok().json(trade),
-        None => HttpResponse::NotFound().body(format!("Nenhum trade encontrado para {}", symbol)),
-    }
+    get_last_trade_for(&settings.binance.account, &symbol)
+        .map(|trade| HttpResponse::Ok().json(trade))
+        .ok_or_else(|| AppError::NotFound(format!("No trade found for {}", symbol)))
 }
 
+#[utoipa::path(
+    tag = "trading",
+    params(AccountQuery),
+    responses(
+        (status = 200, description = "USDT futures balance", body = [BalanceResponse]),
+        (status = 404, description = "Unknown account", body = ErrorResponse),
+        (status = 502, description = "Binance error", body = ErrorResponse),
+        (status = 503, description = "Binance credentials missing", body = ErrorResponse)
+    )
+)]
 #[get("/trades/balance")]
-pub async fn get_trades_balance() -> impl Responder {
-    let settings = Settings::load();
-
-    match get_futures_balance(&settings.binance).await {
-        Ok(balances) => {
-            let usdt_balance: Vec<_> = balances
-                .into_iter()
-                .filter(|b| b.asset == "USDT")
-                .collect();
-            HttpResponse::Ok().json(usdt_balance)
-        }
-        Err(e) => {
-            eprintln!("Erro ao consultar saldo de futuros: {}", e);
-            HttpResponse::InternalServerError().body(format!("Erro: {}", e))
-        }
-    }
+pub async fn get_trades_balance(query: web::Query<AccountQuery>, _caller: Viewer) -> ApiResult {
+    let settings = account_settings(query.account.as_deref())?;
+
+    let usdt_balance: Vec<_> = get_futures_balance(&settings.binance)
+        .await?
+        .into_iter()
+        .filter(|b| b.asset == "USDT")
+        .collect();
+    Ok(HttpResponse::Ok().json(usdt_balance))
 }
 
+#[utoipa::path(
+    tag = "trading",
+    params(AccountQuery),
+    request_body = OpenOrderRequest,
+    responses(
+        (status = 200, description = "Order accepted by Binance", body = OrderResponse),
+        (status = 400, description = "Invalid side or body", body = ErrorResponse),
+        (status = 404, description = "Unknown account", body = ErrorResponse),
+        (status = 409, description = "Duplicate client order id", body = ErrorResponse),
+        (status = 422, description = "Rejected by a risk check", body = ErrorResponse),
+        (status = 502, description = "Binance error", body = ErrorResponse)
+    )
+)]
 #[post("/trades/order/open")]
-pub async fn post_trades_order(req: web::Json<OpenOrderRequest>) -> impl Responder {
-    let settings = Settings::load();
+pub async fn post_trades_order(
+    req: web::Json<OpenOrderRequest>,
+    query: web::Query<AccountQuery>,
+    auth: Trader,
+) -> ApiResult {
+    let settings = account_settings(query.account.as_deref())?;
     let binance_settings = &settings.binance;
 
     let side = req.side.to_uppercase();
     let symbol = req.symbol.to_uppercase();
 
     if side != "BUY" && side != "SELL" {
-        return HttpResponse::BadRequest().body("O parâmetro 'side' deve ser 'BUY' ou 'SELL'");
+        return Err(AppError::validation("'side' must be 'BUY' or 'SELL'"));
     }
 
-    match execute_future_order(binance_settings, &side, &symbol).await {
-        Ok(order) => HttpResponse::Ok().json(order),
-        Err(e) => {
-            eprintln!("Erro ao enviar ordem para Binance: {}", e);
-            HttpResponse::InternalServerError().body(e)
-        }
-    }
+    let order_id = manual_client_order_id(&symbol, &side.to_lowercase());
+    println!(
+        "Manual {} order for {} on account {} requested by {}",
+        side, symbol, binance_settings.account, auth.caller.name
+    );
+
+    let result = run_serialized(
+        &binance_settings.account,
+        &symbol,
+        "manual_open",
+        execute_future_order(binance_settings, &side, &symbol, &order_id),
+    )
+    .await;
+    record_manual_override(
+        &binance_settings.account,
+        &symbol,
+        &format!("open_{}", side.to_lowercase()),
+        &auth.caller.name,
+        &result,
+    );
+
+    Ok(HttpResponse::Ok().json(result?))
 }
 
+#[utoipa::path(
+    tag = "trading",
+    params(AccountQuery),
+    request_body = SymbolRequest,
+    responses(
+        (status = 200, description = "Closing orders sent", body = [OrderResponse]),
+        (status = 404, description = "Unknown account", body = ErrorResponse),
+        (status = 502, description = "Binance error", body = ErrorResponse)
+    )
+)]
 #[post("/trades/order/close")]
-pub async fn post_close_all_positions(req: web::Json<SymbolRequest>) -> impl Responder {
-    let settings = Settings::load();
+pub async fn post_close_all_positions(
+    req: web::Json<SymbolRequest>,
+    query: web::Query<AccountQuery>,
+    auth: Trader,
+) -> ApiResult {
+    let settings = account_settings(query.account.as_deref())?;
     let binance_settings = &settings.binance;
 
-    match close_all_positions(binance_settings, &req.symbol).await {
-        Ok(orders) => HttpResponse::Ok().json(orders),
-        Err(e) => {
-            eprintln!("Erro ao fechar posições: {}", e);
-            HttpResponse::InternalServerError().body(e)
-        }
-    }
+    let order_id = manual_client_order_id(&req.symbol, "close");
+    println!(
+        "Manual close for {} on account {} requested by {}",
+        req.symbol, binance_settings.account, auth.caller.name
+    );
+
+    let result = run_serialized(
+        &binance_settings.account,
+        &req.symbol,
+        "manual_close",
+        close_all_positions(binance_settings, &req.symbol, &order_id),
+    )
+    .await;
+    record_manual_override(&binance_settings.account, &req.symbol, "close", &auth.caller.name, &result);
+
+    Ok(HttpResponse::Ok().json(result?))
+}
+
+#[utoipa::path(
+    tag = "trading",
+    params(AccountQuery),
+    responses(
+        (status = 200, description = "Per-symbol action queues", body = [ExecutorStatus]),
+        (status = 404, description = "Unknown account", body = ErrorResponse)
+    )
+)]
+#[get("/trades/executions")]
+pub async fn get_trades_executions(query: web::Query<AccountQuery>, _caller: Viewer) -> ApiResult {
+    let settings = account_settings(query.account.as_deref())?;
+    Ok(HttpResponse::Ok().json(get_executor_statuses(&settings.binance.account)))
 }
 
+#[utoipa::path(
+    tag = "trading",
+    params(AccountQuery),
+    responses(
+        (status = 200, description = "Local order records", body = [OrderRecord]),
+        (status = 404, description = "Unknown account", body = ErrorResponse)
+    )
+)]
+#[get("/trades/orders")]
+pub async fn get_trades_orders(query: web::Query<AccountQuery>, _caller: Viewer) -> ApiResult {
+    let settings = account_settings(query.account.as_deref())?;
+    Ok(HttpResponse::Ok().json(get_order_records(&settings.binance.account)))
+}
+
+#[utoipa::path(
+    tag = "trading",
+    params(AccountQuery),
+    responses(
+        (status = 200, description = "Order records after querying Binance", body = [OrderRecord]),
+        (status = 404, description = "Unknown account", body = ErrorResponse)
+    )
+)]
+#[post("/trades/orders/reconcile")]
+pub async fn post_reconcile_orders(query: web::Query<AccountQuery>, _caller: Operator) -> ApiResult {
+    let settings = account_settings(query.account.as_deref())?;
+    Ok(HttpResponse::Ok().json(reconcile_orders(&settings.binance).await))
+}
+
+#[utoipa::path(
+    tag = "risk",
+    params(AccountQuery),
+    request_body = LeverageRequest,
+    responses(
+        (status = 200, description = "Leverage applied", body = LeverageResponse),
+        (status = 404, description = "Unknown account", body = ErrorResponse),
+        (status = 422, description = "Above the symbol's maximum leverage", body = ErrorResponse),
+        (status = 502, description = "Binance error", body = ErrorResponse)
+    )
+)]
 #[put("/trades/leverage")]
-pub async fn put_leverage(req: web::Json<SymbolRequest>) -> impl Responder {
-    let settings = Settings::load();
+pub async fn put_leverage(
+    req: web::Json<LeverageRequest>,
+    query: web::Query<AccountQuery>,
+    auth: Trader,
+) -> ApiResult {
+    let settings = account_settings(query.account.as_deref())?;
     let symbol = &req.symbol;
+    let leverage = req.leverage.unwrap_or_else(|| settings.binance.leverage_for(symbol));
+    println!(
+        "Manual leverage change for {} on account {} requested by {}",
+        symbol, settings.binance.account, auth.caller.name
+    );
+
+    let result = run_serialized(
+        &settings.binance.account,
+        symbol,
+        "manual_leverage",
+        set_leverage_with_value(&settings.binance, symbol, leverage),
+    )
+    .await;
+    record_manual_override(&settings.binance.account, symbol, "leverage", &auth.caller.name, &result);
 
-    match set_leverage(&settings.binance, symbol).await {
-        Ok(response) => HttpResponse::Ok().json(response),
-        Err(e) => {
-            eprintln!("Erro ao aplicar alavancagem: {}", e);
-            HttpResponse::InternalServerError().body(format!("Erro: {}", e))
-        }
-    }
+    Ok(HttpResponse::Ok().json(result?))
+}
+
+#[utoipa::path(
+    tag = "risk",
+    params(AccountQuery),
+    responses(
+        (status = 200, description = "Leverage and margin type per active symbol", body = [LeverageStateResponse]),
+        (status = 404, description = "Unknown account", body = ErrorResponse),
+        (status = 502, description = "Binance error", body = ErrorResponse)
+    )
+)]
+#[get("/trades/leverage")]
+pub async fn get_leverage(query: web::Query<AccountQuery>, _caller: Viewer) -> ApiResult {
+    let settings = account_settings(query.account.as_deref())?;
+
+    Ok(HttpResponse::Ok().json(get_leverage_states(&settings.binance).await?))
 }
 
+#[utoipa::path(
+    tag = "risk",
+    params(AccountQuery),
+    responses(
+        (status = 200, description = "Liquidation distance per position", body = [LiquidationRisk]),
+        (status = 404, description = "Unknown account", body = ErrorResponse),
+        (status = 502, description = "Binance error", body = ErrorResponse)
+    )
+)]
+#[get("/trades/liquidation")]
+pub async fn get_liquidation_risk(query: web::Query<AccountQuery>, _caller: Viewer) -> ApiResult {
+    let settings = account_settings(query.account.as_deref())?;
+
+    Ok(HttpResponse::Ok().json(get_liquidation_risks(&settings).await?))
+}
+
+#[utoipa::path(
+    tag = "risk",
+    params(AccountQuery),
+    responses(
+        (status = 200, description = "Accumulated funding per open position", body = [FundingPnlResponse]),
+        (status = 404, description = "Unknown account", body = ErrorResponse)
+    )
+)]
+#[get("/trades/funding")]
+pub async fn get_trades_funding(query: web::Query<AccountQuery>, _caller: Viewer) -> ApiResult {
+    let settings = account_settings(query.account.as_deref())?;
+    Ok(HttpResponse::Ok().json(get_funding_accruals(&settings.binance.account)))
+}
+
+#[utoipa::path(
+    tag = "market",
+    params(AccountQuery),
+    responses(
+        (status = 200, description = "Indicators of every configured symbol", body = [Trade]),
+        (status = 404, description = "Unknown account", body = ErrorResponse),
+        (status = 503, description = "Disabled in the settings", body = ErrorResponse)
+    )
+)]
 #[get("/trades/spy")]
-pub async fn get_trades_spy() -> impl Responder {
-    let settings = Settings::load();
+pub async fn get_trades_spy(query: web::Query<AccountQuery>, _caller: Viewer) -> ApiResult {
+    let settings = account_settings(query.account.as_deref())?;
 
     if !settings.spy {
-        return HttpResponse::Forbidden().body("Serviço /trades/spy está desativado na configuração");
+        return Err(AppError::Config("/trades/spy is disabled in the settings (spy = false)".into()));
     }
 
     let binance_settings = settings.binance.clone();
     let cryptos = settings.cryptos.clone();
 
     let trades = spy_cryptos(
+        &binance_settings.account,
         &binance_settings.base_url,
+        &binance_settings.future_url,
         &binance_settings.interval,
         binance_settings.limit,
         cryptos,
     )
     .await;
 
-    HttpResponse::Ok().json(trades)
+    Ok(HttpResponse::Ok().json(trades))
 }
 
+#[utoipa::path(
+    tag = "chains",
+    params(AccountQuery),
+    responses(
+        (status = 200, description = "Symbols with an open chain", body = [String]),
+        (status = 404, description = "Unknown account", body = ErrorResponse)
+    )
+)]
 #[get("/trades/chains")]
-pub async fn get_all_symbols_chains() -> impl Responder {
-    let symbols = get_all_symbols();
-    HttpResponse::Ok().json(symbols)
+pub async fn get_all_symbols_chains(query: web::Query<AccountQuery>, _caller: Viewer) -> ApiResult {
+    let settings = account_settings(query.account.as_deref())?;
+    Ok(HttpResponse::Ok().json(get_current_blockchain_symbols(&settings.binance.account)))
+}
+
+#[utoipa::path(
+    tag = "chains",
+    params(BlockQuery),
+    responses(
+        (status = 200, description = "Blocks of open and archived chains, oldest first by default", body = BlockPage),
+        (status = 400, description = "Unknown event type, status, bias or order, or an inverted range", body = ErrorResponse),
+        (status = 404, description = "Unknown account", body = ErrorResponse)
+    )
+)]
+#[get("/trades/blocks")]
+pub async fn get_trades_blocks(query: web::Query<BlockQuery>, _caller: Viewer) -> ApiResult {
+    let settings = account_settings(query.account.as_deref())?;
+    Ok(HttpResponse::Ok().json(query_blocks(&settings.binance.account, &query)?))
 }
 
+#[utoipa::path(
+    tag = "chains",
+    responses(
+        (status = 200, description = "Signed Merkle roots over every block hash, oldest first", body = [Checkpoint])
+    )
+)]
+#[get("/trades/checkpoints")]
+pub async fn get_trades_checkpoints(_caller: Viewer) -> ApiResult {
+    Ok(HttpResponse::Ok().json(get_checkpoints()))
+}
+
+#[utoipa::path(
+    tag = "chains",
+    params(("hash" = String, Path, description = "Block hash"), ProofQuery),
+    responses(
+        (status = 200, description = "The block and the path from its leaf to the checkpoint root", body = InclusionProof),
+        (status = 404, description = "Unknown checkpoint, or block not covered by it", body = ErrorResponse)
+    )
+)]
+#[get("/trades/blocks/{hash}/proof")]
+pub async fn get_block_proof(
+    path: web::Path<String>,
+    query: web::Query<ProofQuery>,
+    _caller: Viewer,
+) -> ApiResult {
+    Ok(HttpResponse::Ok().json(inclusion_proof(&path.into_inner(), query.checkpoint)?))
+}
+
+#[utoipa::path(
+    tag = "chains",
+    params(AccountQuery),
+    responses(
+        (status = 200, description = "Block count, first and last block and current status per symbol", body = [ChainSummary]),
+        (status = 404, description = "Unknown account", body = ErrorResponse)
+    )
+)]
+#[get("/trades/chains/summary")]
+pub async fn get_chains_summary(query: web::Query<AccountQuery>, _caller: Viewer) -> ApiResult {
+    let settings = account_settings(query.account.as_deref())?;
+    Ok(HttpResponse::Ok().json(chain_summaries(&settings.binance.account)))
+}
+
+#[utoipa::path(
+    tag = "chains",
+    params(ChainExportQuery),
+    responses(
+        (status = 200, description = "Open and archived chains with all their blocks", body = [ChainExport]),
+        (status = 404, description = "Unknown account", body = ErrorResponse)
+    )
+)]
+#[get("/trades/chains/export")]
+pub async fn get_chains_export(query: web::Query<ChainExportQuery>, _caller: Viewer) -> ApiResult {
+    let settings = account_settings(query.account.as_deref())?;
+    Ok(HttpResponse::Ok().json(export_chains(&settings.binance.account, query.symbol.as_deref())))
+}
+
+// Restores chains from an export, e.g. after a restart; nothing is restored unless every chain validates.
+#[utoipa::path(
+    tag = "chains",
+    params(AccountQuery),
+    request_body = [ChainExport],
+    responses(
+        (status = 200, description = "Chains restored", body = ImportResponse),
+        (status = 400, description = "A chain of another account or not starting with its symbol's status", body = ErrorResponse),
+        (status = 404, description = "Unknown account", body = ErrorResponse),
+        (status = 409, description = "A chain does not validate, is already present or clashes with an open one", body = ErrorResponse)
+    )
+)]
+#[post("/trades/chains/import")]
+pub async fn post_chains_import(
+    query: web::Query<AccountQuery>,
+    body: web::Json<Vec<ChainExport>>,
+    _caller: Operator,
+) -> ApiResult {
+    let settings = account_settings(query.account.as_deref())?;
+    Ok(HttpResponse::Ok().json(import_chains(&settings.binance.account, body.into_inner())?))
+}
+
+#[utoipa::path(
+    tag = "chains",
+    params(("symbol" = String, Path, description = "Symbol, e.g. BTCUSDT"), AccountQuery),
+    responses(
+        (status = 200, description = "Every block links, hashes and carries the bot's signature", body = ChainValidityResponse),
+        (status = 404, description = "Unknown account or no chain for the symbol", body = ErrorResponse),
+        (status = 409, description = "The chain is corrupted", body = ErrorResponse)
+    )
+)]
 #[get("/trades/chains/{symbol}/valid")]
-pub async fn get_chain_validity(path: web::Path<String>) -> impl Responder {
+pub async fn get_chain_validity(
+    path: web::Path<String>,
+    query: web::Query<AccountQuery>,
+    _caller: Viewer,
+) -> ApiResult {
+    let settings = account_settings(query.account.as_deref())?;
     let symbol = path.into_inner();
-    let map = BLOCKCHAIN.lock().unwrap();
-
-    match map.get(&symbol) {
-        Some(chain) => {
-            if chain.is_valid() {
-                HttpResponse::Ok().body("Blockchain válida")
-            } else {
-                HttpResponse::Conflict().body("Blockchain corrompida")
-            }
-        }
-        None => HttpResponse::NotFound().body("Blockchain não encontrada"),
+
+    match is_blockchain_valid(&settings.binance.account, &symbol) {
+        Some(true) => Ok(HttpResponse::Ok().json(ChainValidityResponse { symbol, valid: true })),
+        Some(false) => Err(AppError::Conflict(format!("Chain for {} is corrupted", symbol))),
+        None => Err(AppError::NotFound(format!("No chain found for {}", symbol))),
     }
 }
 
-#[get("/trades/monitor")]
-pub async fn get_trades_monitor(query: web::Query<std::collections::HashMap<String, String>>) -> impl Responder {
-    let settings = Settings::load();
-
-    let trades = spy_cryptos(
-        &settings.binance.base_url,
-        &settings.binance.interval,
-        settings.binance.limit,
-        settings.cryptos.clone(),
+#[utoipa::path(
+    tag = "chains",
+    responses(
+        (status = 200, description = "Ed25519 key that signs blocks and anchors", body = PublicKeyResponse),
+        (status = 503, description = "No signing key loaded", body = ErrorResponse)
     )
-    .await;
+)]
+#[get("/trades/public-key")]
+pub async fn get_public_key(_caller: Viewer) -> ApiResult {
+    let key = public_key().ok_or_else(|| AppError::Config("No signing key loaded".into()))?;
+    Ok(HttpResponse::Ok().json(PublicKeyResponse {
+        algorithm: "ed25519".to_string(),
+        public_key: hex::encode(key.to_bytes()),
+    }))
+}
 
-    let response = monitor_cryptos(&trades, &settings);
-
-    match query.get("format").map(|f| f.as_str()) {
-        Some("text") => {
-            let mut buffer = String::new();
-            writeln!(&mut buffer, "[{}] - Criptos monitoradas:", response.timestamp).unwrap();
-
-            buffer.push_str("\n(tabela disponível apenas no terminal)\n");
-            writeln!(
-                &mut buffer,
-                "\nDistribuicao por zona: {}",
-                response.zone_distribution
-                    .iter()
-                    .map(|z| format!("{}: {}", z.zone, z.count))
-                    .collect::<Vec<_>>()
-                    .join(" | ")
+#[utoipa::path(
+    tag = "market",
+    params(MonitorQuery),
+    responses(
+        (status = 200, description = "Zone and performance of the configured symbols; `?format=` picks text, CSV or Markdown", content(
+            (TradeMonitorPage = "application/json"),
+            (String = "text/plain"),
+            (String = "text/csv"),
+            (String = "text/markdown")
+        )),
+        (status = 400, description = "Unknown format, sort column, bias or order", body = ErrorResponse),
+        (status = 404, description = "Unknown account", body = ErrorResponse)
+    )
+)]
+#[get("/trades/monitor")]
+pub async fn get_trades_monitor(query: web::Query<MonitorQuery>, _caller: Viewer) -> ApiResult {
+    let settings = account_settings(query.account.as_deref())?;
+    let account = &settings.binance.account;
+
+    // Before the scheduler's first cycle there is no snapshot, so scan once on demand.
+    let snapshot = match last_snapshot(account).filter(|_| !query.refresh) {
+        Some(snapshot) => snapshot,
+        None => {
+            let trades = spy_cryptos(
+                account,
+                &settings.binance.base_url,
+                &settings.binance.future_url,
+                &settings.binance.interval,
+                settings.binance.limit,
+                settings.cryptos.clone(),
             )
-            .unwrap();
-
-            HttpResponse::Ok()
-                .content_type("text/plain; charset=utf-8")
-                .body(buffer)
+            .await;
+            let snapshot = monitor_cryptos(&trades, &settings);
+            store_snapshot(account, snapshot.clone());
+            snapshot
         }
-        _ => HttpResponse::Ok().json(response),
+    };
+
+    let response = page_snapshot(snapshot, &query)?;
+    let format = query.format.as_deref().unwrap_or("json");
+    if format == "json" {
+        return Ok(HttpResponse::Ok().json(response));
+    }
+
+    let rendered = render(
+        format,
+        &response.trades,
+        &response.timestamp,
+        &response.zone_distribution,
+        query.details.unwrap_or(settings.show_details_monitor),
+    )?;
+
+    let mut builder = HttpResponse::Ok();
+    builder.content_type(rendered.content_type);
+    if query.download {
+        builder.insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"monitor-{}.{}\"", account, rendered.extension),
+        ));
     }
+    Ok(builder.body(rendered.body))
 }
 
+#[utoipa::path(
+    tag = "market",
+    responses(
+        (status = 200, description = "Assistant answer", body = AssistantReply),
+        (status = 502, description = "OpenAI error", body = ErrorResponse),
+        (status = 503, description = "OpenAI key missing", body = ErrorResponse)
+    )
+)]
 #[post("/monitors/assistant")]
-pub async fn post_monitor_assistant() -> impl Responder {
-    match send_to_assistant().await {
-        Ok(resposta) => {
-            println!("Resposta do ChatGPT:\n{}", resposta.content);
-            HttpResponse::Ok().json(serde_json::json!({
-                "response": resposta.content
-            }))
-        }
-        Err(err) => {
-            eprintln!("Erro ao chamar ChatGPT: {}", err);
-            HttpResponse::InternalServerError().body("Erro ao chamar ChatGPT.")
-        }
+pub async fn post_monitor_assistant(_caller: Operator) -> ApiResult {
+    let resposta = send_to_assistant().await?;
+    println!("Resposta do ChatGPT:\n{}", resposta.content);
+    Ok(HttpResponse::Ok().json(AssistantReply {
+        response: resposta.content,
+    }))
+}
+
+#[utoipa::path(
+    tag = "settings",
+    responses((status = 200, description = "Effective settings with secrets redacted", body = Object))
+)]
+#[get("/settings")]
+pub async fn get_settings(_caller: Viewer) -> impl Responder {
+    HttpResponse::Ok().json(redacted(&Settings::current()))
+}
+
+#[utoipa::path(
+    tag = "settings",
+    request_body(content = Object, description = "Partial settings merged into the live ones"),
+    responses(
+        (status = 200, description = "Applied changes and warnings", body = SettingsUpdateResponse),
+        (status = 400, description = "Rejected; `details` lists the issues", body = ErrorResponse)
+    )
+)]
+#[put("/settings")]
+pub async fn put_settings(req: web::Json<serde_json::Value>, _caller: Trader) -> ApiResult {
+    match apply_patch(&req) {
+        Ok(response) => Ok(HttpResponse::Ok().json(response)),
+        Err(issues) => Err(AppError::Validation(
+            "Settings rejected".into(),
+            serde_json::to_value(issues).ok(),
+        )),
     }
 }
 
+#[utoipa::path(
+    tag = "events",
+    params(EventsQuery),
+    responses(
+        (status = 200, description = "Server-Sent Events stream; each `data` line is one event", content_type = "text/event-stream", body = Event),
+        (status = 400, description = "Unknown topic", body = ErrorResponse),
+        (status = 404, description = "Unknown account", body = ErrorResponse)
+    )
+)]
+#[get("/events")]
+pub async fn get_events(query: web::Query<EventsQuery>, _caller: Viewer) -> ApiResult {
+    let topics = Topic::parse_list(query.topics.as_deref())?;
+    let account = match query.account.as_deref() {
+        Some(name) => Some(account_settings(Some(name))?.binance.account),
+        None => None,
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(subscribe(topics, account)))
+}
+
+#[utoipa::path(
+    tag = "history",
+    params(HistoryQuery),
+    responses(
+        (status = 200, description = "Zone distribution of each stored snapshot, oldest first", body = [ZoneDistributionPoint]),
+        (status = 404, description = "Unknown account", body = ErrorResponse)
+    )
+)]
+#[get("/history/zones")]
+pub async fn get_history_zones(query: web::Query<HistoryQuery>, _caller: Viewer) -> ApiResult {
+    let records = history_records(&query).await?;
+    Ok(HttpResponse::Ok().json(zone_distribution_history(&records)))
+}
+
+#[utoipa::path(
+    tag = "history",
+    params(HistoryQuery),
+    responses(
+        (status = 200, description = "Share of the universe in Z1 and Z7 per snapshot, oldest first", body = [BreadthPoint]),
+        (status = 404, description = "Unknown account", body = ErrorResponse)
+    )
+)]
+#[get("/history/breadth")]
+pub async fn get_history_breadth(query: web::Query<HistoryQuery>, _caller: Viewer) -> ApiResult {
+    let records = history_records(&query).await?;
+    Ok(HttpResponse::Ok().json(breadth_history(&records)))
+}
+
+#[utoipa::path(
+    tag = "history",
+    params(("symbol" = String, Path, description = "Symbol, e.g. BTCUSDT"), HistoryQuery),
+    responses(
+        (status = 200, description = "Zone and metrics of the symbol per snapshot, oldest first", body = [SymbolHistoryPoint]),
+        (status = 404, description = "Unknown account", body = ErrorResponse)
+    )
+)]
+#[get("/history/symbols/{symbol}")]
+pub async fn get_history_symbol(
+    path: web::Path<String>,
+    query: web::Query<HistoryQuery>,
+    _caller: Viewer,
+) -> ApiResult {
+    let records = history_records(&query).await?;
+    Ok(HttpResponse::Ok().json(symbol_history(records, &path.into_inner())))
+}
+
+// A window of days can be many files, so they are read off the worker threads.
+async fn history_records(query: &HistoryQuery) -> Result<Vec<MonitorSnapshotRecord>, AppError> {
+    let settings = account_settings(query.account.as_deref())?;
+    let account = settings.binance.account.clone();
+    let (from, to) = (query.from, query.to);
+    web::block(move || load_snapshots(&account, from, to))
+        .await
+        .map_err(|e| AppError::Internal(format!("Error reading history: {}", e)))
+}
@@ -0,0 +1,63 @@
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+const AUDIT_FILE: &str = "data/audit.log";
+
+// A mutating call as it was answered: who made it, what it targeted and how it ended.
+#[derive(Debug, Serialize)]
+pub struct AuditCall<'a> {
+    pub caller: &'a str,
+    pub role: String,
+    pub method: &'a str,
+    pub path: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub side: Option<String>,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct AuditEntry<'a> {
+    timestamp: String,
+    #[serde(flatten)]
+    call: AuditCall<'a>,
+}
+
+pub fn record_call(call: AuditCall) {
+    let entry = AuditEntry {
+        timestamp: chrono::Local::now().to_rfc3339(),
+        call,
+    };
+
+    let line = match serde_json::to_string(&entry) {
+        Ok(line) => line,
+        Err(e) => {
+            eprintln!("Erro ao serializar auditoria: {}", e);
+            return;
+        }
+    };
+
+    println!("[AUDIT] {}", line);
+
+    if let Some(dir) = Path::new(AUDIT_FILE).parent()
+        && let Err(e) = fs::create_dir_all(dir)
+    {
+        eprintln!("Erro ao criar diretorio de auditoria: {}", e);
+        return;
+    }
+
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(AUDIT_FILE)
+        .and_then(|mut file| writeln!(file, "{}", line));
+
+    if let Err(e) = written {
+        eprintln!("Erro ao gravar auditoria: {}", e);
+    }
+}
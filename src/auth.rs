@@ -0,0 +1,278 @@
+use crate::audit::{record_call, AuditCall};
+use crate::config::{ApiClient, AuthSettings, Role, Settings};
+use actix_web::body::MessageBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::web::{Bytes, BytesMut};
+use crate::error::AppError;
+use actix_web::{FromRequest, HttpMessage, HttpRequest};
+use futures::future::{ready, Ready};
+use futures::StreamExt;
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const CLIENT_HEADER: &str = "X-RKD-Client";
+pub const TIMESTAMP_HEADER: &str = "X-RKD-Timestamp";
+pub const NONCE_HEADER: &str = "X-RKD-Nonce";
+pub const SIGNATURE_HEADER: &str = "X-RKD-Signature";
+
+// Larger bodies are refused before they are buffered for the signature check.
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+const MAX_NONCE_LEN: usize = 64;
+
+// Nonces of signed requests by client, with the timestamp they came with. A nonce only has to be
+// remembered while its timestamp is inside the clock-skew window; after that the timestamp check
+// refuses the replay on its own.
+static SEEN_NONCES: Lazy<Mutex<HashMap<(String, String), u64>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// SHA-256 of the request body as hex, which signed requests cover.
+#[derive(Clone)]
+struct BodyDigest(String);
+
+// Where `authorize` leaves the caller for the audit entry written after the handler.
+#[derive(Clone, Default)]
+struct AuditSlot(Rc<RefCell<Option<Caller>>>);
+
+#[derive(Debug, Clone)]
+pub struct Caller {
+    pub name: String,
+    pub role: Role,
+}
+
+pub trait RequiredRole {
+    const ROLE: Role;
+}
+
+pub struct ViewerRole;
+pub struct OperatorRole;
+pub struct TraderRole;
+
+impl RequiredRole for ViewerRole {
+    const ROLE: Role = Role::Viewer;
+}
+
+impl RequiredRole for OperatorRole {
+    const ROLE: Role = Role::Operator;
+}
+
+impl RequiredRole for TraderRole {
+    const ROLE: Role = Role::Trader;
+}
+
+pub struct Authorized<R: RequiredRole> {
+    pub caller: Caller,
+    _role: PhantomData<R>,
+}
+
+pub type Viewer = Authorized<ViewerRole>;
+pub type Operator = Authorized<OperatorRole>;
+pub type Trader = Authorized<TraderRole>;
+
+impl<R: RequiredRole> FromRequest for Authorized<R> {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let settings = Settings::current();
+        ready(authorize(req, &settings.auth, R::ROLE).map(|caller| Authorized {
+            caller,
+            _role: PhantomData,
+        }))
+    }
+}
+
+fn authorize(req: &HttpRequest, auth: &AuthSettings, required: Role) -> Result<Caller, AppError> {
+    let caller = if auth.enabled {
+        authenticate(req, auth)?
+    } else {
+        Caller {
+            name: "anonymous".to_string(),
+            role: Role::Trader,
+        }
+    };
+
+    if let Some(slot) = req.extensions().get::<AuditSlot>() {
+        *slot.0.borrow_mut() = Some(caller.clone());
+    }
+
+    if caller.role < required {
+        return Err(AppError::Forbidden(format!(
+            "Role {:?} is not allowed to call this endpoint (requires {:?})",
+            caller.role, required
+        )));
+    }
+
+    Ok(caller)
+}
+
+// Buffers the body so signed requests can be checked against it, then writes the audit entry of
+// a mutating call once its handler has answered.
+pub async fn inspect_request(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let body = read_body(req.take_payload()).await?;
+    req.extensions_mut()
+        .insert(BodyDigest(hex::encode(Sha256::digest(&body))));
+
+    let audited = req.method() != Method::GET;
+    let (symbol, side) = if audited { order_fields(&body) } else { (None, None) };
+    let method = req.method().clone();
+    let path = req.uri().path_and_query().map(|p| p.to_string()).unwrap_or_default();
+    let slot = AuditSlot::default();
+    req.extensions_mut().insert(slot.clone());
+    req.set_payload(Payload::from(body));
+
+    let result = next.call(req).await;
+
+    let caller = slot.0.borrow_mut().take();
+    if audited && let Some(caller) = caller {
+        let (status, error) = match &result {
+            Ok(response) => (
+                response.status().as_u16(),
+                response.response().error().map(|e| e.to_string()),
+            ),
+            Err(e) => (e.as_response_error().status_code().as_u16(), Some(e.to_string())),
+        };
+        record_call(AuditCall {
+            caller: &caller.name,
+            role: format!("{:?}", caller.role).to_lowercase(),
+            method: method.as_str(),
+            path: &path,
+            symbol,
+            side,
+            status,
+            error,
+        });
+    }
+
+    result
+}
+
+async fn read_body(mut payload: Payload) -> Result<Bytes, AppError> {
+    let mut body = BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|e| AppError::validation(format!("Invalid request body: {}", e)))?;
+        if body.len() + chunk.len() > MAX_BODY_BYTES {
+            return Err(AppError::validation("Request body too large"));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body.freeze())
+}
+
+// The symbol and side of an order or leverage call, when its body names them.
+fn order_fields(body: &[u8]) -> (Option<String>, Option<String>) {
+    let Ok(value) = serde_json::from_slice::<serde_json::Value>(body) else {
+        return (None, None);
+    };
+    let field = |name: &str| value.get(name).and_then(|v| v.as_str()).map(|v| v.to_uppercase());
+    (field("symbol"), field("side"))
+}
+
+fn authenticate(req: &HttpRequest, auth: &AuthSettings) -> Result<Caller, AppError> {
+    let headers = req.headers();
+
+    if let Some(token) = headers
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+    {
+        return auth
+            .clients
+            .iter()
+            .find(|c| c.token.as_deref().is_some_and(|t| tokens_match(t, token)))
+            .map(client_to_caller)
+            .ok_or_else(|| AppError::Unauthorized("Invalid API token".into()));
+    }
+
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    let (Some(name), Some(timestamp), Some(nonce), Some(signature)) = (
+        header(CLIENT_HEADER),
+        header(TIMESTAMP_HEADER),
+        header(NONCE_HEADER),
+        header(SIGNATURE_HEADER),
+    ) else {
+        return Err(AppError::Unauthorized("Missing credentials".into()));
+    };
+
+    let client = auth
+        .clients
+        .iter()
+        .find(|c| c.name == name)
+        .ok_or_else(|| AppError::Unauthorized("Unknown client".into()))?;
+    let secret = client
+        .secret
+        .as_deref()
+        .ok_or_else(|| AppError::Unauthorized("Client has no signing secret".into()))?;
+
+    let sent_at = timestamp
+        .parse::<u64>()
+        .map_err(|_| AppError::Unauthorized("Invalid timestamp".into()))?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    if now.abs_diff(sent_at) > auth.max_clock_skew_secs {
+        return Err(AppError::Unauthorized("Request timestamp outside the allowed window".into()));
+    }
+
+    if nonce.is_empty()
+        || nonce.len() > MAX_NONCE_LEN
+        || !nonce.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+    {
+        return Err(AppError::Unauthorized("Invalid nonce".into()));
+    }
+
+    let body_digest = req
+        .extensions()
+        .get::<BodyDigest>()
+        .map(|d| d.0.clone())
+        .ok_or_else(|| AppError::Internal("Request body was not captured for the signature check".into()))?;
+
+    let signature = hex::decode(signature).map_err(|_| AppError::Unauthorized("Invalid signature".into()))?;
+    let path = req.uri().path_and_query().map(|p| p.as_str()).unwrap_or("");
+    let message = format!("{}:{}:{}:{}:{}", timestamp, nonce, req.method().as_str(), path, body_digest);
+
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(message.as_bytes());
+    mac.verify_slice(&signature)
+        .map_err(|_| AppError::Unauthorized("Invalid signature".into()))?;
+
+    // Only a correctly signed request claims its nonce, so forged ones can't burn a client's nonces.
+    if !claim_nonce(name, nonce, sent_at, now, auth.max_clock_skew_secs) {
+        return Err(AppError::Unauthorized("Nonce already used".into()));
+    }
+
+    Ok(client_to_caller(client))
+}
+
+fn claim_nonce(client: &str, nonce: &str, sent_at: u64, now: u64, window: u64) -> bool {
+    let mut seen = SEEN_NONCES.lock().unwrap();
+    seen.retain(|_, at| now.abs_diff(*at) <= window);
+    seen.insert((client.to_string(), nonce.to_string()), sent_at)
+        .is_none()
+}
+
+// Compares digests so the comparison time does not depend on how many leading bytes match.
+fn tokens_match(expected: &str, provided: &str) -> bool {
+    Sha256::digest(expected.as_bytes()) == Sha256::digest(provided.as_bytes())
+}
+
+fn client_to_caller(client: &ApiClient) -> Caller {
+    Caller {
+        name: client.name.clone(),
+        role: client.role,
+    }
+}
@@ -0,0 +1,153 @@
+use crate::binance::get_candlesticks;
+use crate::config::Settings;
+use crate::decide::{planned_action, Action};
+use crate::dto::{Bias, Trade, TradeStatus};
+use crate::error::AppError;
+use crate::trade::build_trade;
+use serde::Serialize;
+
+// generate_trade needs 71 candles of offset plus a 200-candle window.
+const WINDOW: usize = 271;
+const REFERENCE_SYMBOL: &str = "BTCUSDT";
+
+#[derive(Debug, Serialize)]
+pub struct BacktestStep {
+    pub close_time: u64,
+    pub price: f64,
+    pub bias: Bias,
+    pub status: Option<TradeStatus>,
+    pub action: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BacktestPosition {
+    pub side: String,
+    pub entry_time: u64,
+    pub entry_price: f64,
+    pub exit_time: u64,
+    pub exit_price: f64,
+    // Price change in the position's favour, without leverage, fees or funding.
+    pub return_pct: f64,
+    // False for a position still open on the last candle, valued at its close.
+    pub closed: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BacktestReport {
+    pub symbol: String,
+    pub interval: String,
+    pub candles: usize,
+    pub steps: Vec<BacktestStep>,
+    pub positions: Vec<BacktestPosition>,
+    pub total_return_pct: f64,
+}
+
+struct Open {
+    side: &'static str,
+    time: u64,
+    price: f64,
+}
+
+impl Open {
+    fn close(self, time: u64, price: f64, closed: bool) -> BacktestPosition {
+        let change = (price / self.price - 1.0) * 100.0;
+        BacktestPosition {
+            side: self.side.to_string(),
+            entry_time: self.time,
+            entry_price: self.price,
+            exit_time: time,
+            exit_price: price,
+            return_pct: if self.side == "BUY" { change } else { -change },
+            closed,
+        }
+    }
+}
+
+// Replays the strategy over the last `candles` candles: every window goes through the same
+// trade generation and status machine as the scheduler, and every status change through the
+// same rules as `decide`. The symbol is followed from the first candle, as if it had a chain.
+pub async fn run_backtest(settings: &Settings, symbol: &str, candles: u32) -> Result<BacktestReport, AppError> {
+    let binance = &settings.binance;
+    let series = get_candlesticks(&binance.base_url, symbol, &binance.interval, candles).await?;
+    let reference = get_candlesticks(&binance.base_url, REFERENCE_SYMBOL, &binance.interval, candles).await?;
+
+    let len = series.len().min(reference.len());
+    if len < WINDOW {
+        return Err(AppError::validation(format!(
+            "At least {} candles are needed, Binance returned {}",
+            WINDOW, len
+        )));
+    }
+    let series = &series[series.len() - len..];
+    let reference = &reference[reference.len() - len..];
+
+    let mut last: Option<Trade> = None;
+    let mut steps = Vec::new();
+    let mut positions = Vec::new();
+    let mut open: Option<Open> = None;
+
+    for end in WINDOW..=len {
+        let trade = build_trade(
+            symbol.to_string(),
+            series[end - WINDOW..end].to_vec(),
+            reference[end - WINDOW..end].to_vec(),
+            last.as_ref(),
+        );
+
+        // Like `TradeBlockchain::add_block`, only status changes become blocks.
+        if last.as_ref().is_some_and(|l| l.status == trade.status) {
+            continue;
+        }
+
+        let time = series[end - 1].close_time;
+        let price = trade.current_price.parse::<f64>().unwrap_or(0.0);
+        let action = planned_action(&trade.bias, trade.status.as_ref());
+
+        match action {
+            Action::Open(side) => {
+                if open.as_ref().is_some_and(|o| o.side != side) {
+                    positions.push(open.take().unwrap().close(time, price, true));
+                }
+                if open.is_none() {
+                    open = Some(Open { side, time, price });
+                }
+            }
+            Action::Close { .. } => {
+                if let Some(position) = open.take() {
+                    positions.push(position.close(time, price, true));
+                }
+            }
+            Action::TargetLeverage | Action::Hold => {}
+        }
+
+        steps.push(BacktestStep {
+            close_time: time,
+            price,
+            bias: trade.bias.clone(),
+            status: trade.status.clone(),
+            action: format!("{:?}", action),
+        });
+        last = Some(trade);
+    }
+
+    if let Some(position) = open {
+        let final_candle = &series[len - 1];
+        let price = final_candle.close_price.parse::<f64>().unwrap_or(position.price);
+        positions.push(position.close(final_candle.close_time, price, false));
+    }
+
+    let total_return_pct = positions
+        .iter()
+        .fold(1.0, |acc, p| acc * (1.0 + p.return_pct / 100.0))
+        * 100.0
+        - 100.0;
+
+    Ok(BacktestReport {
+        symbol: symbol.to_string(),
+        interval: binance.interval.clone(),
+        candles: len,
+        steps,
+        positions,
+        total_return_pct,
+    })
+}
@@ -6,7 +6,8 @@ use reqwest::{
 use sha2::Sha256;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::credential::get_credentials;
+use crate::credential::binance_credentials;
+use crate::error::AppError;
 use crate::dto::BalanceResponse;
 use crate::config::BinanceSettings;
 
@@ -26,8 +27,8 @@ fn sign_query(query: &str, secret: &str) -> String {
 
 pub async fn get_futures_balance(
     settings: &BinanceSettings,
-) -> Result<Vec<BalanceResponse>, Box<dyn std::error::Error>> {
-    let credentials = get_credentials();
+) -> Result<Vec<BalanceResponse>, AppError> {
+    let credentials = binance_credentials(&settings.account)?;
     let api_key = &credentials.key;
     let secret_key = &credentials.secret;
 
@@ -59,6 +60,6 @@ pub async fn get_futures_balance(
     } else {
         let error_text = res.text().await?;
         println!("Error checking balance: {}", error_text);
-        Err("Binance response error".into())
+        Err(AppError::Exchange(format!("Binance error: {}", error_text)))
     }
 }
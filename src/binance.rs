@@ -1,5 +1,11 @@
 use crate::config::BinanceSettings;
-use crate::dto::{Candlestick, ExchangeInfoResponse, LotSizeFilter, LotSizeInfo};
+use crate::credential::binance_credentials;
+use crate::error::AppError;
+use crate::dto::{
+    Candlestick, ExchangeInfoResponse, FundingRate, FuturesAccount, IncomeRecord, LotSizeFilter,
+    LotSizeInfo, PositionRisk,
+};
+use std::collections::HashMap;
 use reqwest::Client;
 use serde_json::Value;
 use hmac::{Hmac, Mac};
@@ -14,7 +20,7 @@ pub async fn get_candlesticks(
     symbol: &str,
     interval: &str,
     limit: u32,
-) -> Result<Vec<Candlestick>, String> {
+) -> Result<Vec<Candlestick>, AppError> {
     let url = format!("{}/uiKlines", base_url);
 
     let params = [
@@ -30,12 +36,12 @@ pub async fn get_candlesticks(
         .query(&params)
         .send()
         .await
-        .map_err(|e| format!("Erro na requisicao HTTP: {:?}", e))?;
+        .map_err(|e| AppError::Exchange(format!("Erro na requisicao HTTP: {:?}", e)))?;
 
     let raw_data = response
         .json::<Vec<Vec<Value>>>()
         .await
-        .map_err(|e| format!("Erro ao desserializar JSON da Binance: {:?}", e))?;
+        .map_err(|e| AppError::Exchange(format!("Erro ao desserializar JSON da Binance: {:?}", e)))?;
 
     let candlesticks: Vec<Candlestick> = raw_data
         .into_iter()
@@ -67,7 +73,7 @@ pub async fn get_candlesticks(
 pub async fn get_current_price(
     settings: &BinanceSettings,
     symbol: &str,
-) -> Result<f64, String> {
+) -> Result<f64, AppError> {
     let url = format!("{}/ticker/price?symbol={}", settings.future_url, symbol);
 
     let client = Client::new();
@@ -76,32 +82,32 @@ pub async fn get_current_price(
         .get(&url)
         .send()
         .await
-        .map_err(|e| format!("Erro ao obter preco atual: {:?}", e))?;
+        .map_err(|e| AppError::Exchange(format!("Erro ao obter preco atual: {:?}", e)))?;
 
     if res.status().is_success() {
         let data: Value = res
             .json()
             .await
-            .map_err(|e| format!("Erro ao interpretar resposta do preco: {:?}", e))?;
+            .map_err(|e| AppError::Exchange(format!("Erro ao interpretar resposta do preco: {:?}", e)))?;
 
         data["price"]
             .as_str()
-            .ok_or("Campo 'price' ausente".to_string())?
+            .ok_or_else(|| AppError::Exchange("Campo 'price' ausente".into()))?
             .parse::<f64>()
-            .map_err(|_| "Erro ao converter preco para f64".to_string())
+            .map_err(|_| AppError::Exchange("Erro ao converter preco para f64".into()))
     } else {
         let err = res
             .text()
             .await
             .unwrap_or_else(|_| "Erro desconhecido".to_string());
-        Err(format!("Erro ao buscar preco: {}", err))
+        Err(AppError::Exchange(format!("Erro ao buscar preco: {}", err)))
     }
 }
 
 pub async fn get_lot_size_info(
     settings: &BinanceSettings,
     symbol: &str,
-) -> Result<LotSizeInfo, String> {
+) -> Result<LotSizeInfo, AppError> {
     let url = format!("{}/exchangeInfo?symbol={}", settings.future_url, symbol);
     let client = Client::new();
 
@@ -109,31 +115,36 @@ pub async fn get_lot_size_info(
         .get(&url)
         .send()
         .await
-        .map_err(|e| format!("Erro ao obter exchangeInfo: {:?}", e))?;
+        .map_err(|e| AppError::Exchange(format!("Erro ao obter exchangeInfo: {:?}", e)))?;
 
     if !res.status().is_success() {
         let err = res
             .text()
             .await
             .unwrap_or_else(|_| "Erro desconhecido".to_string());
-        return Err(format!("Erro da Binance: {}", err));
+        return Err(AppError::Exchange(format!("Erro da Binance: {}", err)));
     }
 
     let data: ExchangeInfoResponse = res
         .json()
         .await
-        .map_err(|e| format!("Erro ao interpretar exchangeInfo: {:?}", e))?;
+        .map_err(|e| AppError::Exchange(format!("Erro ao interpretar exchangeInfo: {:?}", e)))?;
 
-    for filter in &data.symbols.first().ok_or("Simbolo nao encontrado")?.filters {
+    let symbol_info = data
+        .symbols
+        .first()
+        .ok_or_else(|| AppError::NotFound(format!("Simbolo {} nao encontrado", symbol)))?;
+
+    for filter in &symbol_info.filters {
         if let LotSizeFilter::LotSize { step_size } = filter {
             return step_size
                 .parse::<f64>()
                 .map(|step| LotSizeInfo { step_size: step })
-                .map_err(|_| "Erro ao converter stepSize para f64".to_string());
+                .map_err(|_| AppError::Exchange("Erro ao converter stepSize para f64".into()));
         }
     }
 
-    Err("Filtro LOT_SIZE nao encontrado".to_string())
+    Err(AppError::Exchange("Filtro LOT_SIZE nao encontrado".into()))
 }
 
 pub async fn get_unrealized_profit(
@@ -141,7 +152,7 @@ pub async fn get_unrealized_profit(
     symbol: &str,
     api_key: &str,
     secret: &str,
-) -> Result<Option<f64>, String> {
+) -> Result<Option<f64>, AppError> {
     let ts = now_ms();
     let query = format!("timestamp={}", ts);
     let sig = sign(&query, secret);
@@ -149,14 +160,14 @@ pub async fn get_unrealized_profit(
 
     let mut headers = HeaderMap::new();
     headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-    headers.insert("X-MBX-APIKEY", HeaderValue::from_str(api_key).unwrap());
+    headers.insert("X-MBX-APIKEY", HeaderValue::from_str(api_key)?);
 
     let client = Client::new();
     let res = client.get(&url).headers(headers).send().await
-        .map_err(|e| format!("Erro HTTP: {:?}", e))?;
+        .map_err(|e| AppError::Exchange(format!("Erro HTTP: {:?}", e)))?;
 
     let positions: Vec<serde_json::Value> = res.json().await
-        .map_err(|e| format!("Erro ao interpretar JSON: {:?}", e))?;
+        .map_err(|e| AppError::Exchange(format!("Erro ao interpretar JSON: {:?}", e)))?;
 
     for position in positions {
         if position["symbol"] == symbol {
@@ -171,6 +182,113 @@ pub async fn get_unrealized_profit(
     Ok(None)
 }
 
+pub async fn get_position_risk(
+    binance: &BinanceSettings,
+    symbol: Option<&str>,
+) -> Result<Vec<PositionRisk>, AppError> {
+    let credentials = binance_credentials(&binance.account)?;
+    let ts = now_ms();
+    let query = match symbol {
+        Some(symbol) => format!("symbol={}&recvWindow=10000&timestamp={}", symbol, ts),
+        None => format!("recvWindow=10000&timestamp={}", ts),
+    };
+    let sig = sign(&query, &credentials.secret);
+    let url = format!("{}/positionRisk?{}&signature={}", binance.future_url_v2, query, sig);
+
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    headers.insert("X-MBX-APIKEY", HeaderValue::from_str(&credentials.key)?);
+
+    let client = Client::new();
+    let res = client.get(&url).headers(headers).send().await
+        .map_err(|e| AppError::Exchange(format!("Erro HTTP: {:?}", e)))?;
+
+    if !res.status().is_success() {
+        let err = res.text().await.unwrap_or_else(|_| "Erro desconhecido".to_string());
+        return Err(AppError::Exchange(format!("Erro da Binance: {}", err)));
+    }
+
+    res.json::<Vec<PositionRisk>>().await
+        .map_err(|e| AppError::Exchange(format!("Erro ao interpretar positionRisk: {:?}", e)))
+}
+
+pub async fn get_futures_account(binance: &BinanceSettings) -> Result<FuturesAccount, AppError> {
+    let credentials = binance_credentials(&binance.account)?;
+    let query = format!("recvWindow=10000&timestamp={}", now_ms());
+    let sig = sign(&query, &credentials.secret);
+    let url = format!("{}/account?{}&signature={}", binance.future_url_v2, query, sig);
+
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    headers.insert("X-MBX-APIKEY", HeaderValue::from_str(&credentials.key)?);
+
+    let client = Client::new();
+    let res = client.get(&url).headers(headers).send().await
+        .map_err(|e| AppError::Exchange(format!("Erro HTTP: {:?}", e)))?;
+
+    if !res.status().is_success() {
+        let err = res.text().await.unwrap_or_else(|_| "Erro desconhecido".to_string());
+        return Err(AppError::Exchange(format!("Erro da Binance: {}", err)));
+    }
+
+    res.json::<FuturesAccount>().await
+        .map_err(|e| AppError::Exchange(format!("Erro ao interpretar account: {:?}", e)))
+}
+
+pub async fn get_funding_rates(future_url: &str) -> Result<HashMap<String, FundingRate>, AppError> {
+    let url = format!("{}/premiumIndex", future_url);
+    let client = Client::new();
+
+    let res = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| AppError::Exchange(format!("Erro ao obter premiumIndex: {:?}", e)))?;
+
+    if !res.status().is_success() {
+        let err = res.text().await.unwrap_or_else(|_| "Erro desconhecido".to_string());
+        return Err(AppError::Exchange(format!("Erro da Binance: {}", err)));
+    }
+
+    let rates: Vec<FundingRate> = res
+        .json()
+        .await
+        .map_err(|e| AppError::Exchange(format!("Erro ao interpretar premiumIndex: {:?}", e)))?;
+
+    Ok(rates.into_iter().map(|r| (r.symbol.clone(), r)).collect())
+}
+
+pub async fn get_funding_income(
+    binance: &BinanceSettings,
+    symbol: &str,
+    start_time: u64,
+) -> Result<Vec<IncomeRecord>, AppError> {
+    let credentials = binance_credentials(&binance.account)?;
+    let ts = now_ms();
+    let query = format!(
+        "symbol={}&incomeType=FUNDING_FEE&startTime={}&limit=1000&recvWindow=10000&timestamp={}",
+        symbol, start_time, ts
+    );
+    let sig = sign(&query, &credentials.secret);
+    let url = format!("{}/income?{}&signature={}", binance.future_url, query, sig);
+
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    headers.insert("X-MBX-APIKEY", HeaderValue::from_str(&credentials.key)?);
+
+    let client = Client::new();
+    let res = client.get(&url).headers(headers).send().await
+        .map_err(|e| AppError::Exchange(format!("Erro HTTP: {:?}", e)))?;
+
+    if !res.status().is_success() {
+        let err = res.text().await.unwrap_or_else(|_| "Erro desconhecido".to_string());
+        return Err(AppError::Exchange(format!("Erro da Binance: {}", err)));
+    }
+
+    res.json::<Vec<IncomeRecord>>().await
+        .map_err(|e| AppError::Exchange(format!("Erro ao interpretar income: {:?}", e)))
+}
+
 fn now_ms() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -0,0 +1,317 @@
+use crate::blockchain::BlockEvent;
+use crate::dto::{InterventionKind, RiskIntervention, Trade};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+// Schema versions of the hashed block payload:
+// 0 - `trade` plus an optional `intervention`, each hashed as serde JSON (no `event`, no signature).
+// 1 - `event` hashed as serde JSON, so any field added to or moved in `Trade` changed the hash.
+// 2 - `event` in the explicit encoding below, which only a new version may change.
+// 3 - as 2, plus the outcome of a manual override and `migration` events.
+pub const LEGACY_TRADE_VERSION: u32 = 0;
+pub const JSON_EVENT_VERSION: u32 = 1;
+pub const CANONICAL_VERSION: u32 = 2;
+pub const CURRENT_VERSION: u32 = 3;
+
+// Fixed-order, length-prefixed binary fields: integers and floats big-endian (floats as IEEE 754
+// bits), strings as a u32 byte length and UTF-8, options as a 0/1 byte before the value.
+// A field added to `Trade` or an event stays out of the hash until a new version encodes it.
+struct Encoder {
+    version: u32,
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn f64(&mut self, value: f64) {
+        self.u64(value.to_bits());
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.bytes.push(value as u8);
+    }
+
+    fn str(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    fn option<T>(&mut self, value: Option<T>, encode: impl FnOnce(&mut Self, T)) {
+        match value {
+            Some(value) => {
+                self.bool(true);
+                encode(self, value);
+            }
+            None => self.bool(false),
+        }
+    }
+
+    // Unit enum variants go in by their serde name, which is what the API shows.
+    fn variant<T: Serialize>(&mut self, value: &T) {
+        let name = serde_json::to_value(value)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        self.str(&name);
+    }
+
+    fn trade(&mut self, trade: &Trade) {
+        self.str(&trade.symbol);
+        self.str(&trade.current_price);
+        self.str(&trade.cma);
+        self.str(&trade.oma);
+        self.variant(&trade.bias);
+        self.option(trade.status.as_ref(), |e, status| e.variant(status));
+        for zone in [
+            &trade.zone_max,
+            &trade.zone_7,
+            &trade.zone_6,
+            &trade.zone_5,
+            &trade.zone_4,
+            &trade.zone_3,
+            &trade.zone_2,
+            &trade.zone_1,
+            &trade.zone_min,
+        ] {
+            self.str(zone);
+        }
+        self.u64(trade.of as u64);
+        self.str(&trade.performance_24);
+        self.str(&trade.performance_btc_24);
+        self.str(&trade.amplitude_ma_200);
+        self.str(&trade.volume);
+        self.str(&trade.quote_asset_volume);
+        self.str(&trade.number_of_trades);
+        self.str(&trade.taker_buy_base_asset_volume);
+        self.str(&trade.taker_buy_quote_asset_volume);
+        self.str(&trade.funding_rate);
+        self.u64(trade.next_funding_time);
+    }
+
+    fn intervention(&mut self, intervention: &RiskIntervention) {
+        match &intervention.kind {
+            InterventionKind::ReduceLeverage { from, to } => {
+                self.str("reduce_leverage");
+                self.u32(*from);
+                self.u32(*to);
+            }
+            InterventionKind::ReducePosition { fraction, quantity } => {
+                self.str("reduce_position");
+                self.f64(*fraction);
+                self.str(quantity);
+            }
+        }
+        self.f64(intervention.mark_price);
+        self.option(intervention.liquidation_price, Self::f64);
+        self.option(intervention.distance_pct, Self::f64);
+        self.f64(intervention.margin_ratio);
+    }
+
+    fn event(&mut self, event: &BlockEvent) {
+        self.str(event.kind());
+        match event {
+            BlockEvent::StatusChange { trade } => self.trade(trade),
+            BlockEvent::OrderSent {
+                client_order_id,
+                side,
+                quantity,
+                reduce_only,
+            } => {
+                self.str(client_order_id);
+                self.str(side);
+                self.str(quantity);
+                self.bool(*reduce_only);
+            }
+            BlockEvent::OrderResult {
+                client_order_id,
+                state,
+                order_id,
+                executed_qty,
+                error,
+            } => {
+                self.str(client_order_id);
+                self.variant(state);
+                self.option(*order_id, Self::u64);
+                self.option(executed_qty.as_deref(), Self::str);
+                self.option(error.as_deref(), Self::str);
+            }
+            BlockEvent::LeverageChange { leverage } => self.u32(*leverage),
+            BlockEvent::PositionClosed {
+                client_order_id,
+                quantity,
+                mark_price,
+                realized_pnl,
+            } => {
+                self.str(client_order_id);
+                self.str(quantity);
+                self.f64(*mark_price);
+                self.f64(*realized_pnl);
+            }
+            BlockEvent::ManualOverride {
+                action,
+                caller,
+                success,
+                error,
+            } => {
+                self.str(action);
+                self.str(caller);
+                if self.version > CANONICAL_VERSION {
+                    self.option(*success, Self::bool);
+                    self.option(error.as_deref(), Self::str);
+                }
+            }
+            BlockEvent::RiskIntervention { intervention } => self.intervention(intervention),
+            BlockEvent::Migration {
+                previous_key,
+                public_key,
+            } => {
+                self.str(previous_key);
+                self.str(public_key);
+            }
+        }
+    }
+}
+
+fn canonical_hash(version: u32, index: u64, timestamp: u64, event: &BlockEvent, previous_hash: &str) -> String {
+    let mut encoder = Encoder {
+        version,
+        bytes: Vec::new(),
+    };
+    encoder.u32(version);
+    encoder.u64(index);
+    encoder.u64(timestamp);
+    encoder.str(previous_hash);
+    encoder.event(event);
+    hex::encode(Sha256::digest(&encoder.bytes))
+}
+
+// Hash of a block under the schema it was written with; None for versions this build can't
+// rebuild from a `BlockEvent` (0, whose blocks predate events, and anything newer than current).
+pub fn block_hash(version: u32, index: u64, timestamp: u64, event: &BlockEvent, previous_hash: &str) -> Option<String> {
+    match version {
+        JSON_EVENT_VERSION => Some(legacy_event_hash(
+            index,
+            timestamp,
+            &serde_json::to_value(event).ok()?,
+            previous_hash,
+        )),
+        CANONICAL_VERSION..=CURRENT_VERSION => Some(canonical_hash(version, index, timestamp, event, previous_hash)),
+        _ => None,
+    }
+}
+
+// Version 0 hashed the stored JSON of `trade` and `intervention` in field order, so it is
+// rebuilt from the exported values rather than from today's `Trade`.
+pub fn legacy_trade_hash(
+    index: u64,
+    timestamp: u64,
+    trade: &serde_json::Value,
+    intervention: Option<&serde_json::Value>,
+    previous_hash: &str,
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(index.to_be_bytes());
+    hasher.update(timestamp.to_be_bytes());
+    hasher.update(trade.to_string());
+    if let Some(intervention) = intervention {
+        hasher.update(intervention.to_string());
+    }
+    hasher.update(previous_hash.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+// Version 1 the same way, from the exported `event`. serde_json keeps the order of object
+// keys, so a value serializes exactly like the struct it came from.
+pub fn legacy_event_hash(index: u64, timestamp: u64, event: &serde_json::Value, previous_hash: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(index.to_be_bytes());
+    hasher.update(timestamp.to_be_bytes());
+    hasher.update(event.to_string());
+    hasher.update(previous_hash.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn status_change() -> BlockEvent {
+        let trade: Trade = serde_json::from_value(json!({
+            "symbol": "BTCUSDT",
+            "current_price": "100.5",
+            "cma": "99.0",
+            "oma": "98.0",
+            "bias": "Bullish",
+            "status": "InZone7",
+            "zone_max": "110",
+            "zone_7": "108",
+            "zone_6": "106",
+            "zone_5": "104",
+            "zone_4": "102",
+            "zone_3": "100",
+            "zone_2": "98",
+            "zone_1": "96",
+            "zone_min": "90",
+            "of": 5,
+            "performance_24": "1.5",
+            "performance_btc_24": "0.5",
+            "amplitude_ma_200": "3.0",
+            "volume": "1000",
+            "quote_asset_volume": "100000",
+            "number_of_trades": "42",
+            "taker_buy_base_asset_volume": "500",
+            "taker_buy_quote_asset_volume": "50000",
+            "funding_rate": "0.0001",
+            "next_funding_time": 1700000000000u64
+        }))
+        .unwrap();
+        BlockEvent::StatusChange { trade: Box::new(trade) }
+    }
+
+    fn manual_override(success: Option<bool>, error: Option<&str>) -> BlockEvent {
+        BlockEvent::ManualOverride {
+            action: "close".to_string(),
+            caller: "tui".to_string(),
+            success,
+            error: error.map(str::to_string),
+        }
+    }
+
+    // Computed outside this crate from the byte layout in the README; a change here means blocks
+    // already written as version 2 no longer validate.
+    #[test]
+    fn version_2_hash_is_fixed() {
+        assert_eq!(
+            block_hash(CANONICAL_VERSION, 7, 1700000000000, &status_change(), "prev").unwrap(),
+            "b71584380443a2ce116aa7591d336fe807395705f02ead0e5343cd8a43151d9c"
+        );
+    }
+
+    #[test]
+    fn only_version_3_hashes_the_manual_override_outcome() {
+        let hash = |version, event: &BlockEvent| block_hash(version, 1, 1, event, "prev").unwrap();
+        let bare = manual_override(None, None);
+        let failed = manual_override(Some(false), Some("Binance error"));
+
+        assert_eq!(hash(CANONICAL_VERSION, &bare), hash(CANONICAL_VERSION, &failed));
+        assert_ne!(hash(CURRENT_VERSION, &bare), hash(CURRENT_VERSION, &failed));
+        assert_ne!(
+            hash(CURRENT_VERSION, &failed),
+            hash(CURRENT_VERSION, &manual_override(Some(false), Some("another error")))
+        );
+    }
+
+    #[test]
+    fn unknown_versions_do_not_hash() {
+        assert!(block_hash(LEGACY_TRADE_VERSION, 0, 0, &status_change(), "0").is_none());
+        assert!(block_hash(CURRENT_VERSION + 1, 0, 0, &status_change(), "0").is_none());
+    }
+}
@@ -1,42 +1,147 @@
-﻿use crate::dto::Trade;
+﻿use crate::dto::{RiskIntervention, Trade};
 use crate::config::Settings;
+use crate::order_state::OrderState;
+use crate::signing::{public_key, sign, verify};
+use ed25519_dalek::VerifyingKey;
+use crate::events::{publish, EventPayload};
 use serde::{Serialize, Deserialize};
-use sha2::{Digest, Sha256};
+use crate::block_encoding::{block_hash, CURRENT_VERSION, JSON_EVENT_VERSION};
 use std::collections::HashMap;
 use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
 use once_cell::sync::Lazy;
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+// What a block records. Status changes drive the strategy; the other events log what was done
+// about them, so a chain reads as the full history of its symbol.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BlockEvent {
+    StatusChange {
+        trade: Box<Trade>,
+    },
+    OrderSent {
+        client_order_id: String,
+        side: String,
+        quantity: String,
+        reduce_only: bool,
+    },
+    OrderResult {
+        client_order_id: String,
+        state: OrderState,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        order_id: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        executed_qty: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    LeverageChange {
+        leverage: u32,
+    },
+    // Realized PnL is the position's unrealized profit just before the reduce-only order.
+    PositionClosed {
+        client_order_id: String,
+        quantity: String,
+        mark_price: f64,
+        realized_pnl: f64,
+    },
+    ManualOverride {
+        action: String,
+        caller: String,
+        // Whether the action went through; absent on blocks written before it was recorded.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        success: Option<bool>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    RiskIntervention {
+        intervention: RiskIntervention,
+    },
+    // Appended by `chain migrate`: the blocks before it carry `previous_key`'s signatures, this
+    // block and any after it `public_key`'s. Keys are hex Ed25519 public keys.
+    Migration {
+        previous_key: String,
+        public_key: String,
+    },
+}
+
+impl BlockEvent {
+    pub const KINDS: [&'static str; 8] = [
+        "status_change",
+        "order_sent",
+        "order_result",
+        "leverage_change",
+        "position_closed",
+        "manual_override",
+        "risk_intervention",
+        "migration",
+    ];
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BlockEvent::StatusChange { .. } => "status_change",
+            BlockEvent::OrderSent { .. } => "order_sent",
+            BlockEvent::OrderResult { .. } => "order_result",
+            BlockEvent::LeverageChange { .. } => "leverage_change",
+            BlockEvent::PositionClosed { .. } => "position_closed",
+            BlockEvent::ManualOverride { .. } => "manual_override",
+            BlockEvent::RiskIntervention { .. } => "risk_intervention",
+            BlockEvent::Migration { .. } => "migration",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct TradeBlock {
+    // Schema the hash was computed with, see `block_encoding`. Blocks from before it existed are version 1.
+    #[serde(default = "default_block_version")]
+    pub version: u32,
     pub index: u64,
     pub timestamp: u64,
-    pub trade: Trade,
+    pub event: BlockEvent,
     pub previous_hash: String,
     pub hash: String,
+    // Ed25519 signature of `hash` by the bot's key, hex-encoded.
+    pub signature: String,
+}
+
+fn default_block_version() -> u32 {
+    JSON_EVENT_VERSION
 }
 
 impl TradeBlock {
-    pub fn new(index: u64, trade: Trade, previous_hash: String) -> Self {
-        let timestamp = current_timestamp();
-        let hash = Self::calculate_hash(index, timestamp, &trade, &previous_hash);
+    pub fn new(index: u64, event: BlockEvent, previous_hash: String) -> Self {
+        Self::at(index, current_timestamp(), event, previous_hash)
+    }
+
+    // A block in the current schema, signed by this process, for a given time; used to rebuild chains.
+    pub fn at(index: u64, timestamp: u64, event: BlockEvent, previous_hash: String) -> Self {
+        let hash = block_hash(CURRENT_VERSION, index, timestamp, &event, &previous_hash)
+            .expect("current block version always hashes");
+        let signature = sign(hash.as_bytes());
         TradeBlock {
+            version: CURRENT_VERSION,
             index,
             timestamp,
-            trade,
+            event,
             previous_hash,
             hash,
+            signature,
         }
     }
 
-    pub fn calculate_hash(index: u64, timestamp: u64, trade: &Trade, previous_hash: &str) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(index.to_be_bytes());
-        hasher.update(timestamp.to_be_bytes());
-        hasher.update(serde_json::to_string(trade).unwrap());
-        hasher.update(previous_hash.as_bytes());
-        let result = hasher.finalize();
-        hex::encode(result)
+    // The hash this block should carry under its own schema version.
+    pub fn calculate_hash(&self) -> Option<String> {
+        block_hash(self.version, self.index, self.timestamp, &self.event, &self.previous_hash)
+    }
+
+    // The trade of a status change block; None for every other event.
+    pub fn trade(&self) -> Option<&Trade> {
+        match &self.event {
+            BlockEvent::StatusChange { trade } => Some(trade.as_ref()),
+            _ => None,
+        }
     }
 }
 
@@ -57,21 +162,18 @@ impl TradeBlockchain {
         Self { chain: vec![] }
     }
 
+    // Wraps blocks fetched from elsewhere, e.g. a server's API, so they can be checked with `is_valid`.
+    pub fn from_blocks(chain: Vec<TradeBlock>) -> Self {
+        Self { chain }
+    }
+
     pub fn add_block(&mut self, trade: Trade) -> bool {
-        if let Some(last_trade) = self.get_last_trade() {
-            if trade.status == last_trade.status {
-                return false;
-            }
+        if let Some(last_trade) = self.get_last_trade()
+            && trade.status == last_trade.status
+        {
+            return false;
         }
 
-        let index = self.chain.len() as u64;
-        let previous_hash = self.chain.last()
-            .map(|b| b.hash.clone())
-            .unwrap_or_else(|| "0".to_string());
-
-        let new_block = TradeBlock::new(index, trade.clone(), previous_hash);
-        self.chain.push(new_block);
-
         println!("\n\n\n[{}] - New block added - {} - Status: {:?}, Price: {}\n\n\n",
             chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
             trade.symbol,
@@ -79,30 +181,70 @@ impl TradeBlockchain {
             trade.current_price,
         );
 
+        self.push(BlockEvent::StatusChange { trade: Box::new(trade) });
+        true
+    }
+
+    // Events only annotate a chain, so they need a status change block to follow.
+    pub fn add_event(&mut self, event: BlockEvent) -> bool {
+        let Some(symbol) = self.get_last_trade().map(|t| t.symbol) else {
+            return false;
+        };
+
+        println!("\n[{}] - Event block added - {} - {}\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            symbol,
+            event.kind(),
+        );
+
+        self.push(event);
         true
     }
 
+    fn push(&mut self, event: BlockEvent) {
+        let index = self.chain.len() as u64;
+        let previous_hash = self.chain.last()
+            .map(|b| b.hash.clone())
+            .unwrap_or_else(|| "0".to_string());
+
+        self.chain.push(TradeBlock::new(index, event, previous_hash));
+    }
+
+    // The trade of the last status change; later event blocks don't carry one.
     pub fn get_last_trade(&self) -> Option<Trade> {
-        self.chain.last().map(|block| block.trade.clone())
+        self.chain.iter().rev().find_map(|block| block.trade().cloned())
     }
 
+    // Checked against the key this process signs with; a process without one can't vouch for any chain.
     pub fn is_valid(&self) -> bool {
-        for i in 1..self.chain.len() {
-            let current = &self.chain[i];
-            let previous = &self.chain[i - 1];
+        public_key().is_some_and(|key| self.is_valid_with(&key))
+    }
+
+    // Every block, whatever its event, must sit at its index, link to the previous hash, hash to
+    // its own content and carry `key`'s signature of that hash. A chain starts with a status
+    // change and sticks to one symbol.
+    pub fn is_valid_with(&self, key: &VerifyingKey) -> bool {
+        let Some(symbol) = self.chain.first().and_then(|b| b.trade()).map(|t| t.symbol.as_str()) else {
+            return self.chain.is_empty();
+        };
+
+        for (i, current) in self.chain.iter().enumerate() {
+            let previous_hash = match i {
+                0 => "0",
+                _ => self.chain[i - 1].hash.as_str(),
+            };
 
-            if current.previous_hash != previous.hash {
+            if current.index != i as u64 || current.previous_hash != previous_hash {
                 return false;
             }
 
-            let recalculated_hash = TradeBlock::calculate_hash(
-                current.index,
-                current.timestamp,
-                &current.trade,
-                &current.previous_hash,
-            );
+            if current.trade().is_some_and(|t| t.symbol != symbol) {
+                return false;
+            }
 
-            if current.hash != recalculated_hash {
+            if current.calculate_hash().as_ref() != Some(&current.hash)
+                || !verify(key, current.hash.as_bytes(), &current.signature)
+            {
                 return false;
             }
         }
@@ -114,35 +256,148 @@ impl TradeBlockchain {
     }
 }
 
-pub static BLOCKCHAIN: Lazy<Mutex<HashMap<String, TradeBlockchain>>> = Lazy::new(|| {
+// Chains are partitioned by account, then keyed by symbol.
+pub static BLOCKCHAIN: Lazy<Mutex<HashMap<String, HashMap<String, TradeBlockchain>>>> = Lazy::new(|| {
+    Mutex::new(HashMap::new())
+});
+
+#[derive(Debug, Clone)]
+pub struct ChainRecord {
+    pub symbol: String,
+    // When `remove_blockchain` closed the chain; None while it is open.
+    pub archived_at: Option<u64>,
+    pub blocks: Vec<TradeBlock>,
+}
+
+static ARCHIVED_CHAINS: Lazy<Mutex<HashMap<String, Vec<ChainRecord>>>> = Lazy::new(|| {
     Mutex::new(HashMap::new())
 });
 
-pub fn is_blockchain_limit_reached() -> bool {
-    let settings = Settings::load();
+fn limit_operations_for(account: &str) -> usize {
+    Settings::current()
+        .for_account(account)
+        .map(|s| s.limit_operations)
+        .unwrap_or(0)
+}
+
+pub fn is_blockchain_limit_reached(account: &str) -> bool {
     let map = BLOCKCHAIN.lock().unwrap();
-    map.len() >= settings.limit_operations
+    map.get(account).map(|chains| chains.len()).unwrap_or(0) >= limit_operations_for(account)
+}
+
+// Closed chains are archived instead of dropped so their blocks stay queryable.
+pub fn remove_blockchain(account: &str, symbol: &str) {
+    let removed = {
+        let mut map = BLOCKCHAIN.lock().unwrap();
+        map.get_mut(account).and_then(|chains| chains.remove(symbol))
+    };
+
+    if let Some(chain) = removed {
+        ARCHIVED_CHAINS
+            .lock()
+            .unwrap()
+            .entry(account.to_string())
+            .or_default()
+            .push(ChainRecord {
+                symbol: symbol.to_string(),
+                archived_at: Some(current_timestamp()),
+                blocks: chain.chain,
+            });
+    }
 }
 
-pub fn remove_blockchain(symbol: &str) {
+// Every chain of the account: archived ones in the order they were closed, then the open ones.
+pub fn get_all_chains_for(account: &str) -> Vec<ChainRecord> {
+    let mut chains = ARCHIVED_CHAINS
+        .lock()
+        .unwrap()
+        .get(account)
+        .cloned()
+        .unwrap_or_default();
+
+    let map = BLOCKCHAIN.lock().unwrap();
+    if let Some(open) = map.get(account) {
+        chains.extend(open.iter().map(|(symbol, chain)| ChainRecord {
+            symbol: symbol.clone(),
+            archived_at: None,
+            blocks: chain.chain.clone(),
+        }));
+    }
+    chains
+}
+
+// Puts back chains rebuilt from an export, all of them or none: open ones must not clash with an
+// open chain of the same symbol or go over `limit_operations`, and no chain may be present already.
+// The caller checks the blocks; nothing is published, since these aren't new.
+pub fn restore_chains(account: &str, records: Vec<ChainRecord>) -> Result<(), String> {
+    let limit_operations = limit_operations_for(account);
     let mut map = BLOCKCHAIN.lock().unwrap();
-    map.remove(symbol);
+    let mut archived = ARCHIVED_CHAINS.lock().unwrap();
+    let open = map.entry(account.to_string()).or_default();
+    let closed = archived.entry(account.to_string()).or_default();
+
+    let mut known: Vec<&str> = open
+        .values()
+        .filter_map(|chain| chain.chain.last())
+        .chain(closed.iter().filter_map(|record| record.blocks.last()))
+        .map(|block| block.hash.as_str())
+        .collect();
+    let mut opening: Vec<&str> = Vec::new();
+
+    for record in &records {
+        let head = record.blocks.last().map(|b| b.hash.as_str()).unwrap_or_default();
+        if known.contains(&head) {
+            return Err(format!("Chain for {} ending in {} is already present", record.symbol, head));
+        }
+        known.push(head);
+
+        if record.archived_at.is_none() {
+            if open.contains_key(&record.symbol) || opening.contains(&record.symbol.as_str()) {
+                return Err(format!("{} already has an open chain", record.symbol));
+            }
+            opening.push(&record.symbol);
+        }
+    }
+    if open.len() + opening.len() > limit_operations {
+        return Err(format!(
+            "{} open chains would exceed limit_operations ({})",
+            open.len() + opening.len(),
+            limit_operations
+        ));
+    }
+
+    for record in records {
+        match record.archived_at {
+            Some(_) => closed.push(record),
+            None => {
+                open.insert(record.symbol, TradeBlockchain::from_blocks(record.blocks));
+            }
+        }
+    }
+    Ok(())
 }
 
-pub fn get_current_blockchain_symbols() -> Vec<String> {
+pub fn get_current_blockchain_symbols(account: &str) -> Vec<String> {
     let map = BLOCKCHAIN.lock().unwrap();
-    map.keys().cloned().collect()
+    map.get(account)
+        .map(|chains| chains.keys().cloned().collect())
+        .unwrap_or_default()
 }
 
-pub fn add_trade_block(trade: Trade) -> bool {
+pub fn add_trade_block(account: &str, trade: Trade) -> bool {
+    let limit_operations = limit_operations_for(account);
     let mut map = BLOCKCHAIN.lock().unwrap();
+    let chains = map.entry(account.to_string()).or_default();
 
-    if let Some(chain) = map.get_mut(&trade.symbol) {
-        return chain.add_block(trade);
+    if let Some(chain) = chains.get_mut(&trade.symbol) {
+        let added = chain.add_block(trade);
+        if added {
+            publish_last_block(account, chain);
+        }
+        return added;
     }
 
-    let settings = Settings::load();
-    if map.len() >= settings.limit_operations {
+    if chains.len() >= limit_operations {
         return false;
     }
 
@@ -150,23 +405,76 @@ pub fn add_trade_block(trade: Trade) -> bool {
     let added = new_chain.add_block(trade.clone());
 
     if added {
-        map.insert(trade.symbol.clone(), new_chain);
+        publish_last_block(account, &new_chain);
+        chains.insert(trade.symbol.clone(), new_chain);
     }
 
     added
 }
 
-pub fn get_blockchain_for(symbol: &str) -> Option<Vec<TradeBlock>> {
-    let map = BLOCKCHAIN.lock().unwrap();
-    map.get(symbol).map(|chain| chain.all().to_vec())
+// Appends an event to the symbol's open chain. Symbols without one have nothing to annotate.
+pub fn record_event(account: &str, symbol: &str, event: BlockEvent) -> bool {
+    let mut map = BLOCKCHAIN.lock().unwrap();
+    let Some(chain) = map.get_mut(account).and_then(|chains| chains.get_mut(symbol)) else {
+        return false;
+    };
+
+    let added = chain.add_event(event);
+    if added {
+        publish_last_block(account, chain);
+    }
+    added
 }
 
-pub fn get_last_trade_for(symbol: &str) -> Option<Trade> {
-    let map = BLOCKCHAIN.lock().unwrap();
-    map.get(symbol).and_then(|chain| chain.get_last_trade())
+// Recorded once the action has answered, so the chain shows whether an operator's action went through.
+pub fn record_manual_override<T, E: std::fmt::Display>(
+    account: &str,
+    symbol: &str,
+    action: &str,
+    caller: &str,
+    result: &Result<T, E>,
+) -> bool {
+    record_event(
+        account,
+        symbol,
+        BlockEvent::ManualOverride {
+            action: action.to_string(),
+            caller: caller.to_string(),
+            success: Some(result.is_ok()),
+            error: result.as_ref().err().map(|e| e.to_string()),
+        },
+    )
 }
 
-pub fn get_all_symbols() -> Vec<String> {
+fn publish_last_block(account: &str, chain: &TradeBlockchain) {
+    if let Some(block) = chain.all().last() {
+        publish(account, EventPayload::Block(Box::new(block.clone())));
+    }
+}
+
+fn with_chain<T>(account: &str, symbol: &str, f: impl FnOnce(&TradeBlockchain) -> Option<T>) -> Option<T> {
     let map = BLOCKCHAIN.lock().unwrap();
-    map.keys().cloned().collect()
+    map.get(account).and_then(|chains| chains.get(symbol)).and_then(f)
+}
+
+pub fn get_blockchain_for(account: &str, symbol: &str) -> Option<Vec<TradeBlock>> {
+    with_chain(account, symbol, |chain| Some(chain.all().to_vec()))
+}
+
+// Where the symbol's open chain stands: its first block's hash, which tells it apart from earlier
+// chains of the symbol, and the index of its last status change, which decisions are made on.
+pub fn get_chain_step_for(account: &str, symbol: &str) -> Option<String> {
+    with_chain(account, symbol, |chain| {
+        let first = chain.all().first()?;
+        let status = chain.all().iter().rev().find(|b| b.trade().is_some())?;
+        Some(format!("{}-{}", &first.hash[..8.min(first.hash.len())], status.index))
+    })
+}
+
+pub fn get_last_trade_for(account: &str, symbol: &str) -> Option<Trade> {
+    with_chain(account, symbol, |chain| chain.get_last_trade())
+}
+
+pub fn is_blockchain_valid(account: &str, symbol: &str) -> Option<bool> {
+    with_chain(account, symbol, |chain| Some(chain.is_valid()))
 }
@@ -0,0 +1,493 @@
+use crate::blockchain::{get_all_chains_for, restore_chains, BlockEvent, ChainRecord, TradeBlock, TradeBlockchain};
+use crate::dto::Trade;
+use crate::error::AppError;
+use arrow_array::{Array, ArrayRef, RecordBatch, StringArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, Write};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ChainExport {
+    pub account: String,
+    pub symbol: String,
+    /// When the chain was closed; absent while it is open.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<u64>,
+    pub blocks: Vec<TradeBlock>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ImportResponse {
+    pub chains: usize,
+    pub blocks: usize,
+}
+
+// Open and archived chains of the account, or only those of `symbol`.
+pub fn export_chains(account: &str, symbol: Option<&str>) -> Vec<ChainExport> {
+    get_all_chains_for(account)
+        .into_iter()
+        .filter(|chain| symbol.is_none_or(|s| chain.symbol.eq_ignore_ascii_case(s)))
+        .map(|chain| ChainExport {
+            account: account.to_string(),
+            symbol: chain.symbol,
+            archived_at: chain.archived_at,
+            blocks: chain.blocks,
+        })
+        .collect()
+}
+
+// Checks every chain before restoring any.
+pub fn import_chains(account: &str, chains: Vec<ChainExport>) -> Result<ImportResponse, AppError> {
+    check_import(account, &chains)?;
+
+    let response = ImportResponse {
+        chains: chains.len(),
+        blocks: chains.iter().map(|c| c.blocks.len()).sum(),
+    };
+    let records = chains
+        .into_iter()
+        .map(|chain| ChainRecord {
+            symbol: chain.symbol,
+            archived_at: chain.archived_at,
+            blocks: chain.blocks,
+        })
+        .collect();
+    restore_chains(account, records).map_err(AppError::Conflict)?;
+    Ok(response)
+}
+
+// Every chain must belong to the account, start with a status change of its own symbol and have
+// every block hash to its content and carry this bot's signature.
+fn check_import(account: &str, chains: &[ChainExport]) -> Result<(), AppError> {
+    for chain in chains {
+        if chain.account != account {
+            return Err(AppError::validation(format!(
+                "Chain for {} belongs to account {}, not {}",
+                chain.symbol, chain.account, account
+            )));
+        }
+        if chain.blocks.first().and_then(TradeBlock::trade).is_none_or(|t| t.symbol != chain.symbol) {
+            return Err(AppError::validation(format!(
+                "Chain for {} does not start with a status change of {}",
+                chain.symbol, chain.symbol
+            )));
+        }
+        if !TradeBlockchain::from_blocks(chain.blocks.clone()).is_valid() {
+            return Err(AppError::Conflict(format!(
+                "Chain for {} does not validate: hashes, links or signatures are off",
+                chain.symbol
+            )));
+        }
+    }
+    Ok(())
+}
+
+// One block per line or row, with the chain it belongs to.
+#[derive(Serialize, Deserialize)]
+struct BlockRow {
+    account: String,
+    symbol: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    archived_at: Option<u64>,
+    #[serde(flatten)]
+    block: TradeBlock,
+}
+
+// Rows back into chains: a chain goes on while the account, symbol and archive time stay the same
+// and the next block isn't a first one.
+fn group(rows: Vec<BlockRow>) -> Vec<ChainExport> {
+    let mut chains: Vec<ChainExport> = Vec::new();
+    for row in rows {
+        match chains.last_mut() {
+            Some(chain)
+                if row.block.index != 0
+                    && chain.account == row.account
+                    && chain.symbol == row.symbol
+                    && chain.archived_at == row.archived_at =>
+            {
+                chain.blocks.push(row.block)
+            }
+            _ => chains.push(ChainExport {
+                account: row.account,
+                symbol: row.symbol,
+                archived_at: row.archived_at,
+                blocks: vec![row.block],
+            }),
+        }
+    }
+    chains
+}
+
+fn rows(chains: &[ChainExport]) -> impl Iterator<Item = (&ChainExport, &TradeBlock)> {
+    chains.iter().flat_map(|chain| chain.blocks.iter().map(move |block| (chain, block)))
+}
+
+pub fn write_jsonl(chains: &[ChainExport], out: &mut impl Write) -> Result<(), String> {
+    for (chain, block) in rows(chains) {
+        let row = BlockRow {
+            account: chain.account.clone(),
+            symbol: chain.symbol.clone(),
+            archived_at: chain.archived_at,
+            block: block.clone(),
+        };
+        let line = serde_json::to_string(&row).map_err(|e| e.to_string())?;
+        writeln!(out, "{}", line).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+pub fn read_jsonl(input: impl BufRead) -> Result<Vec<ChainExport>, String> {
+    let mut rows = Vec::new();
+    for (number, line) in input.lines().enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        rows.push(serde_json::from_str(&line).map_err(|e| format!("Line {}: {}", number + 1, e))?);
+    }
+    Ok(group(rows))
+}
+
+// CSV and Parquet share one flat layout: the chain, the block, then the `Trade` of status changes
+// spread over its own columns (its symbol is the chain's). Other events go in `event_json`.
+const TRADE_COLUMNS: [&str; 24] = [
+    "current_price",
+    "cma",
+    "oma",
+    "bias",
+    "status",
+    "zone_max",
+    "zone_7",
+    "zone_6",
+    "zone_5",
+    "zone_4",
+    "zone_3",
+    "zone_2",
+    "zone_1",
+    "zone_min",
+    "of",
+    "performance_24",
+    "performance_btc_24",
+    "amplitude_ma_200",
+    "volume",
+    "quote_asset_volume",
+    "number_of_trades",
+    "taker_buy_base_asset_volume",
+    "taker_buy_quote_asset_volume",
+    "funding_rate",
+];
+
+const INTEGER_COLUMNS: [&str; 6] = ["archived_at", "version", "index", "timestamp", "of", "next_funding_time"];
+
+fn columns() -> Vec<&'static str> {
+    let mut columns = vec!["account", "symbol", "archived_at", "version", "index", "timestamp", "event"];
+    columns.extend(TRADE_COLUMNS);
+    columns.extend(["next_funding_time", "event_json", "previous_hash", "hash", "signature"]);
+    columns
+}
+
+fn is_integer(column: &str) -> bool {
+    INTEGER_COLUMNS.contains(&column)
+}
+
+type Cells = Vec<Option<String>>;
+
+fn cell(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+fn to_cells(chain: &ChainExport, block: &TradeBlock) -> Result<Cells, String> {
+    let trade = block.trade().map(serde_json::to_value).transpose().map_err(|e| e.to_string())?;
+    let event_json = match &block.event {
+        BlockEvent::StatusChange { .. } => None,
+        event => Some(serde_json::to_string(event).map_err(|e| e.to_string())?),
+    };
+
+    Ok(columns()
+        .into_iter()
+        .map(|column| match column {
+            "account" => Some(chain.account.clone()),
+            "symbol" => Some(chain.symbol.clone()),
+            "archived_at" => chain.archived_at.map(|t| t.to_string()),
+            "version" => Some(block.version.to_string()),
+            "index" => Some(block.index.to_string()),
+            "timestamp" => Some(block.timestamp.to_string()),
+            "event" => Some(block.event.kind().to_string()),
+            "event_json" => event_json.clone(),
+            "previous_hash" => Some(block.previous_hash.clone()),
+            "hash" => Some(block.hash.clone()),
+            "signature" => Some(block.signature.clone()),
+            trade_column => trade.as_ref().and_then(|t| cell(&t[trade_column])),
+        })
+        .collect())
+}
+
+fn from_cells(header: &[String], cells: Cells, number: usize) -> Result<BlockRow, String> {
+    let mut values: HashMap<&str, Option<String>> = header.iter().map(String::as_str).zip(cells).collect();
+    let mut take = |column: &str| values.remove(column).flatten();
+    let required = |column: &str, value: Option<String>| {
+        value.ok_or_else(|| format!("Row {}: missing {}", number, column))
+    };
+    let integer = |column: &str, value: Option<String>| -> Result<u64, String> {
+        required(column, value)?
+            .parse()
+            .map_err(|_| format!("Row {}: {} is not an integer", number, column))
+    };
+
+    let symbol = required("symbol", take("symbol"))?;
+    let kind = required("event", take("event"))?;
+    let event = if kind == "status_change" {
+        let mut trade = Map::new();
+        trade.insert("symbol".to_string(), Value::String(symbol.clone()));
+        for column in TRADE_COLUMNS.into_iter().chain(["next_funding_time"]) {
+            let value = match (column, take(column)) {
+                (_, None) if column == "status" => Value::Null,
+                (_, Some(value)) if is_integer(column) => Value::Number(integer(column, Some(value))?.into()),
+                (_, value) => Value::String(value.unwrap_or_default()),
+            };
+            trade.insert(column.to_string(), value);
+        }
+        let trade: Trade =
+            serde_json::from_value(Value::Object(trade)).map_err(|e| format!("Row {}: {}", number, e))?;
+        BlockEvent::StatusChange { trade: Box::new(trade) }
+    } else {
+        let event: BlockEvent = serde_json::from_str(&required("event_json", take("event_json"))?)
+            .map_err(|e| format!("Row {}: {}", number, e))?;
+        if event.kind() != kind {
+            return Err(format!("Row {}: event_json is a {}, not a {}", number, event.kind(), kind));
+        }
+        event
+    };
+
+    Ok(BlockRow {
+        account: required("account", take("account"))?,
+        symbol,
+        archived_at: take("archived_at").map(|t| integer("archived_at", Some(t))).transpose()?,
+        block: TradeBlock {
+            version: integer("version", take("version"))? as u32,
+            index: integer("index", take("index"))?,
+            timestamp: integer("timestamp", take("timestamp"))?,
+            event,
+            previous_hash: required("previous_hash", take("previous_hash"))?,
+            hash: required("hash", take("hash"))?,
+            signature: take("signature").unwrap_or_default(),
+        },
+    })
+}
+
+pub fn write_csv(chains: &[ChainExport], out: impl Write) -> Result<(), String> {
+    let mut writer = csv::Writer::from_writer(out);
+    writer.write_record(columns()).map_err(|e| e.to_string())?;
+    for (chain, block) in rows(chains) {
+        let cells = to_cells(chain, block)?;
+        writer
+            .write_record(cells.iter().map(|c| c.as_deref().unwrap_or_default()))
+            .map_err(|e| e.to_string())?;
+    }
+    writer.flush().map_err(|e| e.to_string())
+}
+
+// Empty cells read as missing: a trade's `status` of None, or columns of another event type.
+pub fn read_csv(input: impl std::io::Read) -> Result<Vec<ChainExport>, String> {
+    let mut reader = csv::Reader::from_reader(input);
+    let header: Vec<String> = reader.headers().map_err(|e| e.to_string())?.iter().map(str::to_string).collect();
+
+    let mut rows = Vec::new();
+    for (number, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| e.to_string())?;
+        let cells = record.iter().map(|c| (!c.is_empty()).then(|| c.to_string())).collect();
+        rows.push(from_cells(&header, cells, number + 1)?);
+    }
+    Ok(group(rows))
+}
+
+pub fn write_parquet(chains: &[ChainExport], out: File) -> Result<(), String> {
+    let columns = columns();
+    let schema = Arc::new(Schema::new(
+        columns
+            .iter()
+            .map(|&c| Field::new(c, if is_integer(c) { DataType::UInt64 } else { DataType::Utf8 }, true))
+            .collect::<Vec<_>>(),
+    ));
+
+    let rows: Vec<Cells> = rows(chains).map(|(chain, block)| to_cells(chain, block)).collect::<Result<_, _>>()?;
+    let arrays: Vec<ArrayRef> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, &column)| -> ArrayRef {
+            let values = rows.iter().map(|row| row[i].as_deref());
+            if is_integer(column) {
+                Arc::new(values.map(|v| v.and_then(|v| v.parse::<u64>().ok())).collect::<UInt64Array>())
+            } else {
+                Arc::new(values.collect::<StringArray>())
+            }
+        })
+        .collect();
+
+    let batch = RecordBatch::try_new(schema.clone(), arrays).map_err(|e| e.to_string())?;
+    let mut writer = ArrowWriter::try_new(out, schema, None).map_err(|e| e.to_string())?;
+    writer.write(&batch).map_err(|e| e.to_string())?;
+    writer.close().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn read_parquet(input: File) -> Result<Vec<ChainExport>, String> {
+    let reader = ParquetRecordBatchReaderBuilder::try_new(input)
+        .and_then(|builder| builder.build())
+        .map_err(|e| e.to_string())?;
+
+    let mut rows = Vec::new();
+    for batch in reader {
+        let batch = batch.map_err(|e| e.to_string())?;
+        let header: Vec<String> = batch.schema().fields().iter().map(|f| f.name().clone()).collect();
+
+        for row in 0..batch.num_rows() {
+            let cells = batch
+                .columns()
+                .iter()
+                .map(|column| {
+                    if column.is_null(row) {
+                        None
+                    } else if let Some(integers) = column.as_any().downcast_ref::<UInt64Array>() {
+                        Some(integers.value(row).to_string())
+                    } else {
+                        column.as_any().downcast_ref::<StringArray>().map(|s| s.value(row).to_string())
+                    }
+                })
+                .collect();
+            rows.push(from_cells(&header, cells, rows.len() + 1)?);
+        }
+    }
+    Ok(group(rows))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::init_test_key;
+    use serde_json::json;
+
+    fn trade(symbol: &str, status: Option<&str>) -> Trade {
+        serde_json::from_value(json!({
+            "symbol": symbol,
+            "current_price": "100.5",
+            "cma": "99.0",
+            "oma": "98.0",
+            "bias": "Bullish",
+            "status": status,
+            "zone_max": "110",
+            "zone_7": "108",
+            "zone_6": "106",
+            "zone_5": "104",
+            "zone_4": "102",
+            "zone_3": "100",
+            "zone_2": "98",
+            "zone_1": "96",
+            "zone_min": "90",
+            "of": 5,
+            "performance_24": "1.5",
+            "performance_btc_24": "-0.5",
+            "amplitude_ma_200": "3.0",
+            "volume": "1000",
+            "quote_asset_volume": "100000",
+            "number_of_trades": "42",
+            "taker_buy_base_asset_volume": "500",
+            "taker_buy_quote_asset_volume": "50000",
+            "funding_rate": "0.0001",
+            "next_funding_time": 1700000000000u64
+        }))
+        .unwrap()
+    }
+
+    // An open chain whose first status is None, with order and override events between status
+    // changes, and an archived chain of another symbol.
+    fn chains() -> Vec<ChainExport> {
+        init_test_key();
+
+        let mut open = TradeBlockchain::new();
+        open.add_block(trade("BTCUSDT", None));
+        open.add_event(BlockEvent::OrderSent {
+            client_order_id: "rkd-BTCUSDT-1-buy".to_string(),
+            side: "BUY".to_string(),
+            quantity: "0.010".to_string(),
+            reduce_only: false,
+        });
+        open.add_event(BlockEvent::ManualOverride {
+            action: "close".to_string(),
+            caller: "ops, \"night\" shift".to_string(),
+            success: Some(false),
+            error: Some("Binance error".to_string()),
+        });
+        open.add_block(trade("BTCUSDT", Some("InZone7")));
+
+        let mut archived = TradeBlockchain::new();
+        archived.add_block(trade("ETHUSDT", Some("PrepareZone1")));
+        archived.add_event(BlockEvent::LeverageChange { leverage: 3 });
+
+        vec![
+            ChainExport {
+                account: "default".to_string(),
+                symbol: "BTCUSDT".to_string(),
+                archived_at: None,
+                blocks: open.all().to_vec(),
+            },
+            ChainExport {
+                account: "default".to_string(),
+                symbol: "ETHUSDT".to_string(),
+                archived_at: Some(1700000000),
+                blocks: archived.all().to_vec(),
+            },
+        ]
+    }
+
+    fn assert_round_trip(original: &[ChainExport], read: Vec<ChainExport>) {
+        assert_eq!(serde_json::to_value(original).unwrap(), serde_json::to_value(&read).unwrap());
+        check_import("default", &read).unwrap();
+    }
+
+    #[test]
+    fn jsonl_round_trip() {
+        let chains = chains();
+        let mut out = Vec::new();
+        write_jsonl(&chains, &mut out).unwrap();
+        assert_round_trip(&chains, read_jsonl(out.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let chains = chains();
+        let mut out = Vec::new();
+        write_csv(&chains, &mut out).unwrap();
+        assert_round_trip(&chains, read_csv(out.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn parquet_round_trip() {
+        let chains = chains();
+        let path = std::env::temp_dir().join(format!("rkd-chain-export-{}.parquet", std::process::id()));
+        write_parquet(&chains, File::create(&path).unwrap()).unwrap();
+        let read = read_parquet(File::open(&path).unwrap()).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_round_trip(&chains, read);
+    }
+
+    #[test]
+    fn a_changed_block_fails_the_import_check() {
+        let mut chains = chains();
+        if let BlockEvent::StatusChange { trade } = &mut chains[0].blocks[0].event {
+            trade.current_price = "1.0".to_string();
+        }
+        assert!(check_import("default", &chains).is_err());
+    }
+}
@@ -0,0 +1,145 @@
+use crate::blockchain::{get_all_chains_for, BlockEvent, ChainRecord};
+use crate::dto::{Bias, BlockPage, BlockQuery, ChainBlock, ChainSummary, TradeStatus};
+use crate::error::AppError;
+use std::collections::BTreeMap;
+
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 1000;
+
+fn parse_events(value: &str) -> Result<Vec<&'static str>, AppError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .map(|name| {
+            BlockEvent::KINDS
+                .into_iter()
+                .find(|kind| kind.eq_ignore_ascii_case(name))
+                .ok_or_else(|| AppError::validation(format!("Unknown event type '{}'", name)))
+        })
+        .collect()
+}
+
+// `None` in the list stands for trades without a status (bias changes and exits).
+fn parse_statuses(value: &str) -> Result<Vec<Option<TradeStatus>>, AppError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .map(|name| {
+            if name.eq_ignore_ascii_case("none") {
+                return Ok(None);
+            }
+            serde_json::from_value(serde_json::Value::String(name.to_string()))
+                .map(Some)
+                .map_err(|_| AppError::validation(format!("Unknown status '{}'", name)))
+        })
+        .collect()
+}
+
+// Blocks of every chain of the account, open and archived, filtered, sorted by time and paged.
+pub fn query_blocks(account: &str, query: &BlockQuery) -> Result<BlockPage, AppError> {
+    let symbols: Option<Vec<String>> = query
+        .symbol
+        .as_ref()
+        .map(|s| s.split(',').map(|s| s.trim().to_uppercase()).collect());
+    let events = query.event.as_deref().map(parse_events).transpose()?;
+    let statuses = query.status.as_deref().map(parse_statuses).transpose()?;
+    let bias = query.bias.as_deref().map(Bias::parse).transpose()?;
+    let descending = match query.order.as_deref() {
+        None | Some("asc") => false,
+        Some("desc") => true,
+        Some(other) => {
+            return Err(AppError::validation(format!("order must be 'asc' or 'desc', got '{}'", other)))
+        }
+    };
+    if let (Some(from), Some(to)) = (query.from, query.to)
+        && from > to
+    {
+        return Err(AppError::validation("'from' must not be after 'to'"));
+    }
+    if let (Some(from), Some(to)) = (query.from_index, query.to_index)
+        && from > to
+    {
+        return Err(AppError::validation("'from_index' must not be greater than 'to_index'"));
+    }
+    let include_archived = query.archived.unwrap_or(true);
+    let page = query.page.unwrap_or(1).max(1);
+    let page_size = query.page_size.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+    let mut blocks: Vec<ChainBlock> = get_all_chains_for(account)
+        .into_iter()
+        .filter(|chain| include_archived || chain.archived_at.is_none())
+        .filter(|chain| symbols.as_ref().is_none_or(|s| s.contains(&chain.symbol)))
+        .flat_map(|chain| {
+            let ChainRecord {
+                symbol,
+                archived_at,
+                blocks,
+            } = chain;
+            blocks.into_iter().map(move |block| ChainBlock {
+                symbol: symbol.clone(),
+                archived_at,
+                block,
+            })
+        })
+        .filter(|b| query.from.is_none_or(|from| b.block.timestamp >= from))
+        .filter(|b| query.to.is_none_or(|to| b.block.timestamp <= to))
+        .filter(|b| query.from_index.is_none_or(|from| b.block.index >= from))
+        .filter(|b| query.to_index.is_none_or(|to| b.block.index <= to))
+        .filter(|b| events.as_ref().is_none_or(|e| e.contains(&b.block.event.kind())))
+        .filter(|b| bias.as_ref().is_none_or(|bias| b.block.trade().is_some_and(|t| *bias == t.bias)))
+        .filter(|b| statuses.as_ref().is_none_or(|s| b.block.trade().is_some_and(|t| s.contains(&t.status))))
+        .collect();
+
+    blocks.sort_by(|a, b| {
+        (a.block.timestamp, &a.symbol, a.block.index).cmp(&(b.block.timestamp, &b.symbol, b.block.index))
+    });
+    if descending {
+        blocks.reverse();
+    }
+
+    let total = blocks.len();
+    let blocks = blocks
+        .into_iter()
+        .skip((page - 1) * page_size)
+        .take(page_size)
+        .collect();
+
+    Ok(BlockPage {
+        total,
+        page,
+        page_size,
+        blocks,
+    })
+}
+
+// One line per symbol that ever had a chain, aggregated over its open and archived chains.
+pub fn chain_summaries(account: &str) -> Vec<ChainSummary> {
+    let mut by_symbol: BTreeMap<String, Vec<ChainRecord>> = BTreeMap::new();
+    for chain in get_all_chains_for(account).into_iter().filter(|c| !c.blocks.is_empty()) {
+        by_symbol.entry(chain.symbol.clone()).or_default().push(chain);
+    }
+
+    by_symbol
+        .into_iter()
+        .filter_map(|(symbol, chains)| {
+            let blocks = chains.iter().flat_map(|c| &c.blocks);
+            let first_timestamp = blocks.clone().map(|b| b.timestamp).min()?;
+            let last = blocks.clone().max_by_key(|b| b.timestamp)?;
+            let (_, trade) = blocks
+                .clone()
+                .filter_map(|b| b.trade().map(|t| (b.timestamp, t)))
+                .max_by_key(|(timestamp, _)| *timestamp)?;
+
+            Some(ChainSummary {
+                active: chains.iter().any(|c| c.archived_at.is_none()),
+                chains: chains.len(),
+                blocks: blocks.clone().count(),
+                first_timestamp,
+                last_timestamp: last.timestamp,
+                status: trade.status.clone(),
+                bias: trade.bias.clone(),
+                symbol,
+            })
+        })
+        .collect()
+}
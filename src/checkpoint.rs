@@ -0,0 +1,282 @@
+use crate::blockchain::{get_all_chains_for, TradeBlock};
+use crate::config::Settings;
+use crate::error::AppError;
+use crate::merkle::{leaf_hash, verify_proof, MerkleLog, ProofStep};
+use crate::signing::{public_key, sign, verify};
+use ed25519_dalek::VerifyingKey;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct Checkpoint {
+    /// 1-based, in creation order.
+    pub id: u64,
+    pub timestamp: u64,
+    /// Blocks covered: the first `size` entries of the block log.
+    pub size: usize,
+    /// Merkle root over those blocks' hashes.
+    pub root: String,
+    /// Ed25519 signature of "<id>:<timestamp>:<size>:<root>".
+    pub signature: String,
+}
+
+impl Checkpoint {
+    fn message(&self) -> String {
+        format!("{}:{}:{}:{}", self.id, self.timestamp, self.size, self.root)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct InclusionProof {
+    pub checkpoint: Checkpoint,
+    pub account: String,
+    pub symbol: String,
+    /// Position of the block in the log, i.e. of its leaf in the checkpoint's tree.
+    pub leaf_index: usize,
+    pub block: TradeBlock,
+    /// Siblings from the block's leaf up to `checkpoint.root`.
+    pub path: Vec<ProofStep>,
+}
+
+// One line of the checkpoint file: a checkpoint and the block hashes it added to the log, so the
+// log can be rebuilt on startup and each root checked against it.
+#[derive(Debug, Serialize, Deserialize)]
+struct CheckpointRecord {
+    #[serde(flatten)]
+    checkpoint: Checkpoint,
+    hashes: Vec<String>,
+}
+
+// Every block hash of every chain, in the order checkpoints first saw them, as leaves of a Merkle
+// log. The log only grows, so a checkpoint is just a prefix of it and proofs stay available for
+// all of them without rehashing the blocks.
+#[derive(Default)]
+struct BlockLog {
+    tree: MerkleLog,
+    positions: HashMap<String, usize>,
+    checkpoints: Vec<Checkpoint>,
+}
+
+static BLOCK_LOG: Lazy<Mutex<BlockLog>> = Lazy::new(|| Mutex::new(BlockLog::default()));
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+// Logs the blocks added since the last checkpoint, signs a new root over the whole log and appends
+// it to the checkpoint file. Returns None when no block was added.
+pub fn create_checkpoint(path: &str) -> Result<Option<Checkpoint>, String> {
+    let mut new_blocks: Vec<(u64, String, String, u64, String)> = Settings::current()
+        .account_names()
+        .into_iter()
+        .flat_map(|account| {
+            get_all_chains_for(&account).into_iter().flat_map(move |chain| {
+                let account = account.clone();
+                chain
+                    .blocks
+                    .into_iter()
+                    .map(move |b| (b.timestamp, account.clone(), chain.symbol.clone(), b.index, b.hash))
+            })
+        })
+        .collect();
+
+    let mut log = BLOCK_LOG.lock().unwrap();
+    new_blocks.retain(|(.., hash)| !log.positions.contains_key(hash));
+    if new_blocks.is_empty() {
+        return Ok(None);
+    }
+    new_blocks.sort();
+    let hashes: Vec<String> = new_blocks.into_iter().map(|(.., hash)| hash).collect();
+
+    let previous_size = log.tree.len();
+    let size = log.push_hashes(&hashes);
+    let mut checkpoint = Checkpoint {
+        id: log.checkpoints.len() as u64 + 1,
+        timestamp: now_secs(),
+        size,
+        root: hex::encode(log.tree.root(size)),
+        signature: String::new(),
+    };
+    checkpoint.signature = sign(checkpoint.message().as_bytes());
+
+    // Written before proofs are served for it; if that fails the log drops the new blocks again.
+    let record = CheckpointRecord {
+        checkpoint: checkpoint.clone(),
+        hashes,
+    };
+    if let Err(e) = append_record(path, &record) {
+        log.truncate(previous_size, &record.hashes);
+        return Err(e);
+    }
+    log.checkpoints.push(checkpoint.clone());
+    Ok(Some(checkpoint))
+}
+
+impl BlockLog {
+    // Returns the new size of the log.
+    fn push_hashes(&mut self, hashes: &[String]) -> usize {
+        for hash in hashes {
+            self.positions.insert(hash.clone(), self.tree.len());
+            self.tree.push(leaf_hash(hash.as_bytes()));
+        }
+        self.tree.len()
+    }
+
+    fn truncate(&mut self, size: usize, hashes: &[String]) {
+        self.tree.truncate(size);
+        for hash in hashes {
+            self.positions.remove(hash);
+        }
+    }
+}
+
+fn append_record(path: &str, record: &CheckpointRecord) -> Result<(), String> {
+    let line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+    if let Some(dir) = Path::new(path).parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", line))
+        .map_err(|e| format!("Failed to append to {}: {}", path, e))
+}
+
+// Rebuilds the block log and its checkpoints from the checkpoint file, checking that each
+// checkpoint follows the one before, covers the log so far and is signed by this bot's key.
+// Returns the number of checkpoints loaded.
+pub fn load_checkpoints(path: &str) -> Result<usize, String> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(format!("Failed to read {}: {}", path, e)),
+    };
+    let key = public_key().ok_or("Signing key not loaded")?;
+
+    let mut log = BlockLog::default();
+    for (number, line) in content.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        let number = number + 1;
+        let record: CheckpointRecord = serde_json::from_str(line)
+            .map_err(|e| format!("{} line {}: unreadable checkpoint: {}", path, number, e))?;
+        let checkpoint = &record.checkpoint;
+
+        if checkpoint.id != log.checkpoints.len() as u64 + 1 {
+            return Err(format!("{} line {}: checkpoint {} out of order", path, number, checkpoint.id));
+        }
+        let size = log.push_hashes(&record.hashes);
+        if checkpoint.size != size || hex::encode(log.tree.root(size)) != checkpoint.root {
+            return Err(format!(
+                "{} line {}: checkpoint {} does not match its blocks",
+                path, number, checkpoint.id
+            ));
+        }
+        if !verify(&key, checkpoint.message().as_bytes(), &checkpoint.signature) {
+            return Err(format!("{} line {}: bad signature on checkpoint {}", path, number, checkpoint.id));
+        }
+        log.checkpoints.push(record.checkpoint);
+    }
+
+    let count = log.checkpoints.len();
+    *BLOCK_LOG.lock().unwrap() = log;
+    Ok(count)
+}
+
+pub fn get_checkpoints() -> Vec<Checkpoint> {
+    BLOCK_LOG.lock().unwrap().checkpoints.clone()
+}
+
+fn find_block(hash: &str) -> Option<(String, String, TradeBlock)> {
+    Settings::current().account_names().into_iter().find_map(|account| {
+        get_all_chains_for(&account).into_iter().find_map(|chain| {
+            let block = chain.blocks.into_iter().find(|b| b.hash == hash)?;
+            Some((account.clone(), chain.symbol, block))
+        })
+    })
+}
+
+// Proof that the block with `hash` is under the root of `checkpoint_id`, by default the latest checkpoint.
+pub fn inclusion_proof(hash: &str, checkpoint_id: Option<u64>) -> Result<InclusionProof, AppError> {
+    let (checkpoint, leaf_index, path) = {
+        let log = BLOCK_LOG.lock().unwrap();
+        let checkpoint = match checkpoint_id {
+            Some(id) => log
+                .checkpoints
+                .iter()
+                .find(|c| c.id == id)
+                .ok_or_else(|| AppError::NotFound(format!("Checkpoint {} not found", id)))?,
+            None => log
+                .checkpoints
+                .last()
+                .ok_or_else(|| AppError::NotFound("No checkpoint yet".to_string()))?,
+        };
+        let leaf_index = *log
+            .positions
+            .get(hash)
+            .filter(|&&position| position < checkpoint.size)
+            .ok_or_else(|| {
+                AppError::NotFound(format!("Block {} is not covered by checkpoint {}", hash, checkpoint.id))
+            })?;
+        let path = log.tree.proof(leaf_index, checkpoint.size);
+        (checkpoint.clone(), leaf_index, path)
+    };
+
+    let (account, symbol, block) =
+        find_block(hash).ok_or_else(|| AppError::NotFound(format!("Block {} not found", hash)))?;
+
+    Ok(InclusionProof {
+        checkpoint,
+        account,
+        symbol,
+        leaf_index,
+        block,
+        path,
+    })
+}
+
+// What an auditor checks: the block hashes to its content and is signed, its leaf leads to the
+// checkpoint root, and the checkpoint itself is signed.
+pub fn verify_inclusion(proof: &InclusionProof, key: &VerifyingKey) -> Result<(), String> {
+    let block = &proof.block;
+    if block.calculate_hash().as_ref() != Some(&block.hash) {
+        return Err("Block content does not match its hash".to_string());
+    }
+    if !verify(key, block.hash.as_bytes(), &block.signature) {
+        return Err("Bad block signature".to_string());
+    }
+    if !verify_proof(leaf_hash(block.hash.as_bytes()), &proof.path, &proof.checkpoint.root) {
+        return Err(format!("Path does not lead to the root of checkpoint {}", proof.checkpoint.id));
+    }
+    if !verify(key, proof.checkpoint.message().as_bytes(), &proof.checkpoint.signature) {
+        return Err(format!("Bad signature on checkpoint {}", proof.checkpoint.id));
+    }
+    Ok(())
+}
+
+pub fn spawn_checkpoint_writer() {
+    let settings = Settings::current().blockchain.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(settings.checkpoint_interval_secs));
+        // The first tick fires immediately, before any chain exists.
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            match create_checkpoint(&settings.checkpoint_file) {
+                Ok(Some(checkpoint)) => println!(
+                    "[blockchain] Checkpoint {} - root {} ({} blocks)",
+                    checkpoint.id, checkpoint.root, checkpoint.size
+                ),
+                Ok(None) => {}
+                Err(e) => eprintln!("[blockchain] Failed to write checkpoint: {}", e),
+            }
+        }
+    });
+}
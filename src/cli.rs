@@ -0,0 +1,749 @@
+use crate::anchor::verify_anchors;
+use crate::backtest::run_backtest;
+use crate::block_encoding::CURRENT_VERSION;
+use crate::blockchain::{BlockEvent, TradeBlock, TradeBlockchain};
+use crate::chain_export::{
+    read_csv, read_jsonl, read_parquet, write_csv, write_jsonl, write_parquet, ChainExport, ImportResponse,
+};
+use crate::checkpoint::{verify_inclusion, InclusionProof};
+use crate::config::{Settings, SettingsSource};
+use crate::dto::{LeverageRequest, OpenOrderRequest, PublicKeyResponse, SymbolRequest};
+use crate::error::AppError;
+use crate::migration::{migrate_chain, verify_exported_chain};
+use crate::monitor::monitor_cryptos;
+use crate::monitor_table::render;
+use crate::settings_service::{account_settings, has_errors, validate, Severity};
+use crate::signing::{init_signing_key, parse_public_key};
+use crate::spy::spy_cryptos;
+use chrono::{Local, TimeZone};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use ed25519_dalek::VerifyingKey;
+use prettytable::{row, Table};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "RKD high frequency trading bot")]
+pub struct Cli {
+    /// Settings file; defaults to RKD_CONFIG or config/Settings.toml.
+    #[arg(long, global = true)]
+    pub config: Option<String>,
+    /// Settings profile layered on top, e.g. `testnet`; defaults to RKD_PROFILE.
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+    /// Encrypts the credentials in the environment into a keystore at PATH and exits.
+    #[arg(long, value_name = "PATH")]
+    pub seal_keystore: Option<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Cli {
+    pub fn settings_source(&self) -> SettingsSource {
+        SettingsSource::from_options(self.config.clone(), self.profile.clone())
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Starts the HTTP server and the trading loop (the default).
+    Run {
+        /// Shows the terminal dashboard while the server runs.
+        #[arg(long)]
+        tui: bool,
+    },
+    /// Runs one spy + monitor cycle and prints the monitor table.
+    Scan {
+        /// Account name; defaults to the first one.
+        #[arg(long)]
+        account: Option<String>,
+        /// Output format.
+        #[arg(long, value_enum, default_value_t = ScanFormat::Text)]
+        format: ScanFormat,
+        /// Include the volume columns; defaults to `show_details_monitor`.
+        #[arg(long)]
+        details: Option<bool>,
+    },
+    /// Reads the chains of a running server.
+    Chain {
+        #[command(flatten)]
+        server: ServerArgs,
+        #[command(subcommand)]
+        command: ChainCommand,
+    },
+    /// Sends manual orders to Binance through a running server.
+    Order {
+        #[command(flatten)]
+        server: ServerArgs,
+        #[command(subcommand)]
+        command: OrderCommand,
+    },
+    /// Changes the leverage of a symbol on Binance through a running server.
+    Leverage {
+        #[command(flatten)]
+        server: ServerArgs,
+        #[command(subcommand)]
+        command: LeverageCommand,
+    },
+    /// Replays the strategy over historical candles of a symbol.
+    Backtest {
+        symbol: String,
+        /// Account name; defaults to the first one.
+        #[arg(long)]
+        account: Option<String>,
+        /// Candles to fetch at the configured interval.
+        #[arg(long, default_value_t = 1000, value_parser = clap::value_parser!(u32).range(271..=1000))]
+        candles: u32,
+        /// Print the report as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Validates the settings files.
+    Settings {
+        #[command(subcommand)]
+        command: SettingsCommand,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ScanFormat {
+    Text,
+    Csv,
+    Markdown,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ExportFormat {
+    Json,
+    Jsonl,
+    Csv,
+    Parquet,
+}
+
+#[derive(Debug, Args)]
+pub struct ServerArgs {
+    /// Server base URL; defaults to this machine on the configured port.
+    #[arg(long, env = "RKD_SERVER", global = true)]
+    server: Option<String>,
+    /// Bearer token of an API client, needed when `[auth]` is enabled.
+    #[arg(long, env = "RKD_API_TOKEN", global = true, hide_env_values = true)]
+    token: Option<String>,
+    /// Account name; defaults to the first one.
+    #[arg(long, global = true)]
+    account: Option<String>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ChainCommand {
+    /// Prints the blocks of a symbol's chain.
+    Show { symbol: String },
+    /// Recomputes the hashes of a symbol's chain locally and checks their signatures.
+    Verify {
+        symbol: String,
+        /// Hex Ed25519 key to check against; defaults to the one the server reports.
+        #[arg(long)]
+        public_key: Option<String>,
+    },
+    /// Writes one symbol's chains, or every open and archived chain of the account.
+    Export {
+        /// Symbol; every chain when omitted, which `json` can't do.
+        symbol: Option<String>,
+        /// `json` is the open chain's block array, as `chain migrate` reads it; the others carry
+        /// archived chains too and are what `chain import` reads.
+        #[arg(long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+        /// File to write; defaults to stdout, except for parquet.
+        #[arg(long, short)]
+        output: Option<String>,
+    },
+    /// Rebuilds chains on the server from a jsonl, csv or parquet export; nothing is imported
+    /// unless every block hashes, links and is signed by the server's key.
+    Import {
+        file: String,
+        /// Defaults to the file's extension.
+        #[arg(long, value_enum)]
+        format: Option<ExportFormat>,
+    },
+    /// Checks the anchor file: links between records, Merkle roots and signatures.
+    Anchors {
+        /// Anchor file; defaults to `blockchain.anchor_file` from the settings.
+        #[arg(long)]
+        file: Option<String>,
+        /// Hex Ed25519 key to check against; defaults to the one the server reports.
+        #[arg(long)]
+        public_key: Option<String>,
+    },
+    /// Fetches the inclusion proof of one block and checks it against its checkpoint's root.
+    Proof {
+        /// Block hash.
+        hash: String,
+        /// Checkpoint id; defaults to the latest one.
+        #[arg(long)]
+        checkpoint: Option<u64>,
+        /// Hex Ed25519 key to check against; defaults to the one the server reports.
+        #[arg(long)]
+        public_key: Option<String>,
+    },
+    /// Verifies an exported chain with the schema each block was written with and, given an
+    /// output file, rewrites it in the current schema signed with this machine's key.
+    Migrate {
+        /// Chain exported as a JSON array of blocks.
+        file: String,
+        /// File to write the migrated chain to; without it the chain is only verified. Needs
+        /// `--public-key` and a chain whose blocks are all signed.
+        #[arg(long, short)]
+        output: Option<String>,
+        /// Hex Ed25519 key to check signed blocks against; without it signatures aren't checked.
+        #[arg(long)]
+        public_key: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum OrderCommand {
+    /// Opens a market order with the configured money and leverage.
+    Open {
+        symbol: String,
+        #[arg(value_enum)]
+        side: Side,
+    },
+    /// Closes every position of a symbol.
+    Close { symbol: String },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Side {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Side::Buy => "BUY",
+            Side::Sell => "SELL",
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum LeverageCommand {
+    /// Sets the leverage of a symbol; defaults to its configured value.
+    Set { symbol: String, leverage: Option<u32> },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SettingsCommand {
+    /// Loads and validates the settings, exiting with 1 on errors.
+    Check,
+}
+
+fn print_json<T: Serialize>(value: &T) {
+    println!("{}", serde_json::to_string_pretty(value).unwrap_or_default());
+}
+
+fn format_time(secs: u64) -> String {
+    Local
+        .timestamp_opt(secs as i64, 0)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+// Runs every command except `run`, which main handles because it owns the server.
+pub async fn execute(command: Command) -> Result<(), AppError> {
+    match command {
+        Command::Run { .. } => unreachable!("handled by main"),
+        Command::Scan {
+            account,
+            format,
+            details,
+        } => scan(account.as_deref(), format, details).await,
+        Command::Chain { server, command } => chain(&server, command).await,
+        Command::Order { server, command } => order(&server, command).await,
+        Command::Leverage {
+            server,
+            command: LeverageCommand::Set { symbol, leverage },
+        } => {
+            let request = LeverageRequest {
+                symbol: symbol.to_uppercase(),
+                leverage,
+            };
+            let response: Value = send(&server, reqwest::Method::PUT, "/trades/leverage", Some(&request)).await?;
+            print_json(&response);
+            Ok(())
+        }
+        Command::Backtest {
+            symbol,
+            account,
+            candles,
+            json,
+        } => {
+            let settings = account_settings(account.as_deref())?;
+            let report = run_backtest(&settings, &symbol.to_uppercase(), candles).await?;
+            if json {
+                print_json(&report);
+                return Ok(());
+            }
+
+            let mut steps = Table::new();
+            steps.add_row(row!["Candle", "Price", "Bias", "Status", "Action"]);
+            for step in &report.steps {
+                steps.add_row(row![
+                    format_time(step.close_time / 1000),
+                    step.price,
+                    format!("{:?}", step.bias),
+                    step.status.as_ref().map(|s| format!("{:?}", s)).unwrap_or_else(|| "-".into()),
+                    step.action
+                ]);
+            }
+            steps.printstd();
+
+            let mut positions = Table::new();
+            positions.add_row(row!["Side", "Entry", "Exit", "Entry price", "Exit price", "Return %"]);
+            for p in &report.positions {
+                positions.add_row(row![
+                    p.side,
+                    format_time(p.entry_time / 1000),
+                    if p.closed { format_time(p.exit_time / 1000) } else { "open".into() },
+                    p.entry_price,
+                    p.exit_price,
+                    format!("{:.2}", p.return_pct)
+                ]);
+            }
+            positions.printstd();
+            println!(
+                "{} {} candles ({}), {} status changes, {} positions, total return {:.2}% (no leverage, fees or funding)",
+                report.symbol,
+                report.candles,
+                report.interval,
+                report.steps.len(),
+                report.positions.len(),
+                report.total_return_pct
+            );
+            Ok(())
+        }
+        Command::Settings {
+            command: SettingsCommand::Check,
+        } => {
+            let mut settings = Settings::read_from_disk().map_err(AppError::Config)?;
+            let issues = validate(&mut settings);
+            for issue in &issues {
+                println!("{:?} {}: {}", issue.severity, issue.field, issue.message);
+            }
+
+            let files = SettingsSource::current().files().join(" + ");
+            if has_errors(&issues) {
+                let errors = issues.iter().filter(|i| i.severity == Severity::Error).count();
+                return Err(AppError::Config(format!("{}: {} error(s)", files, errors)));
+            }
+            println!("{}: ok, {} account(s), {} symbol(s)", files, settings.account_names().len(), settings.cryptos.len());
+            Ok(())
+        }
+    }
+}
+
+async fn scan(account: Option<&str>, format: ScanFormat, details: Option<bool>) -> Result<(), AppError> {
+    let settings = account_settings(account)?;
+    let binance = &settings.binance;
+    let trades = spy_cryptos(
+        &binance.account,
+        &binance.base_url,
+        &binance.future_url,
+        &binance.interval,
+        binance.limit,
+        settings.cryptos.clone(),
+    )
+    .await;
+    let snapshot = monitor_cryptos(&trades, &settings);
+
+    let format = match format {
+        ScanFormat::Json => {
+            print_json(&snapshot);
+            return Ok(());
+        }
+        ScanFormat::Text => "text",
+        ScanFormat::Csv => "csv",
+        ScanFormat::Markdown => "markdown",
+    };
+    let rendered = render(
+        format,
+        &snapshot.trades,
+        &snapshot.timestamp,
+        &snapshot.zone_distribution,
+        details.unwrap_or(settings.show_details_monitor),
+    )?;
+    print!("{}", rendered.body);
+    Ok(())
+}
+
+// Orders go through the server, so they queue behind the strategy's actions on the symbol, land
+// on its chain and in its order records like the ones sent from the API.
+async fn order(server: &ServerArgs, command: OrderCommand) -> Result<(), AppError> {
+    let response: Value = match command {
+        OrderCommand::Open { symbol, side } => {
+            let request = OpenOrderRequest {
+                symbol: symbol.to_uppercase(),
+                side: side.as_str().to_string(),
+            };
+            post(server, "/trades/order/open", &request).await?
+        }
+        OrderCommand::Close { symbol } => {
+            let request = SymbolRequest {
+                symbol: symbol.to_uppercase(),
+            };
+            post(server, "/trades/order/close", &request).await?
+        }
+    };
+    print_json(&response);
+    Ok(())
+}
+
+fn describe_event(event: &BlockEvent) -> String {
+    match event {
+        BlockEvent::StatusChange { .. } => String::new(),
+        BlockEvent::OrderSent {
+            client_order_id,
+            side,
+            quantity,
+            reduce_only,
+        } => format!(
+            "{} {} {}{}",
+            side,
+            quantity,
+            client_order_id,
+            if *reduce_only { " (reduce only)" } else { "" }
+        ),
+        BlockEvent::OrderResult {
+            client_order_id,
+            state,
+            executed_qty,
+            error,
+            ..
+        } => format!(
+            "{} {:?} {}",
+            client_order_id,
+            state,
+            error.clone().or_else(|| executed_qty.clone()).unwrap_or_default()
+        ),
+        BlockEvent::LeverageChange { leverage } => format!("{}x", leverage),
+        BlockEvent::PositionClosed {
+            quantity,
+            mark_price,
+            realized_pnl,
+            ..
+        } => format!("{} @ {} PnL {:.2}", quantity, mark_price, realized_pnl),
+        BlockEvent::ManualOverride {
+            action,
+            caller,
+            success,
+            error,
+        } => match (success, error) {
+            (Some(false), Some(error)) => format!("{} by {} failed: {}", action, caller, error),
+            (Some(false), None) => format!("{} by {} failed", action, caller),
+            _ => format!("{} by {}", action, caller),
+        },
+        BlockEvent::RiskIntervention { intervention } => format!("{:?}", intervention.kind),
+        BlockEvent::Migration { public_key, .. } => format!("signed by {} from here on", public_key),
+    }
+}
+
+// Chains live in the server's memory, so these commands read them through its API.
+async fn chain(server: &ServerArgs, command: ChainCommand) -> Result<(), AppError> {
+    let symbol = match &command {
+        ChainCommand::Show { symbol } | ChainCommand::Verify { symbol, .. } => symbol.to_uppercase(),
+        ChainCommand::Export {
+            symbol,
+            format,
+            output,
+        } => return export(server, symbol.as_deref(), *format, output.as_deref()).await,
+        ChainCommand::Import { file, format } => return import(server, file, *format).await,
+        ChainCommand::Anchors { file, public_key } => {
+            return anchors(server, file.clone(), public_key.as_deref()).await;
+        }
+        ChainCommand::Proof {
+            hash,
+            checkpoint,
+            public_key,
+        } => return proof(server, hash, *checkpoint, public_key.as_deref()).await,
+        ChainCommand::Migrate {
+            file,
+            output,
+            public_key,
+        } => return migrate(file, output.as_deref(), public_key.as_deref()),
+    };
+    let blocks: Vec<TradeBlock> = fetch(server, &format!("/trades/chains/{}", symbol)).await?;
+
+    match command {
+        ChainCommand::Show { .. } => {
+            let mut table = Table::new();
+            table.add_row(row!["#", "Time", "Event", "Status", "Bias", "Price", "Detail", "Hash"]);
+            for block in &blocks {
+                let trade = block.trade();
+                table.add_row(row![
+                    block.index,
+                    format_time(block.timestamp),
+                    block.event.kind(),
+                    trade
+                        .map(|t| t.status.as_ref().map(|s| format!("{:?}", s)).unwrap_or_else(|| "-".into()))
+                        .unwrap_or_default(),
+                    trade.map(|t| format!("{:?}", t.bias)).unwrap_or_default(),
+                    trade.map(|t| t.current_price.clone()).unwrap_or_default(),
+                    describe_event(&block.event),
+                    &block.hash[..12.min(block.hash.len())]
+                ]);
+            }
+            table.printstd();
+        }
+        ChainCommand::Verify { public_key, .. } => {
+            let key = verifying_key(server, public_key.as_deref()).await?;
+            let count = blocks.len();
+            if !TradeBlockchain::from_blocks(blocks).is_valid_with(&key) {
+                return Err(AppError::Conflict(format!("Chain for {} is invalid", symbol)));
+            }
+            println!("Chain for {} is valid ({} blocks)", symbol, count);
+        }
+        // Answered above, without fetching a single chain.
+        ChainCommand::Export { .. }
+        | ChainCommand::Import { .. }
+        | ChainCommand::Anchors { .. }
+        | ChainCommand::Proof { .. }
+        | ChainCommand::Migrate { .. } => unreachable!(),
+    }
+    Ok(())
+}
+
+async fn export(
+    server: &ServerArgs,
+    symbol: Option<&str>,
+    format: ExportFormat,
+    output: Option<&str>,
+) -> Result<(), AppError> {
+    if format == ExportFormat::Json {
+        let symbol = symbol
+            .ok_or_else(|| AppError::validation("json exports one chain; pass a symbol or use jsonl, csv or parquet"))?
+            .to_uppercase();
+        let blocks: Vec<TradeBlock> = fetch(server, &format!("/trades/chains/{}", symbol)).await?;
+        let json = serde_json::to_string_pretty(&blocks).map_err(|e| AppError::Internal(e.to_string()))?;
+        match output {
+            Some(path) => {
+                fs::write(path, json).map_err(|e| AppError::Internal(format!("{}: {}", path, e)))?;
+                println!("{} blocks written to {}", blocks.len(), path);
+            }
+            None => println!("{}", json),
+        }
+        return Ok(());
+    }
+
+    let mut path = "/trades/chains/export".to_string();
+    if let Some(symbol) = symbol {
+        path.push_str(&format!("?symbol={}", symbol.to_uppercase()));
+    }
+    let chains: Vec<ChainExport> = fetch(server, &path).await?;
+
+    let Some(output) = output else {
+        let stdout = std::io::stdout();
+        return match format {
+            ExportFormat::Jsonl => write_jsonl(&chains, &mut stdout.lock()),
+            ExportFormat::Csv => write_csv(&chains, stdout.lock()),
+            _ => Err("Parquet needs an output file (-o)".to_string()),
+        }
+        .map_err(AppError::validation);
+    };
+    let file = fs::File::create(output).map_err(|e| AppError::Internal(format!("{}: {}", output, e)))?;
+    match format {
+        ExportFormat::Jsonl => write_jsonl(&chains, &mut std::io::BufWriter::new(file)),
+        ExportFormat::Csv => write_csv(&chains, file),
+        _ => write_parquet(&chains, file),
+    }
+    .map_err(|e| AppError::Internal(format!("{}: {}", output, e)))?;
+
+    println!(
+        "{} chains, {} blocks written to {}",
+        chains.len(),
+        chains.iter().map(|c| c.blocks.len()).sum::<usize>(),
+        output
+    );
+    Ok(())
+}
+
+// Checked here first so a bad file is reported block by block; the server checks again anyway.
+async fn import(server: &ServerArgs, file: &str, format: Option<ExportFormat>) -> Result<(), AppError> {
+    let format = match format {
+        Some(format) => format,
+        None => match file.rsplit('.').next() {
+            Some("jsonl") => ExportFormat::Jsonl,
+            Some("csv") => ExportFormat::Csv,
+            Some("parquet") => ExportFormat::Parquet,
+            _ => return Err(AppError::validation(format!("Can't tell the format of {}; pass --format", file))),
+        },
+    };
+    let input = fs::File::open(file).map_err(|e| AppError::NotFound(format!("{}: {}", file, e)))?;
+    let chains = match format {
+        ExportFormat::Jsonl => read_jsonl(std::io::BufReader::new(input)),
+        ExportFormat::Csv => read_csv(input),
+        ExportFormat::Parquet => read_parquet(input),
+        ExportFormat::Json => Err("json exports are for `chain migrate`; import reads jsonl, csv or parquet".to_string()),
+    }
+    .map_err(|e| AppError::validation(format!("{}: {}", file, e)))?;
+
+    let key = verifying_key(server, None).await?;
+    for chain in &chains {
+        if !TradeBlockchain::from_blocks(chain.blocks.clone()).is_valid_with(&key) {
+            return Err(AppError::Conflict(format!(
+                "{}: chain for {} ({}) is invalid, nothing imported",
+                file, chain.symbol, chain.account
+            )));
+        }
+    }
+
+    let response: ImportResponse = post(server, "/trades/chains/import", &chains).await?;
+    println!("{} chains, {} blocks imported from {}", response.chains, response.blocks, file);
+    Ok(())
+}
+
+async fn anchors(server: &ServerArgs, file: Option<String>, public_key: Option<&str>) -> Result<(), AppError> {
+    let key = verifying_key(server, public_key).await?;
+    let path = file.unwrap_or_else(|| Settings::current().blockchain.anchor_file.clone());
+    let content = fs::read_to_string(&path).map_err(|e| AppError::NotFound(format!("{}: {}", path, e)))?;
+    let count = verify_anchors(&content, &key).map_err(|e| AppError::Conflict(format!("{} is invalid: {}", path, e)))?;
+    println!("{} is valid ({} anchors)", path, count);
+    Ok(())
+}
+
+async fn proof(
+    server: &ServerArgs,
+    hash: &str,
+    checkpoint: Option<u64>,
+    public_key: Option<&str>,
+) -> Result<(), AppError> {
+    let key = verifying_key(server, public_key).await?;
+    let mut path = format!("/trades/blocks/{}/proof", hash);
+    if let Some(id) = checkpoint {
+        path.push_str(&format!("?checkpoint={}", id));
+    }
+    let proof: InclusionProof = fetch(server, &path).await?;
+
+    verify_inclusion(&proof, &key).map_err(|e| AppError::Conflict(format!("Proof for {} is invalid: {}", hash, e)))?;
+    println!(
+        "Block {} of {} ({}) is in checkpoint {}: root {}, {} blocks, {} proof steps",
+        proof.block.index,
+        proof.symbol,
+        proof.account,
+        proof.checkpoint.id,
+        proof.checkpoint.root,
+        proof.checkpoint.size,
+        proof.path.len()
+    );
+    Ok(())
+}
+
+// Works on files alone, so it also runs where no server is up.
+fn migrate(file: &str, output: Option<&str>, public_key: Option<&str>) -> Result<(), AppError> {
+    if output.is_some() && public_key.is_none() {
+        return Err(AppError::validation("--public-key is required with -o: only checked signatures can be migrated"));
+    }
+    let key = public_key.map(parse_public_key).transpose().map_err(AppError::validation)?;
+    let content = fs::read_to_string(file).map_err(|e| AppError::NotFound(format!("{}: {}", file, e)))?;
+    let blocks: Vec<serde_json::Value> =
+        serde_json::from_str(&content).map_err(|e| AppError::validation(format!("{}: {}", file, e)))?;
+
+    let check = verify_exported_chain(&blocks, key.as_ref())
+        .map_err(|e| AppError::Conflict(format!("{} is invalid: {}", file, e)))?;
+    let versions: Vec<String> = check
+        .versions
+        .iter()
+        .map(|(version, count)| format!("{} at version {}", count, version))
+        .collect();
+    println!(
+        "{} is valid ({} blocks: {}; {} signed, signatures {})",
+        file,
+        check.blocks,
+        versions.join(", "),
+        check.signed,
+        if key.is_some() { "checked" } else { "not checked" }
+    );
+
+    let (Some(output), Some(key)) = (output, key) else {
+        return Ok(());
+    };
+    init_signing_key(&Settings::current().blockchain.signing_key).map_err(AppError::Internal)?;
+    let migrated = migrate_chain(&blocks, &key).map_err(AppError::validation)?;
+    verify_exported_chain(&migrated, Some(&key))
+        .map_err(|e| AppError::Internal(format!("Migrated chain does not validate: {}", e)))?;
+
+    let json = serde_json::to_string_pretty(&migrated).map_err(|e| AppError::Internal(e.to_string()))?;
+    fs::write(output, json).map_err(|e| AppError::Internal(format!("{}: {}", output, e)))?;
+    println!(
+        "{} blocks written to {}, the last one a version {} migration block signed with this bot's key",
+        migrated.len(),
+        output,
+        CURRENT_VERSION
+    );
+    Ok(())
+}
+
+async fn verifying_key(server: &ServerArgs, public_key: Option<&str>) -> Result<VerifyingKey, AppError> {
+    let value = match public_key {
+        Some(value) => value.to_string(),
+        None => fetch::<PublicKeyResponse>(server, "/trades/public-key").await?.public_key,
+    };
+    parse_public_key(&value).map_err(AppError::validation)
+}
+
+async fn fetch<T: DeserializeOwned>(server: &ServerArgs, path: &str) -> Result<T, AppError> {
+    send(server, reqwest::Method::GET, path, None::<&()>).await
+}
+
+async fn post<T: DeserializeOwned, B: Serialize>(server: &ServerArgs, path: &str, body: &B) -> Result<T, AppError> {
+    send(server, reqwest::Method::POST, path, Some(body)).await
+}
+
+async fn send<T: DeserializeOwned, B: Serialize>(
+    server: &ServerArgs,
+    method: reqwest::Method,
+    path: &str,
+    body: Option<&B>,
+) -> Result<T, AppError> {
+    let base = server.server.clone().unwrap_or_else(|| {
+        let settings = Settings::current();
+        let scheme = if settings.server.tls_cert.is_some() { "https" } else { "http" };
+        format!("{}://127.0.0.1:{}", scheme, settings.server.port)
+    });
+
+    let mut request = reqwest::Client::new().request(method, format!("{}{}", base.trim_end_matches('/'), path));
+    if let Some(body) = body {
+        request = request.json(body);
+    }
+    if let Some(account) = &server.account {
+        request = request.query(&[("account", account)]);
+    }
+    if let Some(token) = &server.token {
+        request = request.bearer_auth(token);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| AppError::Exchange(format!("Server at {} unreachable: {}", base, e)))?;
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+
+    if !status.is_success() {
+        let message = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|v| v["message"].as_str().map(str::to_string))
+            .unwrap_or(body);
+        return Err(match status.as_u16() {
+            401 => AppError::Unauthorized(message),
+            403 => AppError::Forbidden(message),
+            404 => AppError::NotFound(message),
+            _ => AppError::Exchange(format!("{}: {}", status, message)),
+        });
+    }
+
+    serde_json::from_str(&body).map_err(|e| AppError::Internal(format!("Unexpected response from {}: {}", path, e)))
+}
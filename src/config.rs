@@ -1,6 +1,92 @@
-use serde::Deserialize;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use utoipa::ToSchema;
 
-#[derive(Debug, Deserialize, Clone)]
+pub const SETTINGS_FILE: &str = "config/Settings";
+pub const ENV_PREFIX: &str = "RKD";
+pub const DEFAULT_ACCOUNT: &str = "default";
+
+static SETTINGS: OnceCell<RwLock<Arc<Settings>>> = OnceCell::new();
+static SOURCE: OnceCell<SettingsSource> = OnceCell::new();
+
+#[derive(Debug, Clone)]
+pub struct SettingsSource {
+    pub path: String,
+    pub profile: Option<String>,
+}
+
+impl Default for SettingsSource {
+    fn default() -> Self {
+        Self {
+            path: std::env::var("RKD_CONFIG").unwrap_or_else(|_| SETTINGS_FILE.to_string()),
+            profile: std::env::var("RKD_PROFILE").ok().filter(|p| !p.is_empty()),
+        }
+    }
+}
+
+impl SettingsSource {
+    // `--config <path>` and `--profile <name>` from the command line, falling back to RKD_CONFIG and RKD_PROFILE.
+    pub fn from_options(config: Option<String>, profile: Option<String>) -> Self {
+        let defaults = Self::default();
+        Self {
+            path: config
+                .unwrap_or(defaults.path)
+                .trim_end_matches(".toml")
+                .to_string(),
+            profile: profile.or(defaults.profile),
+        }
+    }
+
+    pub fn profile_path(&self) -> Option<String> {
+        self.profile.as_ref().map(|p| format!("{}.{}", self.path, p))
+    }
+
+    pub fn files(&self) -> Vec<String> {
+        std::iter::once(self.path.clone())
+            .chain(self.profile_path())
+            .map(|p| format!("{}.toml", p))
+            .collect()
+    }
+
+    pub fn install(self) {
+        let _ = SOURCE.set(self);
+    }
+
+    pub fn current() -> &'static SettingsSource {
+        SOURCE.get_or_init(SettingsSource::default)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default, ToSchema)]
+pub enum MarginType {
+    #[serde(rename = "ISOLATED", alias = "isolated")]
+    Isolated,
+    #[default]
+    #[serde(rename = "CROSSED", alias = "crossed", alias = "cross")]
+    Crossed,
+}
+
+impl fmt::Display for MarginType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            MarginType::Isolated => "ISOLATED",
+            MarginType::Crossed => "CROSSED",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SymbolSettings {
+    pub symbol: String,
+    pub leverage: Option<u32>,
+    pub boost_leverage: Option<u32>,
+    pub margin_type: Option<MarginType>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct BinanceSettings {
     pub base_url: String,
     pub future_url: String,
@@ -8,10 +94,365 @@ pub struct BinanceSettings {
     pub interval: String,
     pub limit: u32,
     pub leverage: u32,
+    #[serde(default = "default_boost_leverage")]
+    pub boost_leverage: u32,
+    #[serde(default)]
+    pub margin_type: MarginType,
+    #[serde(default)]
+    pub symbols: Vec<SymbolSettings>,
     pub decide: bool,
+    // Account whose credentials sign requests; set by `Settings::for_account`, never read from disk.
+    #[serde(skip, default = "default_account")]
+    pub account: String,
+}
+
+fn default_account() -> String {
+    DEFAULT_ACCOUNT.into()
+}
+
+fn default_boost_leverage() -> u32 {
+    2
+}
+
+impl BinanceSettings {
+    pub fn symbol_settings(&self, symbol: &str) -> Option<&SymbolSettings> {
+        self.symbols.iter().find(|s| s.symbol.eq_ignore_ascii_case(symbol))
+    }
+
+    pub fn leverage_for(&self, symbol: &str) -> u32 {
+        self.symbol_settings(symbol)
+            .and_then(|s| s.leverage)
+            .unwrap_or(self.leverage)
+    }
+
+    pub fn boost_leverage_for(&self, symbol: &str) -> u32 {
+        self.symbol_settings(symbol)
+            .and_then(|s| s.boost_leverage)
+            .unwrap_or(self.boost_leverage)
+    }
+
+    pub fn margin_type_for(&self, symbol: &str) -> MarginType {
+        self.symbol_settings(symbol)
+            .and_then(|s| s.margin_type)
+            .unwrap_or(self.margin_type)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LiquidationSettings {
+    #[serde(default = "default_min_distance_pct")]
+    pub min_distance_pct: f64,
+    #[serde(default = "default_max_margin_ratio")]
+    pub max_margin_ratio: f64,
+    #[serde(default)]
+    pub auto_deleverage: bool,
+    #[serde(default = "default_reduce_fraction")]
+    pub reduce_fraction: f64,
+    // Seconds after an automatic intervention before the same symbol can get another one.
+    #[serde(default = "default_intervention_cooldown_secs")]
+    pub cooldown_secs: u64,
+}
+
+impl Default for LiquidationSettings {
+    fn default() -> Self {
+        Self {
+            min_distance_pct: default_min_distance_pct(),
+            max_margin_ratio: default_max_margin_ratio(),
+            auto_deleverage: false,
+            reduce_fraction: default_reduce_fraction(),
+            cooldown_secs: default_intervention_cooldown_secs(),
+        }
+    }
+}
+
+fn default_min_distance_pct() -> f64 {
+    10.0
+}
+
+fn default_max_margin_ratio() -> f64 {
+    80.0
+}
+
+fn default_reduce_fraction() -> f64 {
+    0.5
+}
+
+fn default_intervention_cooldown_secs() -> u64 {
+    900
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FundingSettings {
+    #[serde(default)]
+    pub filter: bool,
+    #[serde(default = "default_max_adverse_rate")]
+    pub max_adverse_rate: f64,
+    #[serde(default)]
+    pub score: bool,
+}
+
+impl Default for FundingSettings {
+    fn default() -> Self {
+        Self {
+            filter: false,
+            max_adverse_rate: default_max_adverse_rate(),
+            score: false,
+        }
+    }
+}
+
+fn default_max_adverse_rate() -> f64 {
+    0.0005
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HistorySettings {
+    #[serde(default = "default_history_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_history_path")]
+    pub path: String,
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u32,
+}
+
+impl Default for HistorySettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_history_enabled(),
+            path: default_history_path(),
+            retention_days: default_retention_days(),
+        }
+    }
+}
+
+fn default_history_enabled() -> bool {
+    true
+}
+
+fn default_history_path() -> String {
+    "data/history".into()
+}
+
+fn default_retention_days() -> u32 {
+    30
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BlockchainSettings {
+    // Hex-encoded Ed25519 seed; generated on first start when the file doesn't exist.
+    #[serde(default = "default_signing_key")]
+    pub signing_key: String,
+    #[serde(default = "default_anchor_file")]
+    pub anchor_file: String,
+    #[serde(default = "default_anchor_interval_secs")]
+    pub anchor_interval_secs: u64,
+    #[serde(default = "default_checkpoint_interval_secs")]
+    pub checkpoint_interval_secs: u64,
+    #[serde(default = "default_checkpoint_file")]
+    pub checkpoint_file: String,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+impl Default for BlockchainSettings {
+    fn default() -> Self {
+        Self {
+            signing_key: default_signing_key(),
+            anchor_file: default_anchor_file(),
+            anchor_interval_secs: default_anchor_interval_secs(),
+            checkpoint_interval_secs: default_checkpoint_interval_secs(),
+            checkpoint_file: default_checkpoint_file(),
+        }
+    }
+}
+
+fn default_signing_key() -> String {
+    "data/blockchain.key".into()
+}
+
+fn default_anchor_file() -> String {
+    "data/anchors.jsonl".into()
+}
+
+fn default_anchor_interval_secs() -> u64 {
+    3600
+}
+
+fn default_checkpoint_interval_secs() -> u64 {
+    900
+}
+
+fn default_checkpoint_file() -> String {
+    "data/checkpoints.jsonl".into()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Viewer,
+    Operator,
+    Trader,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApiClient {
+    pub name: String,
+    pub role: Role,
+    pub token: Option<String>,
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AuthSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_max_clock_skew_secs")]
+    pub max_clock_skew_secs: u64,
+    #[serde(default)]
+    pub clients: Vec<ApiClient>,
+}
+
+impl Default for AuthSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_clock_skew_secs: default_max_clock_skew_secs(),
+            clients: vec![],
+        }
+    }
+}
+
+fn default_max_clock_skew_secs() -> u64 {
+    30
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ServerSettings {
+    #[serde(default = "default_bind_address")]
+    pub bind_address: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default = "default_allowed_origins")]
+    pub allowed_origins: Vec<String>,
+    #[serde(default = "default_allowed_methods")]
+    pub allowed_methods: Vec<String>,
+    #[serde(default = "default_allowed_headers")]
+    pub allowed_headers: Vec<String>,
+    pub workers: Option<usize>,
+    pub tls_cert: Option<String>,
+    pub tls_key: Option<String>,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            bind_address: default_bind_address(),
+            port: default_port(),
+            allowed_origins: default_allowed_origins(),
+            allowed_methods: default_allowed_methods(),
+            allowed_headers: default_allowed_headers(),
+            workers: None,
+            tls_cert: None,
+            tls_key: None,
+        }
+    }
+}
+
+fn default_bind_address() -> String {
+    "127.0.0.1".into()
+}
+
+fn default_port() -> u16 {
+    8080
+}
+
+fn default_allowed_origins() -> Vec<String> {
+    vec!["http://localhost:5173".into()]
+}
+
+fn default_allowed_methods() -> Vec<String> {
+    vec!["GET".into(), "POST".into(), "PUT".into()]
+}
+
+fn default_allowed_headers() -> Vec<String> {
+    vec![
+        "content-type".into(),
+        "authorization".into(),
+        "x-rkd-client".into(),
+        "x-rkd-timestamp".into(),
+        "x-rkd-nonce".into(),
+        "x-rkd-signature".into(),
+    ]
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CredentialProviderKind {
+    #[default]
+    Env,
+    File,
+    Keystore,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CredentialSettings {
+    #[serde(default)]
+    pub provider: CredentialProviderKind,
+    #[serde(default = "default_credentials_file")]
+    pub file: String,
+    #[serde(default = "default_keystore_file")]
+    pub keystore: String,
+    #[serde(default = "default_passphrase_env")]
+    pub passphrase_env: String,
+    #[serde(default)]
+    pub env_prefix: String,
+}
+
+impl Default for CredentialSettings {
+    fn default() -> Self {
+        Self {
+            provider: CredentialProviderKind::Env,
+            file: default_credentials_file(),
+            keystore: default_keystore_file(),
+            passphrase_env: default_passphrase_env(),
+            env_prefix: String::new(),
+        }
+    }
+}
+
+fn default_credentials_file() -> String {
+    "config/credentials.toml".into()
+}
+
+fn default_keystore_file() -> String {
+    "config/credentials.keystore".into()
+}
+
+fn default_passphrase_env() -> String {
+    "RKD_KEYSTORE_PASSPHRASE".into()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccountSettings {
+    pub name: String,
+    #[serde(default)]
+    pub symbols: Vec<String>,
+    pub money: Option<f64>,
+    pub leverage: Option<u32>,
+    pub boost_leverage: Option<u32>,
+    pub limit_operations: Option<usize>,
+    pub credentials: Option<CredentialSettings>,
+}
+
+impl AccountSettings {
+    // Without a [accounts.credentials] table the keys come from <NAME>_BINANCE_API_KEY/SECRET.
+    pub fn credential_settings(&self) -> CredentialSettings {
+        self.credentials.clone().unwrap_or_else(|| CredentialSettings {
+            env_prefix: format!("{}_", self.name.to_uppercase()),
+            ..CredentialSettings::default()
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Settings {
     pub binance: BinanceSettings,
     pub spy: bool,
@@ -20,15 +461,107 @@ pub struct Settings {
     pub money: f64,
     pub gain: f64,
     pub show_details_monitor: bool,
+    #[serde(default)]
+    pub liquidation: LiquidationSettings,
+    #[serde(default)]
+    pub funding: FundingSettings,
+    #[serde(default)]
+    pub history: HistorySettings,
+    #[serde(default)]
+    pub blockchain: BlockchainSettings,
+    #[serde(default)]
+    pub auth: AuthSettings,
+    #[serde(default)]
+    pub server: ServerSettings,
+    #[serde(default)]
+    pub credentials: CredentialSettings,
+    #[serde(default)]
+    pub accounts: Vec<AccountSettings>,
 }
 
 impl Settings {
-    pub fn load() -> Self {
-        config::Config::builder()
-            .add_source(config::File::with_name("config/Settings").required(true))
+    pub fn account_names(&self) -> Vec<String> {
+        if self.accounts.is_empty() {
+            return vec![DEFAULT_ACCOUNT.to_string()];
+        }
+        self.accounts.iter().map(|a| a.name.clone()).collect()
+    }
+
+    // Effective settings for one account: its overrides applied on top of the shared values.
+    pub fn for_account(&self, name: &str) -> Option<Settings> {
+        let mut settings = self.clone();
+
+        if self.accounts.is_empty() {
+            if name != DEFAULT_ACCOUNT {
+                return None;
+            }
+        } else {
+            let account = self.accounts.iter().find(|a| a.name == name)?;
+            if !account.symbols.is_empty() {
+                settings.cryptos = account.symbols.clone();
+            }
+            if let Some(money) = account.money {
+                settings.money = money;
+            }
+            if let Some(leverage) = account.leverage {
+                settings.binance.leverage = leverage;
+            }
+            if let Some(boost_leverage) = account.boost_leverage {
+                settings.binance.boost_leverage = boost_leverage;
+            }
+            if let Some(limit_operations) = account.limit_operations {
+                settings.limit_operations = limit_operations;
+            }
+        }
+
+        settings.binance.account = name.to_string();
+        Some(settings)
+    }
+
+    pub fn all_accounts(&self) -> Vec<Settings> {
+        self.account_names()
+            .iter()
+            .filter_map(|name| self.for_account(name))
+            .collect()
+    }
+
+    pub fn read_from_disk() -> Result<Self, String> {
+        let source = SettingsSource::current();
+        let mut builder = config::Config::builder()
+            .add_source(config::File::with_name(&source.path).required(true));
+
+        if let Some(profile_path) = source.profile_path() {
+            builder = builder.add_source(config::File::with_name(&profile_path).required(true));
+        }
+
+        builder
+            .add_source(
+                config::Environment::with_prefix(ENV_PREFIX)
+                    .separator("__")
+                    .try_parsing(true)
+                    .list_separator(",")
+                    .with_list_parse_key("cryptos")
+                    .with_list_parse_key("server.allowed_origins")
+                    .with_list_parse_key("server.allowed_methods")
+                    .with_list_parse_key("server.allowed_headers"),
+            )
             .build()
-            .expect("Failed to load configuration file")
+            .map_err(|e| format!("Failed to load configuration file: {}", e))?
             .try_deserialize()
-            .expect("Failed to deserialize configuration")
+            .map_err(|e| format!("Failed to deserialize configuration: {}", e))
+    }
+
+    // Returns the settings loaded at startup or by the last successful reload.
+    pub fn current() -> Arc<Self> {
+        let lock = SETTINGS.get_or_init(|| {
+            let settings = Settings::read_from_disk().expect("Failed to load configuration");
+            RwLock::new(Arc::new(settings))
+        });
+        lock.read().unwrap().clone()
+    }
+
+    pub fn replace(settings: Settings) {
+        let lock = SETTINGS.get_or_init(|| RwLock::new(Arc::new(settings.clone())));
+        *lock.write().unwrap() = Arc::new(settings);
     }
 }
@@ -1,21 +1,355 @@
+use crate::config::{CredentialProviderKind, CredentialSettings, Settings, DEFAULT_ACCOUNT};
+use crate::error::AppError;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use once_cell::sync::OnceCell;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
 use std::env;
-use once_cell::sync::Lazy;
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::ops::Deref;
 use std::sync::Arc;
 
+const KEYSTORE_VERSION: u32 = 1;
+const KEYSTORE_ITERATIONS: u32 = 200_000;
+
+static CREDENTIAL: OnceCell<Arc<Credential>> = OnceCell::new();
+static ACCOUNT_CREDENTIALS: OnceCell<HashMap<String, BinanceCredential>> = OnceCell::new();
+
+// Wraps a credential value so it never shows up in Debug output or logs.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Deref for Secret {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\"***\"")
+    }
+}
+
 #[derive(Debug, Clone)]
+pub struct BinanceCredential {
+    pub key: Secret,
+    pub secret: Secret,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct Credential {
-    pub key: String,
-    pub secret: String,
-    pub open_ai_key: String,
+    pub binance: Option<BinanceCredential>,
+    pub open_ai_key: Option<Secret>,
+}
+
+// Raw values as read by a provider; every service is optional.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CredentialValues {
+    pub binance_api_key: Option<Secret>,
+    pub binance_api_secret: Option<Secret>,
+    pub open_api_key: Option<Secret>,
+}
+
+pub trait CredentialProvider {
+    fn name(&self) -> String;
+    fn load(&self) -> Result<CredentialValues, String>;
+}
+
+pub struct EnvProvider {
+    pub prefix: String,
+}
+
+impl CredentialProvider for EnvProvider {
+    fn name(&self) -> String {
+        if self.prefix.is_empty() {
+            "env".into()
+        } else {
+            format!("env ({}*)", self.prefix)
+        }
+    }
+
+    fn load(&self) -> Result<CredentialValues, String> {
+        let var = |name: &str| {
+            env::var(format!("{}{}", self.prefix, name))
+                .ok()
+                .filter(|v| !v.is_empty())
+                .map(Secret)
+        };
+        Ok(CredentialValues {
+            binance_api_key: var("BINANCE_API_KEY"),
+            binance_api_secret: var("BINANCE_API_SECRET"),
+            open_api_key: var("OPEN_API_KEY"),
+        })
+    }
+}
+
+pub struct FileProvider {
+    pub path: String,
+}
+
+impl CredentialProvider for FileProvider {
+    fn name(&self) -> String {
+        format!("file ({})", self.path)
+    }
+
+    fn load(&self) -> Result<CredentialValues, String> {
+        check_permissions(&self.path)?;
+
+        config::Config::builder()
+            .add_source(config::File::new(&self.path, config::FileFormat::Toml))
+            .build()
+            .and_then(|c| c.try_deserialize())
+            .map_err(|e| format!("Failed to read credentials file {}: {}", self.path, e))
+    }
+}
+
+pub struct KeystoreProvider {
+    pub path: String,
+    pub passphrase_env: String,
 }
 
-pub static CREDENTIAL: Lazy<Arc<Credential>> = Lazy::new(|| {
-    let key = env::var("BINANCE_API_KEY").expect("BINANCE_API_KEY nao definida");
-    let secret = env::var("BINANCE_API_SECRET").expect("BINANCE_API_SECRET nao definida");
-    let open_ai_key = env::var("OPEN_API_KEY").expect("OPEN_API_KEY nao definida");
-    Arc::new(Credential { key, secret, open_ai_key })
-});
+#[derive(Serialize, Deserialize)]
+struct Keystore {
+    version: u32,
+    iterations: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl CredentialProvider for KeystoreProvider {
+    fn name(&self) -> String {
+        format!("keystore ({})", self.path)
+    }
+
+    fn load(&self) -> Result<CredentialValues, String> {
+        check_permissions(&self.path)?;
+
+        let passphrase = env::var(&self.passphrase_env)
+            .map_err(|_| format!("{} must hold the keystore passphrase", self.passphrase_env))?;
+        let content = fs::read_to_string(&self.path)
+            .map_err(|e| format!("Failed to read keystore {}: {}", self.path, e))?;
+        let keystore: Keystore = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid keystore {}: {}", self.path, e))?;
+
+        if keystore.version != KEYSTORE_VERSION {
+            return Err(format!("Unsupported keystore version {}", keystore.version));
+        }
+
+        let decode = |field: &str, value: &str| {
+            hex::decode(value).map_err(|e| format!("Invalid keystore {}: {}", field, e))
+        };
+        let salt = decode("salt", &keystore.salt)?;
+        let nonce = decode("nonce", &keystore.nonce)?;
+        let ciphertext = decode("ciphertext", &keystore.ciphertext)?;
+        if nonce.len() != 12 {
+            return Err("Invalid keystore nonce length".into());
+        }
+
+        let cipher = keystore_cipher(&passphrase, &salt, keystore.iterations);
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| "Failed to unlock keystore: wrong passphrase or corrupted file".to_string())?;
+
+        serde_json::from_slice(&plaintext).map_err(|e| format!("Invalid keystore content: {}", e))
+    }
+}
+
+fn keystore_cipher(passphrase: &str, salt: &[u8], iterations: u32) -> ChaCha20Poly1305 {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, iterations, &mut key);
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+}
+
+// Encrypts the given values into a keystore file readable only by the owner.
+pub fn seal_keystore(path: &str, values: &CredentialValues, passphrase: &str) -> Result<(), String> {
+    let mut salt = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let plaintext = serde_json::to_vec(values).map_err(|e| e.to_string())?;
+    let ciphertext = keystore_cipher(passphrase, &salt, KEYSTORE_ITERATIONS)
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_ref())
+        .map_err(|_| "Failed to encrypt keystore".to_string())?;
+
+    let keystore = Keystore {
+        version: KEYSTORE_VERSION,
+        iterations: KEYSTORE_ITERATIONS,
+        salt: hex::encode(salt),
+        nonce: hex::encode(nonce),
+        ciphertext: hex::encode(ciphertext),
+    };
+    let content = serde_json::to_string_pretty(&keystore).map_err(|e| e.to_string())?;
+
+    open_private(path, false)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .map_err(|e| format!("Failed to write keystore {}: {}", path, e))?;
+    // A keystore sealed over an older file keeps that file's mode, so it is narrowed as well.
+    restrict_permissions(path)
+}
+
+// Opens a file for writing that only its owner can read from the moment it exists, instead of
+// narrowing its mode after the secret is already on disk. With `create_new` an existing file is
+// an error rather than being overwritten.
+#[cfg(unix)]
+pub fn open_private(path: &str, create_new: bool) -> std::io::Result<fs::File> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).mode(0o600);
+    if create_new {
+        options.create_new(true);
+    } else {
+        options.create(true).truncate(true);
+    }
+    options.open(path)
+}
+
+#[cfg(not(unix))]
+pub fn open_private(path: &str, create_new: bool) -> std::io::Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true);
+    if create_new {
+        options.create_new(true);
+    } else {
+        options.create(true).truncate(true);
+    }
+    options.open(path)
+}
+
+#[cfg(unix)]
+pub fn check_permissions(path: &str) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = fs::metadata(path)
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?
+        .permissions()
+        .mode();
+    if mode & 0o077 != 0 {
+        return Err(format!(
+            "{} is accessible by group/others (mode {:o}), run chmod 600 {}",
+            path,
+            mode & 0o777,
+            path
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn check_permissions(_path: &str) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(unix)]
+pub fn restrict_permissions(path: &str) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+        .map_err(|e| format!("Failed to restrict permissions of {}: {}", path, e))
+}
+
+#[cfg(not(unix))]
+pub fn restrict_permissions(_path: &str) -> Result<(), String> {
+    Ok(())
+}
+
+pub fn provider_for(settings: &CredentialSettings) -> Box<dyn CredentialProvider> {
+    match settings.provider {
+        CredentialProviderKind::Env => Box::new(EnvProvider {
+            prefix: settings.env_prefix.clone(),
+        }),
+        CredentialProviderKind::File => Box::new(FileProvider {
+            path: settings.file.clone(),
+        }),
+        CredentialProviderKind::Keystore => Box::new(KeystoreProvider {
+            path: settings.keystore.clone(),
+            passphrase_env: settings.passphrase_env.clone(),
+        }),
+    }
+}
+
+impl From<CredentialValues> for Credential {
+    fn from(values: CredentialValues) -> Self {
+        let binance = match (values.binance_api_key, values.binance_api_secret) {
+            (Some(key), Some(secret)) => Some(BinanceCredential { key, secret }),
+            _ => None,
+        };
+        Credential {
+            binance,
+            open_ai_key: values.open_api_key,
+        }
+    }
+}
+
+// Loads credentials once at startup and reports which features stay disabled.
+pub fn init_credentials(settings: &Settings) -> Result<(), String> {
+    let provider = provider_for(&settings.credentials);
+    let credential: Credential = provider.load()?.into();
+    println!("[credentials] Loaded from {}", provider.name());
+
+    let mut accounts = HashMap::new();
+    if settings.accounts.is_empty() {
+        if let Some(binance) = credential.binance.clone() {
+            accounts.insert(DEFAULT_ACCOUNT.to_string(), binance);
+        }
+    } else {
+        for account in &settings.accounts {
+            let provider = provider_for(&account.credential_settings());
+            let values = provider
+                .load()
+                .map_err(|e| format!("Account {}: {}", account.name, e))?;
+            println!("[credentials] Account {} loaded from {}", account.name, provider.name());
+            if let Some(binance) = Credential::from(values).binance {
+                accounts.insert(account.name.clone(), binance);
+            }
+        }
+    }
+
+    for name in settings.account_names() {
+        if !accounts.contains_key(&name) {
+            eprintln!(
+                "[credentials] Binance keys missing for account {}: orders, balances, leverage, positions, funding income and liquidation monitoring are disabled for it",
+                name
+            );
+        }
+    }
+    if credential.open_ai_key.is_none() {
+        eprintln!("[credentials] OPEN_API_KEY missing: /monitors/assistant is disabled");
+    }
+
+    let _ = CREDENTIAL.set(Arc::new(credential));
+    let _ = ACCOUNT_CREDENTIALS.set(accounts);
+    Ok(())
+}
+
+pub fn has_binance_credentials(account: &str) -> bool {
+    ACCOUNT_CREDENTIALS
+        .get()
+        .is_some_and(|accounts| accounts.contains_key(account))
+}
+
+pub fn binance_credentials(account: &str) -> Result<BinanceCredential, AppError> {
+    ACCOUNT_CREDENTIALS
+        .get()
+        .and_then(|accounts| accounts.get(account))
+        .cloned()
+        .ok_or_else(|| AppError::Config(format!("Binance credentials not configured for account {}", account)))
+}
 
-pub fn get_credentials() -> Arc<Credential> {
-    CREDENTIAL.clone()
+pub fn open_ai_key() -> Result<Secret, AppError> {
+    CREDENTIAL
+        .get()
+        .and_then(|credential| credential.open_ai_key.clone())
+        .ok_or_else(|| AppError::Config("OpenAI key not configured (OPEN_API_KEY)".into()))
 }
@@ -13,8 +13,26 @@ fn parse(value: &str) -> f64 {
     value.parse::<f64>().unwrap_or(0.0)
 }
 
+// Positive when the side implied by the bias receives funding, negative when it pays.
+fn funding_carry(trade: &Trade) -> f64 {
+    let rate = parse(&trade.funding_rate);
+    match trade.bias {
+        Bias::Bullish => -rate,
+        Bias::Bearish => rate,
+        Bias::None => 0.0,
+    }
+}
+
+fn report_decision(symbol: &str, outcome: Result<String, String>) {
+    match outcome {
+        Ok(message) => println!("Decision for {}: {}", symbol, message),
+        Err(e) => eprintln!("Decision for {} failed: {}", symbol, e),
+    }
+}
+
 pub async fn process_existing_cryptos(trades: &[Trade], settings: &Settings) {
-    let current_symbols = get_current_blockchain_symbols();
+    let account = settings.binance.account.as_str();
+    let current_symbols = get_current_blockchain_symbols(account);
     let existing_trades: Vec<Trade> = trades
         .iter()
         .filter(|t| current_symbols.contains(&t.symbol))
@@ -22,18 +40,19 @@ pub async fn process_existing_cryptos(trades: &[Trade], settings: &Settings) {
         .collect();
 
     for trade in &existing_trades {
-        let was_added = add_trade_block(trade.clone());
+        let was_added = add_trade_block(account, trade.clone());
         if was_added && settings.binance.decide {
-            decide(&trade.symbol, &settings.binance);
+            report_decision(&trade.symbol, decide(&trade.symbol, &settings.binance).await);
             remove_if_out_of_zone(trade, settings, &settings.binance).await;
         }
     }
 }
 
 pub async fn choose_candidate_cryptos(trades: Vec<Trade>, settings: &Settings) {
-    let current_symbols = get_current_blockchain_symbols();
+    let account = settings.binance.account.as_str();
+    let current_symbols = get_current_blockchain_symbols(account);
 
-    if is_blockchain_limit_reached() {
+    if is_blockchain_limit_reached(account) {
         return;
     }
 
@@ -58,6 +77,9 @@ pub async fn choose_candidate_cryptos(trades: Vec<Trade>, settings: &Settings) {
                 _ => false,
             }
         })
+        .filter(|t| {
+            !settings.funding.filter || funding_carry(t) >= -settings.funding.max_adverse_rate
+        })
         .collect();
 
     let mut bullish_z7 = filtered
@@ -100,13 +122,20 @@ pub async fn choose_candidate_cryptos(trades: Vec<Trade>, settings: &Settings) {
         return;
     }
 
-    if let Some(selected) = {
+    let selected = if settings.funding.score {
+        final_candidates
+            .iter()
+            .max_by(|a, b| funding_carry(a).partial_cmp(&funding_carry(b)).unwrap_or(std::cmp::Ordering::Equal))
+            .cloned()
+    } else {
         let mut rng = thread_rng();
         final_candidates.choose(&mut rng).cloned()
-    } {
-        let was_added = add_trade_block(selected.clone());
+    };
+
+    if let Some(selected) = selected {
+        let was_added = add_trade_block(account, selected.clone());
         if was_added && settings.binance.decide {
-            decide(&selected.symbol, &settings.binance);
+            report_decision(&selected.symbol, decide(&selected.symbol, &settings.binance).await);
             remove_if_out_of_zone(&selected, settings, &settings.binance).await;
         }
     }
@@ -2,112 +2,133 @@ use crate::blockchain::get_last_trade_for;
 use crate::dto::{Bias, TradeStatus};
 use crate::order::{execute_future_order, close_all_positions};
 use crate::config::BinanceSettings;
+use crate::executor::run_serialized;
 use crate::leverage::set_leverage_with_value;
+use crate::order_state::chain_client_order_id;
 
-pub fn decide(symbol: &str, binance_settings: &BinanceSettings) {
-    let trade = match get_last_trade_for(symbol) {
+pub async fn decide(symbol: &str, binance_settings: &BinanceSettings) -> Result<String, String> {
+    let account = binance_settings.account.as_str();
+    let trade = match get_last_trade_for(account, symbol) {
         Some(t) => t,
         None => {
             println!("No trades found for decision for symbol: {}", symbol);
-            return;
+            return Ok("no trade".to_string());
         }
     };
 
-    let bias = trade.bias.clone();
-    let status = trade.status.clone();
     let symbol = &trade.symbol;
 
-    match (bias, status) {
-        (_, None) => {
-            let binance = binance_settings.clone();
-            let symbol = symbol.clone();
-            tokio::spawn(async move {
-                match close_all_positions(&binance, &symbol).await {
-                    Ok(closed) => println!("All positions closed (status None): {:?}", closed),
-                    Err(e) => eprintln!("Error closing positions (status None): {}", e),
-                }
-                if let Err(e) = set_leverage_with_value(&binance, &symbol, 1).await {
-                    eprintln!("Error setting leverage to 1 (status None): {}", e);
-                }
-            });
+    match planned_action(&trade.bias, trade.status.as_ref()) {
+        Action::Open(side) => open_order(binance_settings, symbol, side).await,
+
+        Action::TargetLeverage => {
+            let leverage = binance_settings.leverage_for(symbol);
+            run_serialized(account, symbol, "set_leverage", async {
+                set_leverage_with_value(binance_settings, symbol, leverage)
+                    .await
+                    .map(|r| format!("Leverage set to {}x (target zone)", r.leverage))
+                    .map_err(|e| format!("Error setting leverage to {} (target zone): {}", leverage, e))
+            })
+            .await
         }
 
+        Action::Close { boost } => {
+            let leverage = if boost {
+                binance_settings.boost_leverage_for(symbol)
+            } else {
+                binance_settings.leverage_for(symbol)
+            };
+            close_and_set_leverage(binance_settings, symbol, leverage).await
+        }
+
+        Action::Hold => {
+            println!(
+                "No action taken for status: {:?} with bias: {:?} (symbol: {})",
+                trade.status,
+                trade.bias,
+                trade.symbol
+            );
+            Ok("no action".to_string())
+        }
+    }
+}
+
+// What the strategy does when a block is added, apart from the exchange calls so the
+// backtest follows exactly the same rules.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Open(&'static str),
+    // Closes the position and resets the leverage, to the boost value when `boost`.
+    Close { boost: bool },
+    TargetLeverage,
+    Hold,
+}
+
+pub fn planned_action(bias: &Bias, status: Option<&TradeStatus>) -> Action {
+    match (bias, status) {
+        (_, None) => Action::Close { boost: false },
+
         (Bias::Bullish, Some(TradeStatus::InZone7))
         | (Bias::Bullish, Some(TradeStatus::InZone3))
-        | (Bias::Bullish, Some(TradeStatus::LongZone3)) => {
-            let binance = binance_settings.clone();
-            let symbol = symbol.clone();
-            tokio::spawn(async move {
-                match execute_future_order(&binance, "BUY", &symbol).await {
-                    Ok(order) => println!("BUY order executed: {:?}", order),
-                    Err(e) => eprintln!("Error executing BUY order: {}", e),
-                }
-            });
-        }
+        | (Bias::Bullish, Some(TradeStatus::LongZone3)) => Action::Open("BUY"),
 
         (Bias::Bearish, Some(TradeStatus::InZone1))
         | (Bias::Bearish, Some(TradeStatus::InZone5))
-        | (Bias::Bearish, Some(TradeStatus::ShortZone5)) => {
-            let binance = binance_settings.clone();
-            let symbol = symbol.clone();
-            tokio::spawn(async move {
-                match execute_future_order(&binance, "SELL", &symbol).await {
-                    Ok(order) => println!("SELL order executed: {:?}", order),
-                    Err(e) => eprintln!("Error executing SELL order: {}", e),
-                }
-            });
-        }
+        | (Bias::Bearish, Some(TradeStatus::ShortZone5)) => Action::Open("SELL"),
 
         (Bias::Bullish, Some(TradeStatus::TargetZone7))
-        | (Bias::Bearish, Some(TradeStatus::TargetZone1)) => {
-            let binance = binance_settings.clone();
-            let symbol = symbol.clone();
-            tokio::spawn(async move {
-                if let Err(e) = set_leverage_with_value(&binance, &symbol, 1).await {
-                    eprintln!("Error setting leverage to 1 (target zone): {}", e);
-                }
-            });
-        }
+        | (Bias::Bearish, Some(TradeStatus::TargetZone1)) => Action::TargetLeverage,
 
         (Bias::Bullish, Some(TradeStatus::OutZone5))
         | (Bias::Bullish, Some(TradeStatus::PrepareZone1))
         | (Bias::Bearish, Some(TradeStatus::OutZone3))
-        | (Bias::Bearish, Some(TradeStatus::PrepareZone7)) => {
-            let binance = binance_settings.clone();
-            let symbol = symbol.clone();
-            tokio::spawn(async move {
-                match close_all_positions(&binance, &symbol).await {
-                    Ok(closed) => println!("Closed positions (lev 1): {:?}", closed),
-                    Err(e) => eprintln!("Error closing positions: {}", e),
-                }
-                if let Err(e) = set_leverage_with_value(&binance, &symbol, 1).await {
-                    eprintln!("Error setting leverage to 1: {}", e);
-                }
-            });
-        }
+        | (Bias::Bearish, Some(TradeStatus::PrepareZone7)) => Action::Close { boost: false },
 
         (Bias::Bullish, Some(TradeStatus::PrepareZone1Long))
-        | (Bias::Bearish, Some(TradeStatus::PrepareZone7Short)) => {
-            let binance = binance_settings.clone();
-            let symbol = symbol.clone();
-            tokio::spawn(async move {
-                match close_all_positions(&binance, &symbol).await {
-                    Ok(closed) => println!("Closed positions (lev 2): {:?}", closed),
-                    Err(e) => eprintln!("Error closing positions: {}", e),
-                }
-                if let Err(e) = set_leverage_with_value(&binance, &symbol, 2).await {
-                    eprintln!("Error setting leverage to 2: {}", e);
-                }
-            });
-        }
+        | (Bias::Bearish, Some(TradeStatus::PrepareZone7Short)) => Action::Close { boost: true },
 
-        _ => {
-            println!(
-                "No action taken for status: {:?} with bias: {:?} (symbol: {})",
-                trade.status,
-                trade.bias,
-                trade.symbol
-            );
-        }
+        _ => Action::Hold,
     }
 }
+
+async fn open_order(
+    binance: &BinanceSettings,
+    symbol: &str,
+    side: &str,
+) -> Result<String, String> {
+    let order_id = chain_client_order_id(&binance.account, symbol, &side.to_lowercase());
+
+    run_serialized(&binance.account, symbol, side, async {
+        execute_future_order(binance, side, symbol, &order_id)
+            .await
+            .map(|order| format!("{} order executed: {:?}", side, order))
+            .map_err(|e| format!("Error executing {} order: {}", side, e))
+    })
+    .await
+}
+
+// Closing and changing leverage run as one serialized action so no entry can slip in between.
+async fn close_and_set_leverage(
+    binance: &BinanceSettings,
+    symbol: &str,
+    leverage: u32,
+) -> Result<String, String> {
+    let order_id = chain_client_order_id(&binance.account, symbol, "close");
+
+    run_serialized(&binance.account, symbol, "close_and_set_leverage", async {
+        let closed = close_all_positions(binance, symbol, &order_id)
+            .await
+            .map_err(|e| format!("Error closing positions: {}", e))?;
+
+        set_leverage_with_value(binance, symbol, leverage)
+            .await
+            .map_err(|e| format!("Error setting leverage to {}: {}", leverage, e))?;
+
+        Ok(format!(
+            "Closed {} position(s), leverage set to {}x",
+            closed.len(),
+            leverage
+        ))
+    })
+    .await
+}
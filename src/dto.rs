@@ -1,7 +1,11 @@
+use crate::blockchain::TradeBlock;
+use crate::config::MarginType;
+use crate::error::AppError;
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use std::fmt;
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
 pub enum Bias {
     Bullish,
     Bearish,
@@ -19,7 +23,19 @@ impl fmt::Display for Bias {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl Bias {
+    // Query parameter form, case-insensitive.
+    pub fn parse(value: &str) -> Result<Bias, AppError> {
+        match value.to_lowercase().as_str() {
+            "bullish" => Ok(Bias::Bullish),
+            "bearish" => Ok(Bias::Bearish),
+            "none" => Ok(Bias::None),
+            _ => Err(AppError::validation(format!("Unknown bias '{}'", value))),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Candlestick {
     pub open_time: u64,
     pub open_price: String,
@@ -35,7 +51,7 @@ pub struct Candlestick {
     pub ignore: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct Trade {
     pub symbol: String,
     pub current_price: String,
@@ -61,9 +77,17 @@ pub struct Trade {
     pub number_of_trades: String,
     pub taker_buy_base_asset_volume: String,
     pub taker_buy_quote_asset_volume: String,
+    #[serde(default = "default_funding_rate")]
+    pub funding_rate: String,
+    #[serde(default)]
+    pub next_funding_time: u64,
+}
+
+fn default_funding_rate() -> String {
+    "0.0".into()
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
 pub enum TradeStatus {
     InZone7,
     OutZone5,
@@ -107,7 +131,7 @@ pub struct LotSizeInfo {
     pub step_size: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct BalanceResponse {
     pub asset: String,
 
@@ -118,13 +142,16 @@ pub struct BalanceResponse {
     pub available: String,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
 pub struct OrderResponse {
     pub symbol: String,
 
     #[serde(rename = "orderId")]
     pub order_id: u64,
 
+    #[serde(rename = "clientOrderId", default)]
+    pub client_order_id: String,
+
     pub status: String,
     pub side: String,
     pub price: String,
@@ -148,32 +175,223 @@ pub struct OrderResponse {
     pub update_time: u64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
+pub struct PositionRisk {
+    pub symbol: String,
+
+    #[serde(rename = "positionAmt")]
+    pub position_amt: String,
+
+    #[serde(rename = "markPrice")]
+    pub mark_price: String,
+
+    #[serde(rename = "unRealizedProfit")]
+    pub unrealized_profit: String,
+
+    #[serde(rename = "liquidationPrice")]
+    pub liquidation_price: String,
+
+    pub leverage: String,
+
+    #[serde(rename = "maxNotionalValue", default)]
+    pub max_notional_value: String,
+
+    #[serde(rename = "marginType")]
+    pub margin_type: String,
+
+    #[serde(rename = "isolatedMargin", default)]
+    pub isolated_margin: String,
+}
+
+// The account-wide margin of /fapi/v2/account, which backs every cross position.
+#[derive(Debug, Deserialize, Clone)]
+pub struct FuturesAccount {
+    #[serde(rename = "totalMaintMargin")]
+    pub total_maint_margin: String,
+
+    #[serde(rename = "totalMarginBalance")]
+    pub total_margin_balance: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LeverageBracketResponse {
+    pub symbol: String,
+    pub brackets: Vec<LeverageBracket>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LeverageBracket {
+    pub bracket: u32,
+
+    #[serde(rename = "initialLeverage")]
+    pub initial_leverage: u32,
+
+    #[serde(rename = "notionalCap")]
+    pub notional_cap: f64,
+
+    #[serde(rename = "notionalFloor")]
+    pub notional_floor: f64,
+
+    #[serde(rename = "maintMarginRatio")]
+    pub maint_margin_ratio: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LeverageStateResponse {
+    pub symbol: String,
+    pub leverage: u32,
+    pub margin_type: MarginType,
+    pub max_notional_value: f64,
+    pub max_leverage: Option<u32>,
+    pub position_amt: f64,
+    pub configured_leverage: u32,
+    pub configured_boost_leverage: u32,
+    pub configured_margin_type: MarginType,
+}
+
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct LiquidationRisk {
+    pub symbol: String,
+    pub position_amt: f64,
+    pub mark_price: f64,
+    pub liquidation_price: Option<f64>,
+    pub distance_pct: Option<f64>,
+    pub margin_ratio: f64,
+    pub leverage: u32,
+    pub margin_type: String,
+    pub at_risk: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub enum InterventionKind {
+    ReduceLeverage { from: u32, to: u32 },
+    ReducePosition { fraction: f64, quantity: String },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct RiskIntervention {
+    pub kind: InterventionKind,
+    pub mark_price: f64,
+    pub liquidation_price: Option<f64>,
+    pub distance_pct: Option<f64>,
+    pub margin_ratio: f64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct FundingRate {
+    pub symbol: String,
+
+    #[serde(rename = "lastFundingRate")]
+    pub last_funding_rate: String,
+
+    #[serde(rename = "nextFundingTime")]
+    pub next_funding_time: u64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct IncomeRecord {
+    pub symbol: String,
+    pub income: String,
+    pub time: u64,
+}
+
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct FundingPnlResponse {
+    pub symbol: String,
+    pub since: u64,
+    pub funding_rate: f64,
+    pub next_funding_time: u64,
+    pub accumulated_funding: f64,
+    pub funding_payments: usize,
+    pub last_funding_payment: Option<u64>,
+    pub unrealized_profit: f64,
+    pub net_pnl: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct OpenOrderRequest {
     pub side: String,
     pub symbol: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct SymbolRequest {
     pub symbol: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LeverageRequest {
+    pub symbol: String,
+    /// Defaults to the leverage configured for the symbol.
+    pub leverage: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AccountQuery {
+    pub account: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct EventsQuery {
+    /// Comma-separated topics: monitor, blocks, orders, risk. Defaults to all.
+    pub topics: Option<String>,
+    /// Only events of this account. Defaults to every account.
+    pub account: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AccountSummary {
+    pub name: String,
+    pub symbols: Vec<String>,
+    pub money: f64,
+    pub leverage: u32,
+    pub boost_leverage: u32,
+    pub limit_operations: usize,
+    pub open_chains: Vec<String>,
+    pub credentials_configured: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub code: String,
+    pub message: String,
+    pub details: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ChainValidityResponse {
+    pub symbol: String,
+    pub valid: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PublicKeyResponse {
+    pub algorithm: String,
+    /// Hex-encoded key that signs every block hash and anchor record.
+    pub public_key: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AssistantReply {
+    pub response: String,
+}
+
 //
 // MONITORAMENTO (JSON) DTOs
 //
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Clone, ToSchema)]
 pub struct TradeMonitorResponse {
     pub timestamp: String,
     pub trades: Vec<TradeMonitorItem>,
     pub zone_distribution: Vec<ZoneCount>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Clone, ToSchema)]
 pub struct TradeMonitorItem {
     pub symbol: String,
     pub zone: Option<String>,
+    pub bias: Bias,
     pub performance_24: f64,
     pub performance_btc_24: f64,
     pub amplitude_ma_200: f64,
@@ -184,6 +402,8 @@ pub struct TradeMonitorItem {
     pub trades_count: f64,
     pub taker_buy_base_volume: f64,
     pub taker_buy_quote_volume: f64,
+    pub funding_rate: f64,
+    pub next_funding_time: u64,
     pub is_active: bool,
 
     pub logo: Option<String>,
@@ -194,12 +414,184 @@ pub struct TradeMonitorItem {
     pub technical_doc: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct MonitorQuery {
+    /// Account name, defaults to the first one.
+    pub account: Option<String>,
+    /// `json` (default), `text`, `csv` or `markdown`.
+    pub format: Option<String>,
+    /// Send the text formats as a file attachment.
+    #[serde(default)]
+    pub download: bool,
+    /// Include the volume columns in the text formats; defaults to `show_details_monitor`.
+    pub details: Option<bool>,
+    /// Column to sort by, e.g. `performance_24`, `quote_volume`, `zone` or `symbol`.
+    pub sort: Option<String>,
+    /// `asc` or `desc` (default).
+    pub order: Option<String>,
+    /// Comma-separated zones to keep, e.g. `Z1,Z7`.
+    pub zone: Option<String>,
+    /// `Bullish`, `Bearish` or `None`.
+    pub bias: Option<String>,
+    /// Only symbols with an open chain.
+    #[serde(default)]
+    pub active_only: bool,
+    /// Minimum 24h quote volume.
+    pub min_quote_volume: Option<f64>,
+    /// 1-based page number.
+    pub page: Option<usize>,
+    /// Items per page, at most 500.
+    pub page_size: Option<usize>,
+    /// Run a fresh scan instead of serving the scheduler's last snapshot.
+    #[serde(default)]
+    pub refresh: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TradeMonitorPage {
+    pub timestamp: String,
+    /// Items left after the filters, before paging.
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+    pub trades: Vec<TradeMonitorItem>,
+    /// Distribution of the whole universe, regardless of filters.
+    pub zone_distribution: Vec<ZoneCount>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct HistoryQuery {
+    /// Account name, defaults to the first one.
+    pub account: Option<String>,
+    /// Start, in milliseconds since the epoch. Defaults to 24 hours before `to`.
+    pub from: Option<u64>,
+    /// End, in milliseconds since the epoch. Defaults to now.
+    pub to: Option<u64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ZoneDistributionPoint {
+    pub timestamp: u64,
+    pub total: usize,
+    pub zone_distribution: Vec<ZoneCount>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BreadthPoint {
+    pub timestamp: u64,
+    pub total: usize,
+    /// Percentage of the universe in Z1, the bottom zone.
+    pub z1_pct: f64,
+    /// Percentage of the universe in Z7.
+    pub z7_pct: f64,
+    /// `z7_pct - z1_pct`: positive when more symbols sit near the top of their range.
+    pub spread: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct SymbolSnapshot {
+    pub symbol: String,
+    pub zone: Option<String>,
+    pub bias: Bias,
+    pub performance_24: f64,
+    pub performance_btc_24: f64,
+    pub amplitude_ma_200: f64,
+    pub log_position: f64,
+    pub quote_volume: f64,
+    pub funding_rate: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SymbolHistoryPoint {
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub snapshot: SymbolSnapshot,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct ZoneCount {
     pub zone: String,
     pub count: usize,
 }
 
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct BlockQuery {
+    /// Account name, defaults to the first one.
+    pub account: Option<String>,
+    /// Comma-separated symbols; all by default.
+    pub symbol: Option<String>,
+    /// Oldest block, in seconds since the epoch like `TradeBlock.timestamp`.
+    pub from: Option<u64>,
+    /// Newest block, in seconds since the epoch.
+    pub to: Option<u64>,
+    /// Comma-separated event types, e.g. `status_change,position_closed`; all by default.
+    pub event: Option<String>,
+    /// Comma-separated `TradeStatus` values, e.g. `LongZone3,ShortZone5`; `None` matches trades without a status.
+    /// Only status change blocks carry one, so this drops every other event.
+    pub status: Option<String>,
+    /// `Bullish`, `Bearish` or `None`; like `status`, keeps status change blocks only.
+    pub bias: Option<String>,
+    /// Lowest block index within its chain.
+    pub from_index: Option<u64>,
+    /// Highest block index within its chain.
+    pub to_index: Option<u64>,
+    /// Include chains the strategy already closed; defaults to true.
+    pub archived: Option<bool>,
+    /// `asc` (default, oldest first) or `desc`.
+    pub order: Option<String>,
+    /// 1-based page number.
+    pub page: Option<usize>,
+    /// Blocks per page, at most 1000.
+    pub page_size: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ChainExportQuery {
+    pub account: Option<String>,
+    /// Only the chains of this symbol; defaults to all of them.
+    pub symbol: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ProofQuery {
+    /// Checkpoint id; defaults to the latest one.
+    pub checkpoint: Option<u64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ChainBlock {
+    pub symbol: String,
+    /// When the chain was closed, in seconds; absent while it is open.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<u64>,
+    #[serde(flatten)]
+    pub block: TradeBlock,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BlockPage {
+    /// Blocks left after the filters, before paging.
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+    pub blocks: Vec<ChainBlock>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ChainSummary {
+    pub symbol: String,
+    /// Whether the symbol has an open chain.
+    pub active: bool,
+    /// Open and archived chains of the symbol.
+    pub chains: usize,
+    pub blocks: usize,
+    pub first_timestamp: u64,
+    pub last_timestamp: u64,
+    /// Status of the symbol's most recent status change block.
+    pub status: Option<TradeStatus>,
+    pub bias: Bias,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CryptoMetadata {
     pub symbol: String,
@@ -0,0 +1,127 @@
+use crate::dto::ErrorResponse;
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use serde_json::Value;
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub enum AppError {
+    // An upstream service (Binance, OpenAI) failed or refused the request.
+    Exchange(String),
+    Validation(String, Option<Value>),
+    // Missing credentials, unknown accounts or features disabled in the settings.
+    Config(String),
+    // Refused by a local risk check before reaching the exchange.
+    RiskRejected(String),
+    NotFound(String),
+    Conflict(String),
+    Unauthorized(String),
+    Forbidden(String),
+    Internal(String),
+}
+
+impl AppError {
+    pub fn validation(message: impl Into<String>) -> Self {
+        AppError::Validation(message.into(), None)
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Exchange(_) => "exchange_error",
+            AppError::Validation(..) => "validation_error",
+            AppError::Config(_) => "config_error",
+            AppError::RiskRejected(_) => "risk_rejected",
+            AppError::NotFound(_) => "not_found",
+            AppError::Conflict(_) => "conflict",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::Exchange(m)
+            | AppError::Validation(m, _)
+            | AppError::Config(m)
+            | AppError::RiskRejected(m)
+            | AppError::NotFound(m)
+            | AppError::Conflict(m)
+            | AppError::Unauthorized(m)
+            | AppError::Forbidden(m)
+            | AppError::Internal(m) => m,
+        }
+    }
+
+    pub fn details(&self) -> Option<&Value> {
+        match self {
+            AppError::Validation(_, details) => details.as_ref(),
+            _ => None,
+        }
+    }
+
+    // Keeps the error kind while adding context, e.g. the symbol being closed.
+    pub fn context(self, context: &str) -> Self {
+        let with = |m: String| format!("{}: {}", context, m);
+        match self {
+            AppError::Exchange(m) => AppError::Exchange(with(m)),
+            AppError::Validation(m, d) => AppError::Validation(with(m), d),
+            AppError::Config(m) => AppError::Config(with(m)),
+            AppError::RiskRejected(m) => AppError::RiskRejected(with(m)),
+            AppError::NotFound(m) => AppError::NotFound(with(m)),
+            AppError::Conflict(m) => AppError::Conflict(with(m)),
+            AppError::Unauthorized(m) => AppError::Unauthorized(with(m)),
+            AppError::Forbidden(m) => AppError::Forbidden(with(m)),
+            AppError::Internal(m) => AppError::Internal(with(m)),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::Exchange(_) => StatusCode::BAD_GATEWAY,
+            AppError::Validation(..) => StatusCode::BAD_REQUEST,
+            AppError::Config(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::RiskRejected(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        if status.is_server_error() {
+            eprintln!("[api] {} - {}", self.code(), self.message());
+        }
+
+        HttpResponse::build(status).json(ErrorResponse {
+            code: self.code().to_string(),
+            message: self.message().to_string(),
+            details: self.details().cloned(),
+        })
+    }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(e: reqwest::Error) -> Self {
+        AppError::Exchange(format!("HTTP request failed: {}", e))
+    }
+}
+
+impl From<reqwest::header::InvalidHeaderValue> for AppError {
+    fn from(_: reqwest::header::InvalidHeaderValue) -> Self {
+        AppError::Config("API key contains characters not allowed in a header".into())
+    }
+}
@@ -0,0 +1,169 @@
+use crate::blockchain::TradeBlock;
+use crate::dto::{LiquidationRisk, TradeMonitorResponse};
+use crate::error::AppError;
+use crate::order_state::OrderRecord;
+use actix_web::web::Bytes;
+use futures::{Stream, StreamExt};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast::{self, error::RecvError, Receiver, Sender};
+use tokio::time::timeout;
+use utoipa::ToSchema;
+
+// Events kept per subscriber; a slower client skips the oldest ones instead of
+// holding back the scheduler.
+const EVENT_BUFFER: usize = 256;
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+const RETRY_MS: u64 = 5_000;
+
+static EVENTS: Lazy<Sender<Arc<Event>>> = Lazy::new(|| broadcast::channel(EVENT_BUFFER).0);
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Topic {
+    Monitor,
+    Blocks,
+    Orders,
+    Risk,
+}
+
+impl Topic {
+    pub const ALL: [Topic; 4] = [Topic::Monitor, Topic::Blocks, Topic::Orders, Topic::Risk];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Topic::Monitor => "monitor",
+            Topic::Blocks => "blocks",
+            Topic::Orders => "orders",
+            Topic::Risk => "risk",
+        }
+    }
+
+    // Parses `monitor,blocks`; an empty list subscribes to every topic.
+    pub fn parse_list(topics: Option<&str>) -> Result<Vec<Topic>, AppError> {
+        let Some(topics) = topics.filter(|t| !t.trim().is_empty()) else {
+            return Ok(Topic::ALL.to_vec());
+        };
+
+        topics
+            .split(',')
+            .map(|name| {
+                Topic::ALL
+                    .into_iter()
+                    .find(|t| t.name() == name.trim())
+                    .ok_or_else(|| AppError::validation(format!("Unknown topic '{}'", name.trim())))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(tag = "topic", content = "data")]
+pub enum EventPayload {
+    // One scheduler cycle of `monitor_cryptos`.
+    #[serde(rename = "monitor")]
+    Monitor(TradeMonitorResponse),
+    // A block appended to a chain, whatever its event.
+    #[serde(rename = "blocks")]
+    Block(Box<TradeBlock>),
+    // An order record after each state change.
+    #[serde(rename = "orders")]
+    Order(OrderRecord),
+    // A position closer to liquidation than the configured limits.
+    #[serde(rename = "risk")]
+    Risk(LiquidationRisk),
+}
+
+impl EventPayload {
+    pub fn topic(&self) -> Topic {
+        match self {
+            EventPayload::Monitor(_) => Topic::Monitor,
+            EventPayload::Block(_) => Topic::Blocks,
+            EventPayload::Order(_) => Topic::Orders,
+            EventPayload::Risk(_) => Topic::Risk,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Event {
+    pub id: u64,
+    pub account: String,
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub payload: EventPayload,
+}
+
+pub fn publish(account: &str, payload: EventPayload) {
+    let event = Event {
+        id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+        account: account.to_string(),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64,
+        payload,
+    };
+
+    // Fails only when nobody is subscribed.
+    let _ = EVENTS.send(Arc::new(event));
+}
+
+struct Subscription {
+    receiver: Receiver<Arc<Event>>,
+    topics: Vec<Topic>,
+    account: Option<String>,
+}
+
+impl Subscription {
+    fn accepts(&self, event: &Event) -> bool {
+        self.topics.contains(&event.payload.topic())
+            && self.account.as_ref().is_none_or(|a| *a == event.account)
+    }
+}
+
+// Server-Sent Events stream of the selected topics, with periodic keep-alive
+// comments and a `lagged` event whenever this subscriber fell behind.
+pub fn subscribe(
+    topics: Vec<Topic>,
+    account: Option<String>,
+) -> impl Stream<Item = Result<Bytes, AppError>> {
+    let subscription = Subscription {
+        receiver: EVENTS.subscribe(),
+        topics,
+        account,
+    };
+
+    // Sent first so the headers go out right away and clients know how long to wait before reconnecting.
+    let hello = futures::stream::once(async { Ok(Bytes::from(format!("retry: {}\n\n", RETRY_MS))) });
+
+    let events = futures::stream::unfold(subscription, |mut subscription| async move {
+        loop {
+            let frame = match timeout(KEEP_ALIVE, subscription.receiver.recv()).await {
+                Err(_) => ": keep-alive\n\n".to_string(),
+                Ok(Ok(event)) if subscription.accepts(&event) => sse_frame(&event),
+                Ok(Ok(_)) => continue,
+                Ok(Err(RecvError::Lagged(skipped))) => {
+                    format!("event: lagged\ndata: {{\"skipped\":{}}}\n\n", skipped)
+                }
+                Ok(Err(RecvError::Closed)) => return None,
+            };
+            return Some((Ok(Bytes::from(frame)), subscription));
+        }
+    });
+
+    hello.chain(events)
+}
+
+fn sse_frame(event: &Event) -> String {
+    let data = serde_json::to_string(event).unwrap_or_else(|_| "{}".to_string());
+    format!(
+        "id: {}\nevent: {}\ndata: {}\n\n",
+        event.id,
+        event.payload.topic().name(),
+        data
+    )
+}
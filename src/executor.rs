@@ -0,0 +1,114 @@
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct ActionResult {
+    pub action: String,
+    pub success: bool,
+    pub message: String,
+    pub finished_at: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ExecutorStatus {
+    pub account: String,
+    pub symbol: String,
+    pub queue_depth: usize,
+    pub last_action: Option<ActionResult>,
+}
+
+#[derive(Default)]
+struct SymbolExecutor {
+    lock: tokio::sync::Mutex<()>,
+    depth: AtomicUsize,
+    last_action: Mutex<Option<ActionResult>>,
+}
+
+// (account, symbol): different accounts never wait on each other.
+type ExecutorKey = (String, String);
+
+static EXECUTORS: Lazy<Mutex<HashMap<ExecutorKey, Arc<SymbolExecutor>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// Counts an action in its executor's queue until it is dropped, so an action whose caller gives up
+// (a dropped future, a disconnected client) leaves the queue as well.
+struct QueueSlot(Arc<SymbolExecutor>);
+
+impl QueueSlot {
+    fn enter(executor: &Arc<SymbolExecutor>) -> Self {
+        executor.depth.fetch_add(1, Ordering::SeqCst);
+        QueueSlot(executor.clone())
+    }
+}
+
+impl Drop for QueueSlot {
+    fn drop(&mut self) {
+        self.0.depth.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn executor_for(account: &str, symbol: &str) -> Arc<SymbolExecutor> {
+    let mut map = EXECUTORS.lock().unwrap();
+    map.entry((account.to_string(), symbol.to_string()))
+        .or_default()
+        .clone()
+}
+
+// Runs an exchange action for a symbol after every action queued before it has finished.
+// tokio's Mutex is FIFO, so actions execute in the order they were submitted.
+pub async fn run_serialized<T, E, F>(
+    account: &str,
+    symbol: &str,
+    action: &str,
+    future: F,
+) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+    T: std::fmt::Debug,
+    E: std::fmt::Display,
+{
+    let executor = executor_for(account, symbol);
+    let slot = QueueSlot::enter(&executor);
+
+    let result = {
+        let _guard = executor.lock.lock().await;
+        future.await
+    };
+
+    drop(slot);
+
+    let message = match &result {
+        Ok(value) => format!("{:?}", value),
+        Err(e) => e.to_string(),
+    };
+
+    *executor.last_action.lock().unwrap() = Some(ActionResult {
+        action: action.to_string(),
+        success: result.is_ok(),
+        message,
+        finished_at: chrono::Local::now().to_rfc3339(),
+    });
+
+    result
+}
+
+pub fn get_executor_statuses(account: &str) -> Vec<ExecutorStatus> {
+    let map = EXECUTORS.lock().unwrap();
+    let mut statuses: Vec<ExecutorStatus> = map
+        .iter()
+        .filter(|((executor_account, _), _)| executor_account == account)
+        .map(|((account, symbol), executor)| ExecutorStatus {
+            account: account.clone(),
+            symbol: symbol.clone(),
+            queue_depth: executor.depth.load(Ordering::SeqCst),
+            last_action: executor.last_action.lock().unwrap().clone(),
+        })
+        .collect();
+    statuses.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+    statuses
+}
@@ -0,0 +1,130 @@
+use crate::binance::{get_funding_income, get_position_risk};
+use crate::blockchain::{get_blockchain_for, BlockEvent};
+use crate::config::Settings;
+use crate::credential::has_binance_credentials;
+use crate::dto::{FundingPnlResponse, Trade};
+use crate::order_state::{get_orders_in_state, OrderState};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Accruals per account, keyed by symbol.
+static FUNDING_ACCRUALS: Lazy<Mutex<HashMap<String, HashMap<String, FundingPnlResponse>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn parse(value: &str) -> f64 {
+    value.parse::<f64>().unwrap_or(0.0)
+}
+
+// When the current position was entered, in milliseconds: the latest opening fill on the symbol's
+// chain after its last close, since a chain outlives the positions it opens, closes and flips;
+// without one, the start already tracked, else the last opening order the bot filled, else now.
+fn position_start(account: &str, symbol: &str, tracked: Option<u64>) -> u64 {
+    let chain_fill = get_blockchain_for(account, symbol).and_then(|blocks| {
+        let mut reduce_only = HashMap::new();
+        let mut start = None;
+        for block in blocks {
+            match block.event {
+                BlockEvent::OrderSent {
+                    client_order_id,
+                    reduce_only: reduce,
+                    ..
+                } => {
+                    reduce_only.insert(client_order_id, reduce);
+                }
+                BlockEvent::OrderResult {
+                    client_order_id,
+                    state: OrderState::Filled | OrderState::PartiallyFilled,
+                    ..
+                } => {
+                    start = match reduce_only.get(&client_order_id) {
+                        Some(true) => None,
+                        _ => Some(block.timestamp * 1000),
+                    };
+                }
+                BlockEvent::PositionClosed { .. } => start = None,
+                _ => {}
+            }
+        }
+        start
+    });
+
+    chain_fill.or(tracked).unwrap_or_else(|| {
+        get_orders_in_state(account, OrderState::Filled)
+            .into_iter()
+            .filter(|o| o.symbol == symbol && !o.reduce_only)
+            .map(|o| o.updated_at)
+            .max()
+            .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64)
+    })
+}
+
+// Every open position of the account, whether the strategy or an operator opened it.
+pub async fn update_funding_accruals(trades: &[Trade], settings: &Settings) {
+    let account = settings.binance.account.as_str();
+    if !has_binance_credentials(account) {
+        return;
+    }
+
+    let positions = match get_position_risk(&settings.binance, None).await {
+        Ok(positions) => positions,
+        Err(e) => {
+            eprintln!("Erro ao consultar posicoes para funding: {}", e);
+            return;
+        }
+    };
+    let open: Vec<_> = positions.iter().filter(|p| parse(&p.position_amt).abs() > 1e-8).collect();
+
+    let tracked: HashMap<String, u64> = {
+        let mut accruals = FUNDING_ACCRUALS.lock().unwrap();
+        let accruals = accruals.entry(account.to_string()).or_default();
+        accruals.retain(|symbol, _| open.iter().any(|p| &p.symbol == symbol));
+        accruals.iter().map(|(symbol, a)| (symbol.clone(), a.since)).collect()
+    };
+
+    for position in open {
+        let symbol = position.symbol.clone();
+        let since = position_start(account, &symbol, tracked.get(&symbol).copied());
+
+        let income = match get_funding_income(&settings.binance, &symbol, since).await {
+            Ok(income) => income,
+            Err(e) => {
+                eprintln!("Erro ao consultar funding de {}: {}", symbol, e);
+                continue;
+            }
+        };
+
+        let income: Vec<_> = income.into_iter().filter(|i| i.symbol == symbol).collect();
+        let accumulated_funding: f64 = income.iter().map(|i| parse(&i.income)).sum();
+        let unrealized_profit = parse(&position.unrealized_profit);
+        let trade = trades.iter().find(|t| t.symbol == symbol);
+
+        FUNDING_ACCRUALS
+            .lock()
+            .unwrap()
+            .entry(account.to_string())
+            .or_default()
+            .insert(
+                symbol.clone(),
+                FundingPnlResponse {
+                    symbol,
+                    since,
+                    funding_rate: trade.map(|t| parse(&t.funding_rate)).unwrap_or(0.0),
+                    next_funding_time: trade.map(|t| t.next_funding_time).unwrap_or(0),
+                    accumulated_funding,
+                    funding_payments: income.len(),
+                    last_funding_payment: income.iter().map(|i| i.time).max(),
+                    unrealized_profit,
+                    net_pnl: unrealized_profit + accumulated_funding,
+                },
+            );
+    }
+}
+
+pub fn get_funding_accruals(account: &str) -> Vec<FundingPnlResponse> {
+    let map = FUNDING_ACCRUALS.lock().unwrap();
+    map.get(account)
+        .map(|accruals| accruals.values().cloned().collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,211 @@
+use crate::config::Settings;
+use crate::dto::{
+    BreadthPoint, SymbolHistoryPoint, SymbolSnapshot, TradeMonitorResponse, ZoneCount,
+    ZoneDistributionPoint,
+};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const DAY_FORMAT: &str = "%Y-%m-%d";
+const DEFAULT_WINDOW_MS: u64 = 24 * 60 * 60 * 1000;
+
+// Day each account's history was last pruned; files only expire once a day.
+static LAST_PRUNE: Lazy<Mutex<HashMap<String, NaiveDate>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// One monitor cycle as stored on disk, one JSON object per line.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MonitorSnapshotRecord {
+    pub timestamp: u64,
+    pub zone_distribution: Vec<ZoneCount>,
+    pub trades: Vec<SymbolSnapshot>,
+}
+
+impl MonitorSnapshotRecord {
+    fn total(&self) -> usize {
+        self.zone_distribution.iter().map(|z| z.count).sum()
+    }
+
+    fn zone_count(&self, zone: &str) -> usize {
+        self.zone_distribution
+            .iter()
+            .find(|z| z.zone == zone)
+            .map(|z| z.count)
+            .unwrap_or(0)
+    }
+}
+
+fn account_dir(settings: &Settings, account: &str) -> PathBuf {
+    Path::new(&settings.history.path).join(account)
+}
+
+fn timestamp_of(snapshot: &TradeMonitorResponse) -> u64 {
+    DateTime::parse_from_rfc3339(&snapshot.timestamp)
+        .map(|t| t.timestamp_millis() as u64)
+        .unwrap_or_else(|_| Utc::now().timestamp_millis() as u64)
+}
+
+// Appends the snapshot to the account's file for the day. The first snapshot after startup and the
+// first one of each day also drop the days past the retention.
+pub fn record_snapshot(account: &str, snapshot: &TradeMonitorResponse) {
+    let settings = Settings::current();
+    if !settings.history.enabled {
+        return;
+    }
+
+    let record = MonitorSnapshotRecord {
+        timestamp: timestamp_of(snapshot),
+        zone_distribution: snapshot.zone_distribution.clone(),
+        trades: snapshot
+            .trades
+            .iter()
+            .map(|t| SymbolSnapshot {
+                symbol: t.symbol.clone(),
+                zone: t.zone.clone(),
+                bias: t.bias.clone(),
+                performance_24: t.performance_24,
+                performance_btc_24: t.performance_btc_24,
+                amplitude_ma_200: t.amplitude_ma_200,
+                log_position: t.log_position,
+                quote_volume: t.quote_volume,
+                funding_rate: t.funding_rate,
+            })
+            .collect(),
+    };
+
+    let dir = account_dir(&settings, account);
+    if let Err(e) = fs::create_dir_all(&dir) {
+        eprintln!("Erro ao criar diretorio de historico: {}", e);
+        return;
+    }
+
+    let day = Utc::now().format(DAY_FORMAT).to_string();
+    let line = match serde_json::to_string(&record) {
+        Ok(line) => line,
+        Err(e) => {
+            eprintln!("Erro ao serializar snapshot: {}", e);
+            return;
+        }
+    };
+
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(format!("{}.jsonl", day)))
+        .and_then(|mut file| writeln!(file, "{}", line));
+    if let Err(e) = written {
+        eprintln!("Erro ao gravar historico: {}", e);
+    }
+
+    let today = Utc::now().date_naive();
+    let due = LAST_PRUNE.lock().unwrap().insert(account.to_string(), today) != Some(today);
+    if due {
+        prune(&dir, settings.history.retention_days);
+    }
+}
+
+fn prune(dir: &Path, retention_days: u32) {
+    let oldest = (Utc::now() - Duration::days(retention_days as i64)).date_naive();
+
+    for (day, path) in day_files(dir) {
+        if day < oldest
+            && let Err(e) = fs::remove_file(&path)
+        {
+            eprintln!("Erro ao remover historico {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn day_files(dir: &Path) -> Vec<(NaiveDate, PathBuf)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<(NaiveDate, PathBuf)> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let path = e.path();
+            let stem = path.file_stem()?.to_str()?;
+            let day = NaiveDate::parse_from_str(stem, DAY_FORMAT).ok()?;
+            (path.extension()? == "jsonl").then_some((day, path))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+// Snapshots of an account between `from` and `to` (ms), oldest first.
+pub fn load_snapshots(account: &str, from: Option<u64>, to: Option<u64>) -> Vec<MonitorSnapshotRecord> {
+    let settings = Settings::current();
+    let to = to.unwrap_or_else(|| Utc::now().timestamp_millis() as u64);
+    let from = from.unwrap_or(to.saturating_sub(DEFAULT_WINDOW_MS));
+
+    let day_of = |ms: u64| {
+        DateTime::from_timestamp_millis(ms as i64)
+            .unwrap_or_default()
+            .date_naive()
+    };
+    let (first_day, last_day) = (day_of(from), day_of(to));
+
+    day_files(&account_dir(&settings, account))
+        .into_iter()
+        .filter(|(day, _)| *day >= first_day && *day <= last_day)
+        .filter_map(|(_, path)| fs::File::open(path).ok())
+        .flat_map(|file| BufReader::new(file).lines().map_while(Result::ok))
+        .filter_map(|line| serde_json::from_str::<MonitorSnapshotRecord>(&line).ok())
+        .filter(|record| record.timestamp >= from && record.timestamp <= to)
+        .collect()
+}
+
+pub fn zone_distribution_history(records: &[MonitorSnapshotRecord]) -> Vec<ZoneDistributionPoint> {
+    records
+        .iter()
+        .map(|r| ZoneDistributionPoint {
+            timestamp: r.timestamp,
+            total: r.total(),
+            zone_distribution: r.zone_distribution.clone(),
+        })
+        .collect()
+}
+
+pub fn breadth_history(records: &[MonitorSnapshotRecord]) -> Vec<BreadthPoint> {
+    records
+        .iter()
+        .map(|r| {
+            let total = r.total();
+            let pct = |zone: &str| {
+                if total == 0 {
+                    0.0
+                } else {
+                    r.zone_count(zone) as f64 / total as f64 * 100.0
+                }
+            };
+            let (z1_pct, z7_pct) = (pct("Z1"), pct("Z7"));
+            BreadthPoint {
+                timestamp: r.timestamp,
+                total,
+                z1_pct,
+                z7_pct,
+                spread: z7_pct - z1_pct,
+            }
+        })
+        .collect()
+}
+
+pub fn symbol_history(records: Vec<MonitorSnapshotRecord>, symbol: &str) -> Vec<SymbolHistoryPoint> {
+    records
+        .into_iter()
+        .filter_map(|r| {
+            let timestamp = r.timestamp;
+            r.trades
+                .into_iter()
+                .find(|t| t.symbol.eq_ignore_ascii_case(symbol))
+                .map(|snapshot| SymbolHistoryPoint { timestamp, snapshot })
+        })
+        .collect()
+}
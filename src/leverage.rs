@@ -1,19 +1,37 @@
-use crate::credential::get_credentials;
-use crate::config::BinanceSettings;
+use crate::binance::get_position_risk;
+use crate::blockchain::{get_current_blockchain_symbols, record_event, BlockEvent};
+use crate::credential::binance_credentials;
+use crate::error::AppError;
+use crate::config::{BinanceSettings, MarginType};
+use crate::dto::{LeverageBracket, LeverageBracketResponse, LeverageStateResponse};
 use serde::{Deserialize, Serialize};
 use reqwest::{Client, header::{HeaderMap, HeaderValue, CONTENT_TYPE}};
 use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
 use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
 
 type HmacSha256 = Hmac<Sha256>;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub struct LeverageResponse {
     pub leverage: u32,
     pub symbol: String,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum LeverageBracketPayload {
+    Many(Vec<LeverageBracketResponse>),
+    One(LeverageBracketResponse),
+}
+
+static APPLIED_MARGIN_TYPES: Lazy<Mutex<HashMap<String, MarginType>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
 fn get_timestamp() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -27,19 +45,22 @@ fn sign_query(query: &str, secret: &str) -> String {
     hex::encode(mac.finalize().into_bytes())
 }
 
-pub async fn set_leverage(
-    settings: &BinanceSettings,
-    symbol: &str,
-) -> Result<LeverageResponse, Box<dyn std::error::Error>> {
-    set_leverage_with_value(settings, symbol, settings.leverage).await
-}
-
 pub async fn set_leverage_with_value(
     settings: &BinanceSettings,
     symbol: &str,
     leverage: u32,
-) -> Result<LeverageResponse, Box<dyn std::error::Error>> {
-    let credentials = get_credentials();
+) -> Result<LeverageResponse, AppError> {
+    let brackets = get_leverage_brackets(settings, symbol).await?;
+    if let Some(max) = max_leverage(&brackets)
+        && leverage > max
+    {
+        return Err(AppError::RiskRejected(format!(
+            "Leverage {}x exceeds the maximum allowed for {} ({}x)",
+            leverage, symbol, max
+        )));
+    }
+
+    let credentials = binance_credentials(&settings.account)?;
     let timestamp = get_timestamp();
 
     let query = format!(
@@ -62,10 +83,155 @@ pub async fn set_leverage_with_value(
             "Leverage successfully applied: {}x to {}",
             response.leverage, response.symbol
         );
+        record_event(
+            &settings.account,
+            symbol,
+            BlockEvent::LeverageChange {
+                leverage: response.leverage,
+            },
+        );
         Ok(response)
     } else {
         let error_text = res.text().await?;
         eprintln!("Error applying leverage: {}", error_text);
-        Err(format!("Binance error: {}", error_text).into())
+        Err(AppError::Exchange(format!("Binance error: {}", error_text)))
+    }
+}
+
+pub async fn set_margin_type(
+    settings: &BinanceSettings,
+    symbol: &str,
+    margin_type: MarginType,
+) -> Result<(), AppError> {
+    let credentials = binance_credentials(&settings.account)?;
+    let timestamp = get_timestamp();
+
+    let query = format!(
+        "symbol={}&marginType={}&recvWindow=10000&timestamp={}",
+        symbol, margin_type, timestamp
+    );
+    let signature = sign_query(&query, &credentials.secret);
+    let full_url = format!("{}/marginType?{}&signature={}", settings.future_url, query, signature);
+
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    headers.insert("X-MBX-APIKEY", HeaderValue::from_str(&credentials.key)?);
+
+    let client = Client::new();
+    let res = client.post(&full_url).headers(headers).send().await?;
+
+    if res.status().is_success() {
+        println!("Margin type successfully applied: {} to {}", margin_type, symbol);
+        return Ok(());
+    }
+
+    let error_text = res.text().await?;
+
+    // -4046: "No need to change margin type." - the symbol already uses the requested mode.
+    if error_text.contains("-4046") {
+        return Ok(());
+    }
+
+    eprintln!("Error applying margin type: {}", error_text);
+    Err(AppError::Exchange(format!("Binance error: {}", error_text)))
+}
+
+pub async fn ensure_margin_type(
+    settings: &BinanceSettings,
+    symbol: &str,
+) -> Result<(), AppError> {
+    let margin_type = settings.margin_type_for(symbol);
+
+    let key = format!("{}/{}", settings.account, symbol);
+    if APPLIED_MARGIN_TYPES.lock().unwrap().get(&key) == Some(&margin_type) {
+        return Ok(());
+    }
+
+    set_margin_type(settings, symbol, margin_type).await?;
+    APPLIED_MARGIN_TYPES
+        .lock()
+        .unwrap()
+        .insert(key, margin_type);
+
+    Ok(())
+}
+
+pub async fn get_leverage_brackets(
+    settings: &BinanceSettings,
+    symbol: &str,
+) -> Result<Vec<LeverageBracket>, AppError> {
+    let credentials = binance_credentials(&settings.account)?;
+    let timestamp = get_timestamp();
+
+    let query = format!("symbol={}&recvWindow=10000&timestamp={}", symbol, timestamp);
+    let signature = sign_query(&query, &credentials.secret);
+    let full_url = format!("{}/leverageBracket?{}&signature={}", settings.future_url, query, signature);
+
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    headers.insert("X-MBX-APIKEY", HeaderValue::from_str(&credentials.key)?);
+
+    let client = Client::new();
+    let res = client.get(&full_url).headers(headers).send().await?;
+
+    if !res.status().is_success() {
+        let error_text = res.text().await?;
+        return Err(AppError::Exchange(format!("Binance error: {}", error_text)));
+    }
+
+    let brackets = match res.json::<LeverageBracketPayload>().await? {
+        LeverageBracketPayload::One(response) => response.brackets,
+        LeverageBracketPayload::Many(responses) => responses
+            .into_iter()
+            .find(|r| r.symbol == symbol)
+            .map(|r| r.brackets)
+            .unwrap_or_default(),
+    };
+
+    Ok(brackets)
+}
+
+pub fn max_leverage(brackets: &[LeverageBracket]) -> Option<u32> {
+    brackets.iter().map(|b| b.initial_leverage).max()
+}
+
+pub fn max_notional_for(brackets: &[LeverageBracket], leverage: u32) -> Option<f64> {
+    brackets
+        .iter()
+        .filter(|b| b.initial_leverage >= leverage)
+        .map(|b| b.notional_cap)
+        .reduce(f64::max)
+}
+
+pub async fn get_leverage_states(
+    settings: &BinanceSettings,
+) -> Result<Vec<LeverageStateResponse>, AppError> {
+    let mut states = Vec::new();
+
+    for symbol in get_current_blockchain_symbols(&settings.account) {
+        let positions = get_position_risk(settings, Some(&symbol)).await?;
+        let brackets = get_leverage_brackets(settings, &symbol).await.unwrap_or_default();
+
+        for position in positions.into_iter().filter(|p| p.symbol == symbol) {
+            let margin_type = if position.margin_type.eq_ignore_ascii_case("isolated") {
+                MarginType::Isolated
+            } else {
+                MarginType::Crossed
+            };
+
+            states.push(LeverageStateResponse {
+                symbol: symbol.clone(),
+                leverage: position.leverage.parse().unwrap_or(0),
+                margin_type,
+                max_notional_value: position.max_notional_value.parse().unwrap_or(0.0),
+                max_leverage: max_leverage(&brackets),
+                position_amt: position.position_amt.parse().unwrap_or(0.0),
+                configured_leverage: settings.leverage_for(&symbol),
+                configured_boost_leverage: settings.boost_leverage_for(&symbol),
+                configured_margin_type: settings.margin_type_for(&symbol),
+            });
+        }
     }
+
+    Ok(states)
 }
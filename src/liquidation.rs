@@ -0,0 +1,213 @@
+use crate::binance::{get_futures_account, get_position_risk};
+use crate::events::{publish, EventPayload};
+use crate::blockchain::{record_event, BlockEvent};
+use crate::config::Settings;
+use crate::credential::has_binance_credentials;
+use crate::dto::{FuturesAccount, InterventionKind, LeverageBracket, LiquidationRisk, PositionRisk, RiskIntervention};
+use crate::leverage::{get_leverage_brackets, set_leverage_with_value};
+use crate::order::reduce_position;
+use crate::order_state::timed_client_order_id;
+use crate::executor::run_serialized;
+use crate::error::AppError;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Last automatic intervention per account and symbol, so one breach isn't acted on every cycle.
+static LAST_INTERVENTION: Lazy<Mutex<HashMap<(String, String), Instant>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn parse(value: &str) -> f64 {
+    value.parse::<f64>().unwrap_or(0.0)
+}
+
+fn maint_margin_ratio(brackets: &[LeverageBracket], notional: f64) -> f64 {
+    brackets
+        .iter()
+        .find(|b| notional >= b.notional_floor && notional < b.notional_cap)
+        .or_else(|| brackets.last())
+        .map(|b| b.maint_margin_ratio)
+        .unwrap_or(0.0)
+}
+
+fn is_cross(margin_type: &str) -> bool {
+    margin_type.eq_ignore_ascii_case("cross") || margin_type.eq_ignore_ascii_case("crossed")
+}
+
+fn ratio_pct(maint_margin: f64, margin: f64) -> f64 {
+    if margin <= 0.0 {
+        return 100.0;
+    }
+
+    (maint_margin / margin) * 100.0
+}
+
+// An isolated position is liquidated on its own margin.
+fn isolated_margin_ratio(position: &PositionRisk, brackets: &[LeverageBracket]) -> f64 {
+    let notional = (parse(&position.position_amt) * parse(&position.mark_price)).abs();
+    ratio_pct(notional * maint_margin_ratio(brackets, notional), parse(&position.isolated_margin))
+}
+
+// Cross positions share the account's margin balance, and Binance liquidates them when the
+// account's maintenance margin reaches it.
+fn cross_margin_ratio(account: &FuturesAccount) -> f64 {
+    ratio_pct(parse(&account.total_maint_margin), parse(&account.total_margin_balance))
+}
+
+// Every open position of the account, whether the strategy or an operator opened it.
+pub async fn get_liquidation_risks(settings: &Settings) -> Result<Vec<LiquidationRisk>, AppError> {
+    let positions = get_position_risk(&settings.binance, None).await?;
+    let open: Vec<_> = positions.iter().filter(|p| parse(&p.position_amt).abs() > 1e-8).collect();
+    let cross_ratio = if open.iter().any(|p| is_cross(&p.margin_type)) {
+        let account = get_futures_account(&settings.binance)
+            .await
+            .map_err(|e| e.context("Erro ao consultar margem da conta"))?;
+        cross_margin_ratio(&account)
+    } else {
+        0.0
+    };
+    let mut risks = Vec::new();
+
+    for position in open {
+        let margin_ratio = if is_cross(&position.margin_type) {
+            cross_ratio
+        } else {
+            let brackets = get_leverage_brackets(&settings.binance, &position.symbol)
+                .await
+                .map_err(|e| e.context("Erro ao consultar leverage brackets"))?;
+            isolated_margin_ratio(position, &brackets)
+        };
+
+        let leverage = position.leverage.parse::<u32>().unwrap_or(1);
+        let mark_price = parse(&position.mark_price);
+        let liquidation_price = Some(parse(&position.liquidation_price)).filter(|p| *p > 0.0);
+        let distance_pct = liquidation_price
+            .filter(|_| mark_price > 0.0)
+            .map(|liq| ((mark_price - liq).abs() / mark_price) * 100.0);
+
+        let at_risk = distance_pct.is_some_and(|d| d < settings.liquidation.min_distance_pct)
+            || margin_ratio >= settings.liquidation.max_margin_ratio;
+
+        risks.push(LiquidationRisk {
+            symbol: position.symbol.clone(),
+            position_amt: parse(&position.position_amt),
+            mark_price,
+            liquidation_price,
+            distance_pct,
+            margin_ratio,
+            leverage,
+            margin_type: position.margin_type.to_uppercase(),
+            at_risk,
+        });
+    }
+
+    Ok(risks)
+}
+
+// Alerts always; only acts on them while the bot trades on its own (`decide`) and `auto_deleverage` is on.
+pub async fn monitor_liquidation(settings: &Settings) {
+    if !has_binance_credentials(&settings.binance.account) {
+        return;
+    }
+
+    let risks = match get_liquidation_risks(settings).await {
+        Ok(risks) => risks,
+        Err(e) => {
+            eprintln!("Erro ao monitorar liquidacao: {}", e);
+            return;
+        }
+    };
+
+    for risk in risks.into_iter().filter(|r| r.at_risk) {
+        eprintln!(
+            "[{}] ALERTA de liquidacao - {} - distancia: {} - margin ratio: {:.2}% - alavancagem: {}x",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            risk.symbol,
+            risk.distance_pct
+                .map(|d| format!("{:.2}%", d))
+                .unwrap_or_else(|| "-".to_string()),
+            risk.margin_ratio,
+            risk.leverage,
+        );
+        publish(&settings.binance.account, EventPayload::Risk(risk.clone()));
+
+        if settings.binance.decide && settings.liquidation.auto_deleverage && !cooling_down(settings, &risk.symbol) {
+            deleverage(settings, &risk).await;
+        }
+    }
+}
+
+fn cooling_down(settings: &Settings, symbol: &str) -> bool {
+    let cooldown = Duration::from_secs(settings.liquidation.cooldown_secs);
+    LAST_INTERVENTION
+        .lock()
+        .unwrap()
+        .get(&(settings.binance.account.clone(), symbol.to_string()))
+        .is_some_and(|at| at.elapsed() < cooldown)
+}
+
+async fn deleverage(settings: &Settings, risk: &LiquidationRisk) {
+    let account = settings.binance.account.as_str();
+    let base_leverage = settings.binance.leverage_for(&risk.symbol);
+
+    // In cross margin the whole wallet backs the position, so a lower leverage doesn't move its
+    // liquidation price; only a smaller position does.
+    let kind = if !is_cross(&risk.margin_type) && risk.leverage > base_leverage {
+        let result = run_serialized(
+            account,
+            &risk.symbol,
+            "deleverage",
+            set_leverage_with_value(&settings.binance, &risk.symbol, base_leverage),
+        )
+        .await;
+        match result {
+            Ok(_) => InterventionKind::ReduceLeverage {
+                from: risk.leverage,
+                to: base_leverage,
+            },
+            Err(e) => {
+                eprintln!("Erro ao reduzir alavancagem de {}: {}", risk.symbol, e);
+                return;
+            }
+        }
+    } else {
+        let fraction = settings.liquidation.reduce_fraction;
+        // The cooldown keeps reductions apart, so each one is a new order.
+        let order_id = timed_client_order_id(&risk.symbol, "reduce");
+        let result = run_serialized(
+            account,
+            &risk.symbol,
+            "reduce_position",
+            reduce_position(&settings.binance, &risk.symbol, fraction, &order_id),
+        )
+        .await;
+        match result {
+            Ok(order) => InterventionKind::ReducePosition {
+                fraction,
+                quantity: order.orig_qty,
+            },
+            Err(e) => {
+                eprintln!("Erro ao reduzir posicao de {}: {}", risk.symbol, e);
+                return;
+            }
+        }
+    };
+
+    LAST_INTERVENTION
+        .lock()
+        .unwrap()
+        .insert((account.to_string(), risk.symbol.clone()), Instant::now());
+    record_event(
+        account,
+        &risk.symbol,
+        BlockEvent::RiskIntervention {
+            intervention: RiskIntervention {
+                kind,
+                mark_price: risk.mark_price,
+                liquidation_price: risk.liquidation_price,
+                distance_pct: risk.distance_pct,
+                margin_ratio: risk.margin_ratio,
+            },
+        },
+    );
+}
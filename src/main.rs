@@ -11,62 +11,227 @@ mod schedule;
 mod leverage;
 mod decide;
 mod monitor;
+mod monitor_table;
 mod status_trade;
 mod spy;
 mod swap;
 mod crypto_candidate;
 mod open_ai;
 mod crypto_metadata;
+mod liquidation;
+mod funding;
+mod order_state;
+mod executor;
+mod auth;
+mod audit;
+mod server;
+mod settings_service;
+mod error;
+mod openapi;
+mod events;
+mod history;
+mod tui;
+mod cli;
+mod backtest;
+mod chain_query;
+mod signing;
+mod merkle;
+mod anchor;
+mod checkpoint;
+mod block_encoding;
+mod migration;
+mod chain_export;
 
-use actix_cors::Cors;
-use actix_web::{App, HttpServer, http};
+use actix_web::{middleware, web, App, HttpServer};
+use clap::Parser;
+use cli::{Cli, Command};
+use error::AppError;
+use credential::{init_credentials, seal_keystore, CredentialProvider, EnvProvider};
+use server::{build_cors, load_rustls_config};
+use signing::init_signing_key;
+use anchor::spawn_anchor_writer;
+use checkpoint::{load_checkpoints, spawn_checkpoint_writer};
+use settings_service::{init_settings, load_settings, spawn_settings_watcher};
 use api::{
     post_trades_start,
     post_trades_stop,
     get_trades_health_check,
+    get_trades_blocks,
+    get_trades_checkpoints,
+    get_block_proof,
+    get_chains_summary,
+    get_chains_export,
+    post_chains_import,
     get_trades_chain_by_symbol,
     get_last_trade_by_symbol,
     get_all_symbols_chains,
     get_chain_validity,
+    get_public_key,
     post_trades_order,
     get_trades_balance,
     post_close_all_positions,
+    get_trades_orders,
+    get_trades_executions,
+    post_reconcile_orders,
     put_leverage,
+    get_leverage,
+    get_liquidation_risk,
+    get_trades_funding,
     get_trades_spy,
     get_trades_monitor,
     post_monitor_assistant,
+    get_settings,
+    put_settings,
+    get_accounts,
+    get_events,
+    get_history_zones,
+    get_history_breadth,
+    get_history_symbol,
 };
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    println!("Server running at http://localhost:8080");
+    let cli = Cli::parse();
+    cli.settings_source().install();
 
-    HttpServer::new(|| {
-        App::new()
-            .wrap(
-                Cors::default()
-                    .allowed_origin("http://localhost:5173")
-                    .allowed_methods(vec!["GET", "POST", "PUT"])
-                    .allowed_headers(vec![http::header::CONTENT_TYPE])
-                    .supports_credentials()
-                    .max_age(3600),
+    let tui = match cli.command {
+        None => false,
+        Some(Command::Run { tui }) => tui,
+        Some(command) => {
+            if let Err(e) = run_command(command).await {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+    };
+
+    let settings = init_settings().map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+    // `--seal-keystore <path>` encrypts the credentials currently in the environment and exits.
+    if let Some(path) = &cli.seal_keystore {
+        let passphrase = std::env::var(&settings.credentials.passphrase_env).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{} must hold the keystore passphrase", settings.credentials.passphrase_env),
             )
+        })?;
+        EnvProvider { prefix: String::new() }
+            .load()
+            .and_then(|values| seal_keystore(path, &values, &passphrase))
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        println!("Keystore written to {}", path);
+        return Ok(());
+    }
+
+    init_credentials(&settings)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    init_signing_key(&settings.blockchain.signing_key)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let checkpoints = load_checkpoints(&settings.blockchain.checkpoint_file)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    if checkpoints > 0 {
+        println!("[blockchain] {} checkpoints carregados de {}", checkpoints, settings.blockchain.checkpoint_file);
+    }
+    spawn_anchor_writer();
+    spawn_checkpoint_writer();
+    let server_settings = settings.server.clone();
+    spawn_settings_watcher();
+    let cors_settings = server_settings.clone();
+
+    let mut server = HttpServer::new(move || {
+        App::new()
+            .wrap(middleware::from_fn(auth::inspect_request))
+            .wrap(build_cors(&cors_settings))
+            .app_data(web::JsonConfig::default().error_handler(|e, _| AppError::validation(e.to_string()).into()))
+            .app_data(web::QueryConfig::default().error_handler(|e, _| AppError::validation(e.to_string()).into()))
+            .app_data(web::PathConfig::default().error_handler(|e, _| AppError::validation(e.to_string()).into()))
             .service(post_trades_start)
             .service(post_trades_stop)
             .service(get_trades_health_check)
+            .service(get_trades_blocks)
+            .service(get_trades_checkpoints)
+            .service(get_block_proof)
+            // Registered before `/trades/chains/{symbol}`, which would otherwise match them.
+            .service(get_chains_summary)
+            .service(get_chains_export)
+            .service(post_chains_import)
             .service(get_trades_chain_by_symbol)
             .service(get_last_trade_by_symbol)
             .service(get_all_symbols_chains)
             .service(get_chain_validity)
+            .service(get_public_key)
             .service(post_trades_order)
             .service(get_trades_balance)
             .service(post_close_all_positions)
+            .service(get_trades_orders)
+            .service(get_trades_executions)
+            .service(post_reconcile_orders)
             .service(put_leverage)
+            .service(get_leverage)
+            .service(get_liquidation_risk)
+            .service(get_trades_funding)
             .service(get_trades_spy)
             .service(get_trades_monitor)
             .service(post_monitor_assistant)
-    })
-    .bind(("127.0.0.1", 8080))?
-    .run()
-    .await
+            .service(get_settings)
+            .service(put_settings)
+            .service(get_accounts)
+            .service(get_events)
+            .service(get_history_zones)
+            .service(get_history_breadth)
+            .service(get_history_symbol)
+            .service(openapi::explorer())
+            .default_service(web::route().to(|| async {
+                Err::<actix_web::HttpResponse, _>(AppError::NotFound("Route not found".into()))
+            }))
+    });
+
+    if let Some(workers) = server_settings.workers {
+        server = server.workers(workers);
+    }
+
+    let address = (server_settings.bind_address.as_str(), server_settings.port);
+
+    let server = match (&server_settings.tls_cert, &server_settings.tls_key) {
+        (Some(cert), Some(key)) => {
+            let tls_config = load_rustls_config(cert, key)?;
+            println!(
+                "Server running at https://{}:{}",
+                server_settings.bind_address, server_settings.port
+            );
+            server.bind_rustls_021(address, tls_config)?
+        }
+        _ => {
+            println!(
+                "Server running at http://{}:{}",
+                server_settings.bind_address, server_settings.port
+            );
+            server.bind(address)?
+        }
+    };
+
+    let server = server.run();
+
+    // `run --tui` keeps the API up and takes over the terminal with the dashboard until it is closed.
+    if tui {
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+        actix_web::rt::task::spawn_blocking(tui::run)
+            .await
+            .map_err(std::io::Error::other)??;
+        handle.stop(true).await;
+        return Ok(());
+    }
+
+    server.await
+}
+
+// One-shot commands: `settings check` reads the files itself, the rest need the settings loaded.
+// Orders and leverage changes go through the server, which holds the credentials.
+async fn run_command(command: Command) -> Result<(), AppError> {
+    if !matches!(command, Command::Settings { .. }) {
+        load_settings().map_err(AppError::Config)?;
+    }
+    cli::execute(command).await
 }
@@ -0,0 +1,228 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+
+// RFC 6962 style: leaves and inner nodes get different prefixes so a leaf can't pass for a node.
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+pub fn leaf_hash(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+pub fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Left,
+    Right,
+}
+
+// A sibling on the way from a leaf to the root, and which side of the running hash it goes on.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ProofStep {
+    pub side: Side,
+    pub hash: String,
+}
+
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+// Pairs nodes level by level; an odd node out is carried up unchanged. An empty tree hashes to SHA-256("").
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    if leaves.is_empty() {
+        return Sha256::digest([]).into();
+    }
+
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+// Leaves appended one at a time, with every complete subtree's hash kept as it forms:
+// `levels[j][i]` covers leaves `i * 2^j .. (i + 1) * 2^j`. The tree over any prefix of the leaves
+// shares those nodes and differs only along its right edge, so roots and proofs of any prefix take
+// O(log n) hashes, and they match `merkle_root` over the same leaves.
+#[derive(Debug, Default)]
+pub struct MerkleLog {
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleLog {
+    pub fn len(&self) -> usize {
+        self.levels.first().map_or(0, Vec::len)
+    }
+
+    pub fn push(&mut self, leaf: [u8; 32]) {
+        let mut node = leaf;
+        for level in 0.. {
+            if self.levels.len() == level {
+                self.levels.push(Vec::new());
+            }
+            let nodes = &mut self.levels[level];
+            nodes.push(node);
+            if nodes.len() % 2 == 1 {
+                break;
+            }
+            node = node_hash(&nodes[nodes.len() - 2], &nodes[nodes.len() - 1]);
+        }
+    }
+
+    // Drops the leaves from `size` on, and the subtrees that covered them.
+    pub fn truncate(&mut self, size: usize) {
+        for (level, nodes) in self.levels.iter_mut().enumerate() {
+            nodes.truncate(size >> level);
+        }
+    }
+
+    // Node `index` of `level` in the tree over the first `size` leaves.
+    fn node(&self, level: usize, index: usize, size: usize) -> [u8; 32] {
+        if level == 0 || (index + 1) << level <= size {
+            return self.levels[level][index];
+        }
+        let left = self.node(level - 1, 2 * index, size);
+        if (2 * index + 1) << (level - 1) < size {
+            node_hash(&left, &self.node(level - 1, 2 * index + 1, size))
+        } else {
+            left
+        }
+    }
+
+    fn width(level: usize, size: usize) -> usize {
+        size.div_ceil(1 << level)
+    }
+
+    // Root of the first `size` leaves, which must not exceed `len`.
+    pub fn root(&self, size: usize) -> [u8; 32] {
+        if size == 0 {
+            return Sha256::digest([]).into();
+        }
+        let mut level = 0;
+        while Self::width(level, size) > 1 {
+            level += 1;
+        }
+        self.node(level, 0, size)
+    }
+
+    // Siblings from leaf `index` up to the root of the first `size` leaves; levels where the node
+    // is carried up add no step.
+    pub fn proof(&self, mut index: usize, size: usize) -> Vec<ProofStep> {
+        let mut steps = Vec::new();
+        let mut level = 0;
+
+        while Self::width(level, size) > 1 {
+            let sibling = index ^ 1;
+            if sibling < Self::width(level, size) {
+                steps.push(ProofStep {
+                    side: if sibling < index { Side::Left } else { Side::Right },
+                    hash: hex::encode(self.node(level, sibling, size)),
+                });
+            }
+            level += 1;
+            index /= 2;
+        }
+        steps
+    }
+}
+
+pub fn verify_proof(leaf: [u8; 32], steps: &[ProofStep], root: &str) -> bool {
+    let mut hash = leaf;
+    for step in steps {
+        let Some(sibling) = hex::decode(&step.hash).ok().and_then(|b| <[u8; 32]>::try_from(b).ok()) else {
+            return false;
+        };
+        hash = match step.side {
+            Side::Left => node_hash(&sibling, &hash),
+            Side::Right => node_hash(&hash, &sibling),
+        };
+    }
+    hex::encode(hash) == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(count: usize) -> Vec<[u8; 32]> {
+        (0..count).map(|i| leaf_hash(format!("block-{}", i).as_bytes())).collect()
+    }
+
+    fn log(leaves: &[[u8; 32]]) -> MerkleLog {
+        let mut log = MerkleLog::default();
+        leaves.iter().for_each(|leaf| log.push(*leaf));
+        log
+    }
+
+    #[test]
+    fn every_leaf_proves_against_the_root_of_odd_and_even_trees() {
+        for count in [1, 2, 3, 4, 5, 6, 7, 8, 13] {
+            let leaves = leaves(count);
+            let log = log(&leaves);
+            let root = hex::encode(log.root(count));
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = log.proof(index, count);
+                assert!(verify_proof(*leaf, &proof, &root), "leaf {} of {}", index, count);
+            }
+        }
+    }
+
+    #[test]
+    fn prefixes_of_the_log_match_trees_built_from_scratch() {
+        let leaves = leaves(21);
+        let log = log(&leaves);
+        for size in 0..=leaves.len() {
+            let root = merkle_root(&leaves[..size]);
+            assert_eq!(log.root(size), root, "size {}", size);
+            for (index, leaf) in leaves[..size].iter().enumerate() {
+                assert!(verify_proof(*leaf, &log.proof(index, size), &hex::encode(root)));
+            }
+        }
+
+        let mut truncated = log;
+        truncated.truncate(6);
+        truncated.push(leaves[6]);
+        assert_eq!(truncated.len(), 7);
+        assert_eq!(truncated.root(7), merkle_root(&leaves[..7]));
+    }
+
+    #[test]
+    fn a_proof_fails_for_another_leaf_or_root() {
+        for count in [4, 5] {
+            let leaves = leaves(count);
+            let log = log(&leaves);
+            let root = hex::encode(log.root(count));
+            let proof = log.proof(1, count);
+
+            assert!(!verify_proof(leaves[2], &proof, &root));
+            assert!(!verify_proof(leaves[1], &proof, &hex::encode(log.root(count - 1))));
+        }
+    }
+
+    #[test]
+    fn leaves_and_nodes_hash_apart() {
+        let leaves = leaves(2);
+        let node = node_hash(&leaves[0], &leaves[1]);
+        assert_eq!(merkle_root(&leaves), node);
+        assert_ne!(leaf_hash(&[leaves[0], leaves[1]].concat()), node);
+    }
+}
@@ -0,0 +1,320 @@
+use crate::block_encoding::{
+    legacy_event_hash, legacy_trade_hash, CANONICAL_VERSION, JSON_EVENT_VERSION, LEGACY_TRADE_VERSION,
+};
+use crate::blockchain::{BlockEvent, TradeBlock};
+use crate::signing::{parse_public_key, public_key, verify};
+use ed25519_dalek::VerifyingKey;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Default)]
+pub struct ChainCheck {
+    pub blocks: usize,
+    // Blocks per schema version.
+    pub versions: BTreeMap<u32, usize>,
+    pub signed: usize,
+}
+
+fn field<'a>(block: &'a Value, index: usize, name: &str) -> Result<&'a Value, String> {
+    block
+        .get(name)
+        .ok_or_else(|| format!("Block {}: missing '{}'", index, name))
+}
+
+fn u64_field(block: &Value, index: usize, name: &str) -> Result<u64, String> {
+    field(block, index, name)?
+        .as_u64()
+        .ok_or_else(|| format!("Block {}: '{}' is not an integer", index, name))
+}
+
+fn str_field<'a>(block: &'a Value, index: usize, name: &str) -> Result<&'a str, String> {
+    field(block, index, name)?
+        .as_str()
+        .ok_or_else(|| format!("Block {}: '{}' is not a string", index, name))
+}
+
+fn parse<T: DeserializeOwned>(value: &Value, index: usize) -> Result<T, String> {
+    serde_json::from_value(value.clone()).map_err(|e| format!("Block {}: {}", index, e))
+}
+
+// Blocks without `event` predate it; event blocks without `version` predate versioning.
+fn version_of(block: &Value) -> u32 {
+    if block.get("event").is_none() {
+        return LEGACY_TRADE_VERSION;
+    }
+    block
+        .get("version")
+        .and_then(Value::as_u64)
+        .map(|v| v as u32)
+        .unwrap_or(JSON_EVENT_VERSION)
+}
+
+fn intervention_of(block: &Value) -> Option<&Value> {
+    block.get("intervention").filter(|v| !v.is_null())
+}
+
+// The hash a block should carry under the schema it was written with. The JSON schemas hash the
+// exported values as they are, so fields added to `Trade` since then don't get in the way.
+fn original_hash(block: &Value, index: usize, version: u32) -> Result<String, String> {
+    let block_index = u64_field(block, index, "index")?;
+    let timestamp = u64_field(block, index, "timestamp")?;
+    let previous_hash = str_field(block, index, "previous_hash")?;
+
+    match version {
+        LEGACY_TRADE_VERSION => Ok(legacy_trade_hash(
+            block_index,
+            timestamp,
+            field(block, index, "trade")?,
+            intervention_of(block),
+            previous_hash,
+        )),
+        JSON_EVENT_VERSION => Ok(legacy_event_hash(
+            block_index,
+            timestamp,
+            field(block, index, "event")?,
+            previous_hash,
+        )),
+        _ => parse::<TradeBlock>(block, index)?
+            .calculate_hash()
+            .ok_or_else(|| format!("Block {}: unknown schema version {}", index, version)),
+    }
+}
+
+// Only looks at the type, so events an older `Trade` no longer parses as don't get in the way.
+fn is_migration(block: &Value) -> bool {
+    block.pointer("/event/type").and_then(Value::as_str) == Some("migration")
+}
+
+fn key_hex(key: &VerifyingKey) -> String {
+    hex::encode(key.to_bytes())
+}
+
+// Checks an exported chain block by block, each with its own schema version. Signatures are
+// checked when a key is given; blocks from before signing have none, current ones must. A
+// `migration` block hands the chain over to its `public_key`, which must sign it and what follows.
+pub fn verify_exported_chain(blocks: &[Value], key: Option<&VerifyingKey>) -> Result<ChainCheck, String> {
+    let mut check = ChainCheck::default();
+    let mut previous = "0".to_string();
+    let mut key = key.copied();
+
+    for (index, block) in blocks.iter().enumerate() {
+        let version = version_of(block);
+        if u64_field(block, index, "index")? != index as u64 {
+            return Err(format!("Block {}: index out of place", index));
+        }
+        if str_field(block, index, "previous_hash")? != previous {
+            return Err(format!("Block {}: does not link to the previous block", index));
+        }
+
+        let hash = str_field(block, index, "hash")?;
+        if original_hash(block, index, version)? != hash {
+            return Err(format!("Block {}: hash does not match its version {} content", index, version));
+        }
+
+        if is_migration(block)
+            && let BlockEvent::Migration {
+                previous_key,
+                public_key,
+            } = parse(field(block, index, "event")?, index)?
+        {
+            if key.is_some_and(|key| key_hex(&key) != previous_key) {
+                return Err(format!("Block {}: migrates from {}, not from the checked key", index, previous_key));
+            }
+            key = Some(parse_public_key(&public_key).map_err(|e| format!("Block {}: {}", index, e))?);
+        }
+
+        match block.get("signature").and_then(Value::as_str).filter(|s| !s.is_empty()) {
+            Some(signature) => {
+                if key.is_some_and(|key| !verify(&key, hash.as_bytes(), signature)) {
+                    return Err(format!("Block {}: bad signature", index));
+                }
+                check.signed += 1;
+            }
+            None if version >= CANONICAL_VERSION => return Err(format!("Block {}: unsigned", index)),
+            None => {}
+        }
+
+        *check.versions.entry(version).or_default() += 1;
+        previous = hash.to_string();
+    }
+
+    check.blocks = blocks.len();
+    Ok(check)
+}
+
+// Moves a chain signed by `key` to this process's key without touching its history: every block
+// keeps its version, hash and signature, and a `migration` block signed by this process records
+// the handover. Unsigned chains are refused, since nothing would vouch for their content.
+pub fn migrate_chain(blocks: &[Value], key: &VerifyingKey) -> Result<Vec<Value>, String> {
+    let check = verify_exported_chain(blocks, Some(key))?;
+    if let Some(index) = blocks
+        .iter()
+        .position(|b| b.get("signature").and_then(Value::as_str).is_none_or(str::is_empty))
+    {
+        return Err(format!("Block {}: unsigned; only signed chains can be migrated", index));
+    }
+    let last = blocks.last().ok_or("Empty chain")?;
+    let local_key = public_key().ok_or("No signing key loaded")?;
+
+    let block = TradeBlock::new(
+        check.blocks as u64,
+        BlockEvent::Migration {
+            previous_key: key_hex(key),
+            public_key: key_hex(&local_key),
+        },
+        str_field(last, check.blocks - 1, "hash")?.to_string(),
+    );
+
+    let mut migrated = blocks.to_vec();
+    migrated.push(serde_json::to_value(block).map_err(|e| e.to_string())?);
+    Ok(migrated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_encoding::{block_hash, CURRENT_VERSION};
+    use crate::signing::init_test_key;
+    use ed25519_dalek::{Signer, SigningKey};
+    use serde_json::json;
+
+    // Chains as `chain export` wrote them before each schema change, with hashes computed outside
+    // this crate from the version 0 and 1 rules.
+    fn version_0_chain() -> Vec<Value> {
+        vec![
+            json!({
+                "index": 0,
+                "timestamp": 1600000000000u64,
+                "trade": {"symbol": "BTCUSDT", "current_price": "100.0", "status": null},
+                "previous_hash": "0",
+                "hash": "043ca9e049091c22b3b032019e258e68258d88363b42925c31f50d9d14d6087d"
+            }),
+            json!({
+                "index": 1,
+                "timestamp": 1600000060000u64,
+                "trade": {"symbol": "BTCUSDT", "current_price": "110.0", "status": "InZone7"},
+                "previous_hash": "043ca9e049091c22b3b032019e258e68258d88363b42925c31f50d9d14d6087d",
+                "hash": "0002b874653ae544ba3d7be3f2f43903268a30e1d9996c23d610fcd4fcf00faf"
+            }),
+        ]
+    }
+
+    fn version_1_chain() -> Vec<Value> {
+        vec![
+            json!({
+                "index": 0,
+                "timestamp": 1650000000000u64,
+                "event": {
+                    "type": "status_change",
+                    "trade": {"symbol": "ETHUSDT", "current_price": "2000.0", "status": null}
+                },
+                "previous_hash": "0",
+                "hash": "3b4e8feb1e8d6d44edba844b22a16d352228b8eca112d8744005144cf2222ecf"
+            }),
+            json!({
+                "index": 1,
+                "timestamp": 1650000060000u64,
+                "event": {"type": "leverage_change", "leverage": 3},
+                "previous_hash": "3b4e8feb1e8d6d44edba844b22a16d352228b8eca112d8744005144cf2222ecf",
+                "hash": "4148cd108d20809698e30e1f1c2a31a5c62bad30380eccd6661b5d4c477ee232"
+            }),
+        ]
+    }
+
+    #[test]
+    fn accepts_version_0_and_1_exports() {
+        for (blocks, version) in [(version_0_chain(), LEGACY_TRADE_VERSION), (version_1_chain(), JSON_EVENT_VERSION)] {
+            let check = verify_exported_chain(&blocks, None).unwrap();
+            assert_eq!(check.blocks, 2);
+            assert_eq!(check.versions.get(&version), Some(&2));
+            assert_eq!(check.signed, 0);
+        }
+    }
+
+    #[test]
+    fn rejects_a_tampered_block() {
+        let mut blocks = version_0_chain();
+        blocks[1]["trade"]["current_price"] = json!("120.0");
+        let error = verify_exported_chain(&blocks, None).unwrap_err();
+        assert!(error.starts_with("Block 1: hash does not match"), "{}", error);
+
+        let mut blocks = version_1_chain();
+        blocks[1]["event"]["leverage"] = json!(20);
+        let error = verify_exported_chain(&blocks, None).unwrap_err();
+        assert!(error.starts_with("Block 1: hash does not match"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_a_broken_link() {
+        let mut blocks = version_1_chain();
+        blocks.remove(0);
+        assert!(verify_exported_chain(&blocks, None).is_err());
+
+        let mut blocks = version_1_chain();
+        blocks[1]["previous_hash"] = json!("0");
+        let error = verify_exported_chain(&blocks, None).unwrap_err();
+        assert!(error.contains("does not link"), "{}", error);
+    }
+
+    // A current chain signed by another bot's key.
+    fn signed_chain(key: &SigningKey) -> Vec<Value> {
+        let mut previous_hash = "0".to_string();
+        (0..2u64)
+            .map(|index| {
+                let event = BlockEvent::LeverageChange { leverage: 2 + index as u32 };
+                let timestamp = 1700000000 + index;
+                let hash = block_hash(CURRENT_VERSION, index, timestamp, &event, &previous_hash).unwrap();
+                let block = TradeBlock {
+                    version: CURRENT_VERSION,
+                    index,
+                    timestamp,
+                    event,
+                    previous_hash: std::mem::replace(&mut previous_hash, hash.clone()),
+                    signature: hex::encode(key.sign(hash.as_bytes()).to_bytes()),
+                    hash,
+                };
+                serde_json::to_value(block).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn migration_keeps_the_history_and_hands_over_the_key() {
+        init_test_key();
+        let source = SigningKey::from_bytes(&[7; 32]);
+        let blocks = signed_chain(&source);
+
+        let migrated = migrate_chain(&blocks, &source.verifying_key()).unwrap();
+        assert_eq!(migrated.len(), 3);
+        assert_eq!(&migrated[..2], &blocks[..]);
+        assert_eq!(migrated[2]["event"]["type"], "migration");
+        assert_eq!(migrated[2]["event"]["public_key"], key_hex(&public_key().unwrap()));
+
+        let check = verify_exported_chain(&migrated, Some(&source.verifying_key())).unwrap();
+        assert_eq!(check.signed, 3);
+
+        // The handover doesn't vouch for a chain another key signed.
+        let other = SigningKey::from_bytes(&[8; 32]).verifying_key();
+        assert!(verify_exported_chain(&migrated, Some(&other)).is_err());
+    }
+
+    #[test]
+    fn migration_refuses_unsigned_or_forged_blocks() {
+        init_test_key();
+        let source = SigningKey::from_bytes(&[7; 32]);
+
+        let error = migrate_chain(&version_1_chain(), &source.verifying_key()).unwrap_err();
+        assert!(error.contains("unsigned"), "{}", error);
+
+        // Edited content with a recomputed hash still carries the old signature.
+        let mut blocks = signed_chain(&source);
+        blocks.truncate(1);
+        let event = BlockEvent::LeverageChange { leverage: 20 };
+        let hash = block_hash(CURRENT_VERSION, 0, 1700000000, &event, "0").unwrap();
+        blocks[0]["event"] = serde_json::to_value(&event).unwrap();
+        blocks[0]["hash"] = json!(hash);
+        let error = migrate_chain(&blocks, &source.verifying_key()).unwrap_err();
+        assert!(error.contains("bad signature"), "{}", error);
+    }
+}
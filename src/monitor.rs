@@ -1,9 +1,149 @@
 use crate::blockchain::get_current_blockchain_symbols;
 use crate::config::Settings;
 use crate::crypto_metadata::get_crypto_metadata;
-use crate::dto::{Bias, Trade, TradeMonitorItem, TradeMonitorResponse, ZoneCount};
+use crate::dto::{
+    Bias, MonitorQuery, Trade, TradeMonitorItem, TradeMonitorPage, TradeMonitorResponse, ZoneCount,
+};
+use crate::error::AppError;
+use crate::history::record_snapshot;
 use chrono::Local;
-use prettytable::{color, Attr, Cell, Row, Table};
+use once_cell::sync::Lazy;
+use crate::monitor_table::{build_table, zone_summary};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+
+// Last scheduler snapshot per account, served by `/trades/monitor`.
+static SNAPSHOTS: Lazy<Mutex<HashMap<String, TradeMonitorResponse>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub fn store_snapshot(account: &str, snapshot: TradeMonitorResponse) {
+    record_snapshot(account, &snapshot);
+    SNAPSHOTS.lock().unwrap().insert(account.to_string(), snapshot);
+}
+
+pub fn last_snapshot(account: &str) -> Option<TradeMonitorResponse> {
+    SNAPSHOTS.lock().unwrap().get(account).cloned()
+}
+
+pub const SORT_COLUMNS: &[&str] = &[
+    "symbol",
+    "zone",
+    "performance_24",
+    "performance_btc_24",
+    "amplitude_ma_200",
+    "log_amplitude",
+    "log_position",
+    "volume",
+    "quote_volume",
+    "trades_count",
+    "taker_buy_base_volume",
+    "taker_buy_quote_volume",
+    "funding_rate",
+    "next_funding_time",
+];
+
+fn compare(a: &TradeMonitorItem, b: &TradeMonitorItem, column: &str) -> Ordering {
+    let numeric = |item: &TradeMonitorItem| match column {
+        "performance_24" => item.performance_24,
+        "performance_btc_24" => item.performance_btc_24,
+        "amplitude_ma_200" => item.amplitude_ma_200,
+        "log_amplitude" => item.log_amplitude,
+        "log_position" => item.log_position,
+        "volume" => item.volume,
+        "quote_volume" => item.quote_volume,
+        "trades_count" => item.trades_count,
+        "taker_buy_base_volume" => item.taker_buy_base_volume,
+        "taker_buy_quote_volume" => item.taker_buy_quote_volume,
+        "funding_rate" => item.funding_rate,
+        _ => item.next_funding_time as f64,
+    };
+
+    match column {
+        "symbol" => a.symbol.cmp(&b.symbol),
+        // Z1..Z8 sort naturally as strings; symbols without a zone go last.
+        "zone" => match (&a.zone, &b.zone) {
+            (Some(x), Some(y)) => x.cmp(y),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        },
+        _ => numeric(a).partial_cmp(&numeric(b)).unwrap_or(Ordering::Equal),
+    }
+}
+
+// Applies the filters, sorting and paging of `/trades/monitor` to a snapshot.
+pub fn page_snapshot(
+    snapshot: TradeMonitorResponse,
+    query: &MonitorQuery,
+) -> Result<TradeMonitorPage, AppError> {
+    let zones: Option<Vec<String>> = query
+        .zone
+        .as_ref()
+        .map(|z| z.split(',').map(|z| z.trim().to_uppercase()).collect());
+    let bias = query.bias.as_deref().map(Bias::parse).transpose()?;
+    let descending = match query.order.as_deref() {
+        None | Some("desc") => true,
+        Some("asc") => false,
+        Some(other) => {
+            return Err(AppError::validation(format!("order must be 'asc' or 'desc', got '{}'", other)))
+        }
+    };
+    let page = query.page.unwrap_or(1).max(1);
+    let page_size = query.page_size.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+    let mut trades: Vec<TradeMonitorItem> = snapshot
+        .trades
+        .into_iter()
+        .filter(|t| {
+            zones
+                .as_ref()
+                .is_none_or(|zones| t.zone.as_ref().is_some_and(|z| zones.contains(z)))
+        })
+        .filter(|t| bias.as_ref().is_none_or(|b| *b == t.bias))
+        .filter(|t| !query.active_only || t.is_active)
+        .filter(|t| query.min_quote_volume.is_none_or(|min| t.quote_volume >= min))
+        .collect();
+
+    if let Some(column) = query.sort.as_deref() {
+        if !SORT_COLUMNS.contains(&column) {
+            return Err(AppError::Validation(
+                format!("Unknown sort column '{}'", column),
+                Some(serde_json::json!({ "allowed": SORT_COLUMNS })),
+            ));
+        }
+        trades.sort_by(|a, b| {
+            let ordering = compare(a, b, column);
+            if descending { ordering.reverse() } else { ordering }
+        });
+    }
+
+    let total = trades.len();
+    let trades = trades
+        .into_iter()
+        .skip((page - 1) * page_size)
+        .take(page_size)
+        .collect();
+
+    Ok(TradeMonitorPage {
+        timestamp: snapshot.timestamp,
+        total,
+        page,
+        page_size,
+        trades,
+        zone_distribution: snapshot.zone_distribution,
+    })
+}
+
+// The scheduler's per-cycle table on the terminal.
+pub fn print_snapshot(snapshot: &TradeMonitorResponse, show_details: bool) {
+    println!("[{}] - Criptos monitoradas:", Local::now().format("%Y-%m-%d %H:%M:%S"));
+    build_table(&snapshot.trades, show_details).print();
+    println!("\nDistribuicao por zona: {}", zone_summary(&snapshot.zone_distribution));
+}
 
 pub fn monitor_cryptos(trades: &[Trade], settings: &Settings) -> TradeMonitorResponse {
     fn parse(value: &str) -> f64 {
@@ -12,7 +152,7 @@ pub fn monitor_cryptos(trades: &[Trade], settings: &Settings) -> TradeMonitorRes
 
     fn find_zone_index(trade: &Trade) -> Option<usize> {
         let price = parse(&trade.current_price);
-        let zones = vec![
+        let zones = [
             parse(&trade.zone_1),
             parse(&trade.zone_2),
             parse(&trade.zone_3),
@@ -33,34 +173,6 @@ pub fn monitor_cryptos(trades: &[Trade], settings: &Settings) -> TradeMonitorRes
         None
     }
 
-    fn zone_label_cell(index: Option<usize>, bias: &Bias) -> Cell {
-        match index {
-            Some(i) => {
-                let zone_str = format!("Z{}", i + 1);
-                match (i, bias) {
-                    (0, Bias::Bullish) | (6, Bias::Bullish) => {
-                        Cell::new(&zone_str).with_style(Attr::ForegroundColor(color::BLUE))
-                    }
-                    (1, Bias::Bearish) | (7, Bias::Bearish) => {
-                        Cell::new(&zone_str).with_style(Attr::ForegroundColor(color::BLUE))
-                    }
-                    _ => Cell::new(&zone_str),
-                }
-            }
-            None => Cell::new("-"),
-        }
-    }
-
-    fn color_unique(index: usize, max_index: usize, min_index: usize) -> Option<Attr> {
-        if index == max_index {
-            Some(Attr::ForegroundColor(color::GREEN))
-        } else if index == min_index {
-            Some(Attr::ForegroundColor(color::RED))
-        } else {
-            None
-        }
-    }
-
     fn calc_linear_ampl(min: f64, max: f64) -> f64 {
         if min <= 0.0 || max <= 0.0 || min >= max {
             return 0.0;
@@ -75,147 +187,22 @@ pub fn monitor_cryptos(trades: &[Trade], settings: &Settings) -> TradeMonitorRes
         ((price - min) / (max - min)) * 100.0
     }
 
-    fn extract_column(values: &[(f64, f64, f64, f64, f64, f64, f64, f64, f64, f64)], index: usize) -> Vec<f64> {
-        values.iter().map(|v| match index {
-            0 => v.0,
-            1 => v.1,
-            2 => v.2,
-            3 => v.3,
-            4 => v.4,
-            5 => v.5,
-            6 => v.6,
-            7 => v.7,
-            8 => v.8,
-            9 => v.9,
-            _ => 0.0,
-        }).collect()
-    }
-
-    fn max_index(v: &[f64]) -> usize {
-        v.iter().enumerate().max_by(|a, b| a.1.partial_cmp(b.1).unwrap()).map(|(i, _)| i).unwrap_or(0)
-    }
-
-    fn min_index(v: &[f64]) -> usize {
-        v.iter().enumerate().min_by(|a, b| a.1.partial_cmp(b.1).unwrap()).map(|(i, _)| i).unwrap_or(0)
-    }
-
-    print!("\x1B[2J\x1B[1;1H");
     let now = Local::now();
-    println!("[{}] - Criptos monitoradas:", now.format("%Y-%m-%d %H:%M:%S"));
-
     let metadata_list = get_crypto_metadata();
-    let active_symbols = get_current_blockchain_symbols();
-    let mut table = Table::new();
-    let show_details = settings.show_details_monitor;
-
-    if show_details {
-        table.add_row(Row::new(vec![
-            Cell::new("Symbol"), Cell::new("Zone"), Cell::new("24h"), Cell::new("BTC"), Cell::new("MA200"),
-            Cell::new("Ampl"), Cell::new("Pos%"), Cell::new("Volume"), Cell::new("Quote Volume"),
-            Cell::new("Trades"), Cell::new("Taker Base"), Cell::new("Taker Quote"),
-        ]));
-    } else {
-        table.add_row(Row::new(vec![
-            Cell::new("Symbol"), Cell::new("Zone"), Cell::new("24h"), Cell::new("BTC"), Cell::new("MA200"),
-        ]));
-    }
-
-    let values: Vec<_> = trades.iter().map(|t| {
-        let min = parse(&t.zone_min);
-        let max = parse(&t.zone_max);
-        let current = parse(&t.current_price);
-        (
-            parse(&t.performance_24),
-            parse(&t.performance_btc_24),
-            parse(&t.amplitude_ma_200),
-            calc_linear_ampl(min, max),
-            calc_linear_position(current, min, max),
-            parse(&t.volume),
-            parse(&t.quote_asset_volume),
-            parse(&t.number_of_trades),
-            parse(&t.taker_buy_base_asset_volume),
-            parse(&t.taker_buy_quote_asset_volume),
-        )
-    }).collect();
-
-    let perf_col = extract_column(&values, 0);
-    let btc_col = extract_column(&values, 1);
-    let ma200_col = extract_column(&values, 2);
-    let ampl_col = extract_column(&values, 3);
-    let pos_col = extract_column(&values, 4);
-    let volume_col = extract_column(&values, 5);
-    let quote_col = extract_column(&values, 6);
-    let trades_col = extract_column(&values, 7);
-    let taker_base_col = extract_column(&values, 8);
-    let taker_quote_col = extract_column(&values, 9);
-
-    let max_perf = max_index(&perf_col);
-    let min_perf = min_index(&perf_col);
-    let max_btc = max_index(&btc_col);
-    let min_btc = min_index(&btc_col);
-    let max_ma200 = max_index(&ma200_col);
-    let min_ma200 = min_index(&ma200_col);
-    let max_ampl = max_index(&ampl_col);
-    let min_ampl = min_index(&ampl_col);
-    let max_pos = max_index(&pos_col);
-    let min_pos = min_index(&pos_col);
-    let max_vol = max_index(&volume_col);
-    let min_vol = min_index(&volume_col);
-    let max_quote = max_index(&quote_col);
-    let min_quote = min_index(&quote_col);
-    let max_trades = max_index(&trades_col);
-    let min_trades = min_index(&trades_col);
-    let max_taker_base = max_index(&taker_base_col);
-    let min_taker_base = min_index(&taker_base_col);
-    let max_taker_quote = max_index(&taker_quote_col);
-    let min_taker_quote = min_index(&taker_quote_col);
+    let active_symbols = get_current_blockchain_symbols(&settings.binance.account);
 
     let mut json_items = vec![];
     let mut zone_counts = [0usize; 8];
 
-    for (i, t) in trades.iter().enumerate() {
+    for t in trades {
         let zone_index = find_zone_index(t);
         if let Some(z) = zone_index {
             zone_counts[z] += 1;
         }
 
-        let mut symbol_cell = Cell::new(&t.symbol);
-        if active_symbols.contains(&t.symbol) {
-            symbol_cell = symbol_cell.with_style(Attr::ForegroundColor(color::YELLOW));
-        }
-
-        let mut row = vec![
-            symbol_cell,
-            zone_label_cell(zone_index, &t.bias),
-        ];
-
-        macro_rules! push_cell {
-            ($vec:expr, $col:expr, $i:expr, $max:expr, $min:expr) => {
-                {
-                    let mut cell = Cell::new(&format!("{:.2}", $col[$i]));
-                    if let Some(attr) = color_unique($i, $max, $min) {
-                        cell = cell.with_style(attr);
-                    }
-                    $vec.push(cell);
-                }
-            };
-        }
-
-        push_cell!(row, perf_col, i, max_perf, min_perf);
-        push_cell!(row, btc_col, i, max_btc, min_btc);
-        push_cell!(row, ma200_col, i, max_ma200, min_ma200);
-
-        if show_details {
-            push_cell!(row, ampl_col, i, max_ampl, min_ampl);
-            push_cell!(row, pos_col, i, max_pos, min_pos);
-            push_cell!(row, volume_col, i, max_vol, min_vol);
-            push_cell!(row, quote_col, i, max_quote, min_quote);
-            push_cell!(row, trades_col, i, max_trades, min_trades);
-            push_cell!(row, taker_base_col, i, max_taker_base, min_taker_base);
-            push_cell!(row, taker_quote_col, i, max_taker_quote, min_taker_quote);
-        }
-
-        table.add_row(Row::new(row));
+        let min = parse(&t.zone_min);
+        let max = parse(&t.zone_max);
+        let current = parse(&t.current_price);
 
         let base_symbol = t.symbol.trim_end_matches("USDT");
         let metadata = metadata_list.iter().find(|m| m.symbol.eq_ignore_ascii_case(base_symbol));
@@ -223,16 +210,19 @@ pub fn monitor_cryptos(trades: &[Trade], settings: &Settings) -> TradeMonitorRes
         json_items.push(TradeMonitorItem {
             symbol: t.symbol.clone(),
             zone: zone_index.map(|i| format!("Z{}", i + 1)),
-            performance_24: perf_col[i],
-            performance_btc_24: btc_col[i],
-            amplitude_ma_200: ma200_col[i],
-            log_amplitude: ampl_col[i],
-            log_position: pos_col[i],
-            volume: volume_col[i],
-            quote_volume: quote_col[i],
-            trades_count: trades_col[i],
-            taker_buy_base_volume: taker_base_col[i],
-            taker_buy_quote_volume: taker_quote_col[i],
+            bias: t.bias.clone(),
+            performance_24: parse(&t.performance_24),
+            performance_btc_24: parse(&t.performance_btc_24),
+            amplitude_ma_200: parse(&t.amplitude_ma_200),
+            log_amplitude: calc_linear_ampl(min, max),
+            log_position: calc_linear_position(current, min, max),
+            volume: parse(&t.volume),
+            quote_volume: parse(&t.quote_asset_volume),
+            trades_count: parse(&t.number_of_trades),
+            taker_buy_base_volume: parse(&t.taker_buy_base_asset_volume),
+            taker_buy_quote_volume: parse(&t.taker_buy_quote_asset_volume),
+            funding_rate: parse(&t.funding_rate),
+            next_funding_time: t.next_funding_time,
             is_active: active_symbols.contains(&t.symbol),
             logo: metadata.and_then(|m| m.logo.clone()),
             name: metadata.and_then(|m| m.name.clone()),
@@ -243,18 +233,6 @@ pub fn monitor_cryptos(trades: &[Trade], settings: &Settings) -> TradeMonitorRes
         });
     }
 
-    table.printstd();
-
-    println!(
-        "\nDistribuicao por zona: {}",
-        zone_counts
-            .iter()
-            .enumerate()
-            .map(|(i, count)| format!("Z{}: {}", i + 1, count))
-            .collect::<Vec<_>>()
-            .join(" | ")
-    );
-
     let json_distribution: Vec<ZoneCount> = zone_counts
         .iter()
         .enumerate()
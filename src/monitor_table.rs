@@ -0,0 +1,261 @@
+use crate::dto::{Bias, TradeMonitorItem, ZoneCount};
+use crate::error::AppError;
+use prettytable::{color, Attr, Cell, Row, Table};
+
+// Text formats cannot carry the terminal colors, so each highlight gets a marker instead.
+pub const LEGEND: &str = "▲ maior valor | ▼ menor valor | * cadeia aberta | [Zn] zona extrema a favor do bias";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mark {
+    Max,
+    Min,
+    Active,
+    Highlight,
+}
+
+#[derive(Debug, Clone)]
+pub struct MonitorCell {
+    pub text: String,
+    pub mark: Option<Mark>,
+}
+
+impl MonitorCell {
+    fn plain(text: impl Into<String>) -> Self {
+        MonitorCell {
+            text: text.into(),
+            mark: None,
+        }
+    }
+
+    pub fn marked(&self) -> String {
+        match self.mark {
+            Some(Mark::Max) => format!("{} ▲", self.text),
+            Some(Mark::Min) => format!("{} ▼", self.text),
+            Some(Mark::Active) => format!("*{}", self.text),
+            Some(Mark::Highlight) => format!("[{}]", self.text),
+            None => self.text.clone(),
+        }
+    }
+
+    fn color(&self) -> Option<Attr> {
+        match self.mark? {
+            Mark::Max => Some(Attr::ForegroundColor(color::GREEN)),
+            Mark::Min => Some(Attr::ForegroundColor(color::RED)),
+            Mark::Active => Some(Attr::ForegroundColor(color::YELLOW)),
+            Mark::Highlight => Some(Attr::ForegroundColor(color::BLUE)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MonitorTable {
+    pub headers: Vec<&'static str>,
+    pub rows: Vec<Vec<MonitorCell>>,
+}
+
+type Column = (&'static str, fn(&TradeMonitorItem) -> f64);
+
+const SUMMARY_COLUMNS: &[Column] = &[
+    ("24h", |t| t.performance_24),
+    ("BTC", |t| t.performance_btc_24),
+    ("MA200", |t| t.amplitude_ma_200),
+];
+
+const DETAIL_COLUMNS: &[Column] = &[
+    ("Ampl", |t| t.log_amplitude),
+    ("Pos%", |t| t.log_position),
+    ("Volume", |t| t.volume),
+    ("Quote Volume", |t| t.quote_volume),
+    ("Trades", |t| t.trades_count),
+    ("Taker Base", |t| t.taker_buy_base_volume),
+    ("Taker Quote", |t| t.taker_buy_quote_volume),
+];
+
+fn max_index(v: &[f64]) -> usize {
+    v.iter().enumerate().max_by(|a, b| a.1.partial_cmp(b.1).unwrap()).map(|(i, _)| i).unwrap_or(0)
+}
+
+fn min_index(v: &[f64]) -> usize {
+    v.iter().enumerate().min_by(|a, b| a.1.partial_cmp(b.1).unwrap()).map(|(i, _)| i).unwrap_or(0)
+}
+
+// Z1/Z7 on a bullish bias and Z2/Z8 on a bearish one are the zones the strategy waits for.
+fn zone_cell(item: &TradeMonitorItem) -> MonitorCell {
+    let Some(zone) = &item.zone else {
+        return MonitorCell::plain("-");
+    };
+
+    let highlight = matches!(
+        (zone.as_str(), &item.bias),
+        ("Z1" | "Z7", Bias::Bullish) | ("Z2" | "Z8", Bias::Bearish)
+    );
+    MonitorCell {
+        text: zone.clone(),
+        mark: highlight.then_some(Mark::Highlight),
+    }
+}
+
+// Builds the monitor table once so the terminal and every HTTP format show the same thing.
+pub fn build_table(items: &[TradeMonitorItem], show_details: bool) -> MonitorTable {
+    let columns: Vec<&Column> = if show_details {
+        SUMMARY_COLUMNS.iter().chain(DETAIL_COLUMNS).collect()
+    } else {
+        SUMMARY_COLUMNS.iter().collect()
+    };
+
+    let mut headers = vec!["Symbol", "Zone"];
+    headers.extend(columns.iter().map(|(name, _)| *name));
+
+    let values: Vec<Vec<f64>> = columns
+        .iter()
+        .map(|(_, value)| items.iter().map(value).collect())
+        .collect();
+    let extremes: Vec<(usize, usize)> = values.iter().map(|v| (max_index(v), min_index(v))).collect();
+
+    let rows = items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let mut row = vec![
+                MonitorCell {
+                    text: item.symbol.clone(),
+                    mark: item.is_active.then_some(Mark::Active),
+                },
+                zone_cell(item),
+            ];
+            row.extend(values.iter().zip(&extremes).map(|(column, (max, min))| MonitorCell {
+                text: format!("{:.2}", column[i]),
+                mark: if i == *max {
+                    Some(Mark::Max)
+                } else if i == *min {
+                    Some(Mark::Min)
+                } else {
+                    None
+                },
+            }));
+            row
+        })
+        .collect();
+
+    MonitorTable { headers, rows }
+}
+
+pub fn zone_summary(distribution: &[ZoneCount]) -> String {
+    distribution
+        .iter()
+        .map(|z| format!("{}: {}", z.zone, z.count))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+pub struct RenderedMonitor {
+    pub body: String,
+    pub content_type: &'static str,
+    pub extension: &'static str,
+}
+
+// The text formats of `/trades/monitor`, also printed by the `scan` command.
+pub fn render(
+    format: &str,
+    items: &[TradeMonitorItem],
+    timestamp: &str,
+    distribution: &[ZoneCount],
+    show_details: bool,
+) -> Result<RenderedMonitor, AppError> {
+    let table = build_table(items, show_details);
+    let heading = format!("[{}] - Criptos monitoradas", timestamp);
+    let zones = format!("Distribuicao por zona: {}", zone_summary(distribution));
+
+    let (body, content_type, extension) = match format {
+        "text" => (
+            format!("{}:\n{}\n{}\n{}\n", heading, table.to_text(), zones, LEGEND),
+            "text/plain; charset=utf-8",
+            "txt",
+        ),
+        "csv" => (table.to_csv(), "text/csv; charset=utf-8", "csv"),
+        "markdown" => (
+            format!("## {}\n\n{}\n{}\n\n{}\n", heading, table.to_markdown(), zones, LEGEND),
+            "text/markdown; charset=utf-8",
+            "md",
+        ),
+        other => {
+            return Err(AppError::validation(format!(
+                "Unknown format '{}', expected json, text, csv or markdown",
+                other
+            )))
+        }
+    };
+
+    Ok(RenderedMonitor {
+        body,
+        content_type,
+        extension,
+    })
+}
+
+impl MonitorTable {
+    fn pretty(&self, colored: bool) -> Table {
+        let mut table = Table::new();
+        table.add_row(Row::new(self.headers.iter().map(|h| Cell::new(h)).collect()));
+        for row in &self.rows {
+            table.add_row(Row::new(
+                row.iter()
+                    .map(|cell| match (colored, cell.color()) {
+                        (true, Some(attr)) => Cell::new(&cell.text).with_style(attr),
+                        (true, None) => Cell::new(&cell.text),
+                        (false, _) => Cell::new(&cell.marked()),
+                    })
+                    .collect(),
+            ));
+        }
+        table
+    }
+
+    pub fn print(&self) {
+        self.pretty(true).printstd();
+    }
+
+    pub fn to_text(&self) -> String {
+        self.pretty(false).to_string()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = csv_line(self.headers.iter().map(|h| h.to_string()));
+        for row in &self.rows {
+            out.push_str(&csv_line(row.iter().map(MonitorCell::marked)));
+        }
+        out
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let line = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+
+        let mut out = line(self.headers.iter().map(|h| h.to_string()).collect());
+        out.push_str(&line(
+            (0..self.headers.len())
+                .map(|i| if i < 2 { "---".to_string() } else { "---:".to_string() })
+                .collect(),
+        ));
+        for row in &self.rows {
+            out.push_str(&line(
+                row.iter()
+                    .map(|c| c.marked().replace('|', "\\|").replace('*', "\\*"))
+                    .collect(),
+            ));
+        }
+        out
+    }
+}
+
+fn csv_line(cells: impl Iterator<Item = String>) -> String {
+    let cells: Vec<String> = cells
+        .map(|c| {
+            if c.contains([',', '"', '\n']) {
+                format!("\"{}\"", c.replace('"', "\"\""))
+            } else {
+                c
+            }
+        })
+        .collect();
+    format!("{}\n", cells.join(","))
+}
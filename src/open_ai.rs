@@ -1,6 +1,7 @@
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use crate::credential::get_credentials;
+use crate::credential::open_ai_key;
+use crate::error::AppError;
 use std::time::Duration;
 use tokio::time::sleep;
 
@@ -37,11 +38,11 @@ pub struct AssistantResponse {
     pub content: String,
 }
 
-pub async fn send_to_assistant() -> Result<AssistantResponse, String> {
+pub async fn send_to_assistant() -> Result<AssistantResponse, AppError> {
     const PERGUNTA: &str = "Como funciona a Ethereum?";
 
-    let credentials = get_credentials();
-    let api_key = &credentials.open_ai_key;
+    let api_key = open_ai_key()?;
+    let api_key: &str = &api_key;
     let client = Client::new();
 
     // 1. Cria o assistente
@@ -55,7 +56,7 @@ pub async fn send_to_assistant() -> Result<AssistantResponse, String> {
         })
         .send()
         .await
-        .map_err(|e| format!("Erro criando assistente: {:?}", e))?;
+        .map_err(|e| AppError::Exchange(format!("Erro criando assistente: {:?}", e)))?;
 
     let status = response.status();
     let text = response.text().await.unwrap_or_default();
@@ -63,11 +64,11 @@ pub async fn send_to_assistant() -> Result<AssistantResponse, String> {
     println!("\n🔍 Resposta da OpenAI (status: {}):\n{}", status, text);
 
     if !status.is_success() {
-        return Err(format!("Erro ao criar assistente: {}", text));
+        return Err(AppError::Exchange(format!("Erro ao criar assistente: {}", text)));
     }
 
     let assistant: Assistant = serde_json::from_str(&text)
-        .map_err(|e| format!("Erro no JSON do assistente: {:?}", e))?;
+        .map_err(|e| AppError::Exchange(format!("Erro no JSON do assistente: {:?}", e)))?;
 
     // 2. Cria uma thread
     let thread: Thread = client
@@ -76,10 +77,10 @@ pub async fn send_to_assistant() -> Result<AssistantResponse, String> {
         .header("OpenAI-Beta", "assistants=v2")
         .send()
         .await
-        .map_err(|e| format!("Erro criando thread: {:?}", e))?
+        .map_err(|e| AppError::Exchange(format!("Erro criando thread: {:?}", e)))?
         .json()
         .await
-        .map_err(|e| format!("Erro no JSON da thread: {:?}", e))?;
+        .map_err(|e| AppError::Exchange(format!("Erro no JSON da thread: {:?}", e)))?;
 
     // 3. Adiciona a pergunta
     client
@@ -92,7 +93,7 @@ pub async fn send_to_assistant() -> Result<AssistantResponse, String> {
         })
         .send()
         .await
-        .map_err(|e| format!("Erro adicionando mensagem: {:?}", e))?;
+        .map_err(|e| AppError::Exchange(format!("Erro adicionando mensagem: {:?}", e)))?;
 
     // 4. Executa o assistente
     let run: Run = client
@@ -102,10 +103,10 @@ pub async fn send_to_assistant() -> Result<AssistantResponse, String> {
         .json(&serde_json::json!({ "assistant_id": assistant.id }))
         .send()
         .await
-        .map_err(|e| format!("Erro iniciando run: {:?}", e))?
+        .map_err(|e| AppError::Exchange(format!("Erro iniciando run: {:?}", e)))?
         .json()
         .await
-        .map_err(|e| format!("Erro no JSON da run: {:?}", e))?;
+        .map_err(|e| AppError::Exchange(format!("Erro no JSON da run: {:?}", e)))?;
 
     // 5. Aguarda a execução (até 30s)
     let mut status = run.status;
@@ -119,16 +120,16 @@ pub async fn send_to_assistant() -> Result<AssistantResponse, String> {
             .header("OpenAI-Beta", "assistants=v2")
             .send()
             .await
-            .map_err(|e| format!("Erro ao verificar status da run: {:?}", e))?
+            .map_err(|e| AppError::Exchange(format!("Erro ao verificar status da run: {:?}", e)))?
             .json()
             .await
-            .map_err(|e| format!("Erro no JSON da run polling: {:?}", e))?;
+            .map_err(|e| AppError::Exchange(format!("Erro no JSON da run polling: {:?}", e)))?;
         status = r.status.clone();
         attempts += 1;
     }
 
     if status != "completed" {
-        return Err("Timeout esperando resposta do assistente.".into());
+        return Err(AppError::Exchange("Timeout esperando resposta do assistente.".into()));
     }
 
     // 6. Obtém a resposta final
@@ -138,10 +139,10 @@ pub async fn send_to_assistant() -> Result<AssistantResponse, String> {
         .header("OpenAI-Beta", "assistants=v2")
         .send()
         .await
-        .map_err(|e| format!("Erro ao obter mensagens: {:?}", e))?
+        .map_err(|e| AppError::Exchange(format!("Erro ao obter mensagens: {:?}", e)))?
         .json()
         .await
-        .map_err(|e| format!("Erro no JSON das mensagens: {:?}", e))?;
+        .map_err(|e| AppError::Exchange(format!("Erro no JSON das mensagens: {:?}", e)))?;
 
     if let Some(answer) = messages["data"]
         .as_array()
@@ -154,6 +155,6 @@ pub async fn send_to_assistant() -> Result<AssistantResponse, String> {
             content: answer.to_string(),
         })
     } else {
-        Err("Não foi possível extrair resposta.".into())
+        Err(AppError::Exchange("Não foi possível extrair resposta.".into()))
     }
 }
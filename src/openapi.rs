@@ -0,0 +1,142 @@
+use crate::api;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use utoipa_swagger_ui::SwaggerUi;
+
+pub const SPEC_PATH: &str = "/openapi.json";
+pub const EXPLORER_PATH: &str = "/docs";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "RKD High Frequency Trading"),
+    paths(
+        api::get_accounts,
+        api::post_trades_start,
+        api::post_trades_stop,
+        api::get_trades_health_check,
+        api::get_trades_chain_by_symbol,
+        api::get_last_trade_by_symbol,
+        api::get_all_symbols_chains,
+        api::get_chain_validity,
+        api::get_public_key,
+        api::get_trades_blocks,
+        api::get_trades_checkpoints,
+        api::get_block_proof,
+        api::get_chains_summary,
+        api::get_chains_export,
+        api::post_chains_import,
+        api::get_trades_balance,
+        api::post_trades_order,
+        api::post_close_all_positions,
+        api::get_trades_executions,
+        api::get_trades_orders,
+        api::post_reconcile_orders,
+        api::put_leverage,
+        api::get_leverage,
+        api::get_liquidation_risk,
+        api::get_trades_funding,
+        api::get_trades_spy,
+        api::get_trades_monitor,
+        api::post_monitor_assistant,
+        api::get_settings,
+        api::put_settings,
+        api::get_events,
+        api::get_history_zones,
+        api::get_history_breadth,
+        api::get_history_symbol,
+    ),
+    modifiers(&Security),
+    security(("bearer" = []), ("signature" = [])),
+    tags(
+        (name = "accounts", description = "Configured Binance accounts"),
+        (name = "scheduler", description = "Start, stop and check the trading loop"),
+        (name = "chains", description = "Per-symbol trade chains"),
+        (name = "trading", description = "Orders, balances and executions"),
+        (name = "risk", description = "Leverage, liquidation and funding"),
+        (name = "market", description = "Indicators and monitoring"),
+        (name = "settings", description = "Live settings"),
+        (name = "history", description = "Stored monitor snapshots and market breadth"),
+        (name = "events", description = "Live stream of monitor snapshots, blocks, orders and risk alerts"),
+    )
+)]
+pub struct ApiDoc;
+
+// Documents both ways of authenticating described in the README.
+struct Security;
+
+impl Modify for Security {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+        components.add_security_scheme(
+            "signature",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                "X-RKD-Signature",
+                "HMAC-SHA256 of \"timestamp:nonce:METHOD:path:sha256(body)\", sent with X-RKD-Client, X-RKD-Timestamp and X-RKD-Nonce",
+            ))),
+        );
+    }
+}
+
+// Serves the spec at `/openapi.json` and the explorer at `/docs/`.
+pub fn explorer() -> SwaggerUi {
+    SwaggerUi::new(format!("{}/{{_:.*}}", EXPLORER_PATH)).url(SPEC_PATH, ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every `#[get("...")]`-style route declared in api.rs, as (method, path).
+    fn declared_routes() -> Vec<(String, String)> {
+        include_str!("api.rs")
+            .lines()
+            .filter_map(|line| {
+                let (method, rest) = line.trim().strip_prefix("#[")?.split_once("(\"")?;
+                if !["get", "post", "put", "delete", "patch"].contains(&method) {
+                    return None;
+                }
+                Some((method.to_string(), rest.split_once('"')?.0.to_string()))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_route_is_documented() {
+        let spec = ApiDoc::openapi();
+        let routes = declared_routes();
+        assert!(!routes.is_empty(), "no routes found in api.rs");
+
+        let missing: Vec<String> = routes
+            .iter()
+            .filter(|(method, path)| {
+                let operation = spec.paths.paths.get(path).and_then(|item| match method.as_str() {
+                    "get" => item.get.as_ref(),
+                    "post" => item.post.as_ref(),
+                    "put" => item.put.as_ref(),
+                    "delete" => item.delete.as_ref(),
+                    _ => item.patch.as_ref(),
+                });
+                operation.is_none()
+            })
+            .map(|(method, path)| format!("{} {}", method.to_uppercase(), path))
+            .collect();
+
+        assert!(missing.is_empty(), "routes missing from the OpenAPI spec: {:?}", missing);
+    }
+
+    #[test]
+    fn spec_has_no_undeclared_routes() {
+        let routes = declared_routes();
+        for path in ApiDoc::openapi().paths.paths.keys() {
+            assert!(
+                routes.iter().any(|(_, route)| route == path),
+                "{} is documented but not served by api.rs",
+                path
+            );
+        }
+    }
+}
@@ -1,11 +1,18 @@
-﻿use crate::binance::{get_current_price, get_lot_size_info};
-use crate::credential::get_credentials;
+﻿use crate::binance::{get_current_price, get_lot_size_info, get_position_risk};
+use crate::blockchain::{record_event, BlockEvent};
+use crate::leverage::{ensure_margin_type, get_leverage_brackets, max_notional_for};
+use crate::credential::binance_credentials;
 use crate::dto::OrderResponse;
+use crate::order_state::{
+    begin_order, get_order_records, get_orders_in_state, leg_client_order_id, mark_response, mark_state, OrderRecord,
+    OrderState,
+};
 use crate::config::{BinanceSettings, Settings};
+use crate::error::AppError;
 use hmac::{Hmac, Mac};
 use reqwest::{
     header::{HeaderMap, HeaderValue, CONTENT_TYPE},
-    Client,
+    Client, StatusCode,
 };
 use sha2::Sha256;
 use std::collections::HashMap;
@@ -14,6 +21,25 @@ use url::form_urlencoded;
 
 type HmacSha256 = Hmac<Sha256>;
 
+// How long a Pending order is left alone before reconcile looks it up on Binance.
+const PENDING_GRACE_MS: u64 = 60_000;
+
+// Binance codes that leave an order's fate open: -1000 unknown error, -1001 internal disconnect,
+// -1006 unexpected response, -1007 timeout ("execution status unknown").
+const UNKNOWN_OUTCOME_CODES: [i64; 4] = [-1000, -1001, -1006, -1007];
+
+// Only a 4xx answer with a Binance error code means the order was refused. A 5xx, a timeout code
+// or an unreadable body may still have placed it, so reconcile looks it up before it can be resent.
+fn failed_order_state(status: StatusCode, body: &str) -> OrderState {
+    let code = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v.get("code").and_then(serde_json::Value::as_i64));
+    match code {
+        Some(code) if status.is_client_error() && !UNKNOWN_OUTCOME_CODES.contains(&code) => OrderState::Rejected,
+        _ => OrderState::Unknown,
+    }
+}
+
 fn round_quantity(value: f64, step: f64) -> f64 {
     (value / step).floor() * step
 }
@@ -30,7 +56,7 @@ fn sign_query(query: &str, secret: &str) -> String {
     hex::encode(mac.finalize().into_bytes())
 }
 
-async fn get_server_time_offset(settings: &BinanceSettings) -> Result<i64, String> {
+async fn get_server_time_offset(settings: &BinanceSettings) -> Result<i64, AppError> {
     let client = Client::new();
     let time_url = format!("{}/time", settings.future_url);
 
@@ -38,17 +64,19 @@ async fn get_server_time_offset(settings: &BinanceSettings) -> Result<i64, Strin
         .get(&time_url)
         .send()
         .await
-        .map_err(|e| format!("Error querying /time: {:?}", e))?;
+        .map_err(|e| AppError::Exchange(format!("Error querying /time: {:?}", e)))?;
 
     let json: serde_json::Value = res
         .json()
         .await
-        .map_err(|e| format!("Error parsing /time: {:?}", e))?;
+        .map_err(|e| AppError::Exchange(format!("Error parsing /time: {:?}", e)))?;
 
-    let server_time = json["serverTime"].as_i64().ok_or("serverTime field missing")?;
+    let server_time = json["serverTime"]
+        .as_i64()
+        .ok_or_else(|| AppError::Exchange("serverTime field missing".into()))?;
     let local_time = SystemTime::now()
         .duration_since(UNIX_EPOCH)
-        .map_err(|_| "Local clock error")?
+        .map_err(|_| AppError::Internal("Local clock error".into()))?
         .as_millis() as i64;
 
     Ok(server_time - local_time)
@@ -58,31 +86,19 @@ pub async fn execute_future_order(
     settings: &BinanceSettings,
     side: &str,
     symbol: &str,
-) -> Result<OrderResponse, String> {
-    let credentials = get_credentials();
-    let api_key = &credentials.key;
-    let secret_key = &credentials.secret;
-
-    let base_url = format!("{}/order", settings.future_url);
-
-    let offset = get_server_time_offset(settings).await.unwrap_or(0);
-    let timestamp = (get_timestamp() as i64 + offset) as u64;
-    let timestamp_str = timestamp.to_string();
-
+    client_order_id: &str,
+) -> Result<OrderResponse, AppError> {
     let preco_btc = get_current_price(settings, symbol).await?;
     let lot_size_info = get_lot_size_info(settings, symbol).await?;
 
-    let config = Settings::load();
-    let money = config.money;
+    let money = Settings::current()
+        .for_account(&settings.account)
+        .map(|s| s.money)
+        .ok_or_else(|| AppError::Config(format!("Unknown account {}", settings.account)))?;
 
     let quantity_raw = money / preco_btc;
     let quantity = round_quantity(quantity_raw, lot_size_info.step_size);
-
-    let precision = (1.0 / lot_size_info.step_size).log10().round() as usize;
-    let quantity_str = format!("{:.*}", precision, quantity)
-        .trim_end_matches('0')
-        .trim_end_matches('.')
-        .to_string();
+    let quantity_str = format_quantity(quantity, lot_size_info.step_size);
 
     println!(
         "Sending order with side: '{}', quantity: '{}' (USDT: {}, Cryptocurrency Price: {}, StepSize: {})",
@@ -91,17 +107,63 @@ pub async fn execute_future_order(
 
     let notional = quantity * preco_btc;
     if notional < 20.0 {
-        return Err(format!(
+        return Err(AppError::RiskRejected(format!(
             "Total order value ({:.2} USDT) is less than the minimum required (20 USDT)",
             notional
-        ));
+        )));
     }
 
+    ensure_margin_type(settings, symbol)
+        .await
+        .map_err(|e| e.context("Error applying margin type"))?;
+
+    check_max_notional(settings, symbol, notional).await?;
+
+    submit_order(settings, symbol, side, &quantity_str, false, client_order_id).await
+}
+
+fn format_quantity(quantity: f64, step_size: f64) -> String {
+    let precision = (1.0 / step_size).log10().round() as usize;
+    format!("{:.*}", precision, quantity)
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+fn signed_headers(api_key: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    headers.insert("X-MBX-APIKEY", HeaderValue::from_str(api_key).unwrap());
+    headers
+}
+
+async fn submit_order(
+    settings: &BinanceSettings,
+    symbol: &str,
+    side: &str,
+    quantity_str: &str,
+    reduce_only: bool,
+    client_order_id: &str,
+) -> Result<OrderResponse, AppError> {
+    let account = settings.account.as_str();
+    let credentials = binance_credentials(account)?;
+
+    begin_order(account, client_order_id, symbol, side, quantity_str, reduce_only)?;
+
+    let offset = get_server_time_offset(settings).await.unwrap_or(0);
+    let timestamp = (get_timestamp() as i64 + offset) as u64;
+    let timestamp_str = timestamp.to_string();
+
     let mut params = HashMap::new();
     params.insert("symbol", symbol);
     params.insert("side", side);
     params.insert("type", "MARKET");
-    params.insert("quantity", &quantity_str);
+    if reduce_only {
+        params.insert("reduceOnly", "true");
+    }
+    params.insert("quantity", quantity_str);
+    params.insert("newClientOrderId", client_order_id);
+    params.insert("newOrderRespType", "RESULT");
     params.insert("recvWindow", "10000");
     params.insert("timestamp", &timestamp_str);
 
@@ -109,81 +171,172 @@ pub async fn execute_future_order(
         .extend_pairs(&params)
         .finish();
 
-    let signature = sign_query(&query_string, secret_key);
+    let signature = sign_query(&query_string, &credentials.secret);
     let signed_query = format!("{}&signature={}", query_string, signature);
 
-    let mut headers = HeaderMap::new();
-    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-    headers.insert("X-MBX-APIKEY", HeaderValue::from_str(api_key).unwrap());
-
     let client = Client::new();
 
-    let res = client
-        .post(format!("{}?{}", base_url, signed_query))
-        .headers(headers)
+    mark_state(account, client_order_id, OrderState::Sent, None);
+
+    let res = match client
+        .post(format!("{}/order?{}", settings.future_url, signed_query))
+        .headers(signed_headers(&credentials.key))
         .send()
         .await
-        .map_err(|e| format!("Request error: {:?}", e))?;
+    {
+        Ok(res) => res,
+        Err(e) => {
+            // The request may have reached Binance; reconciliation settles the final state.
+            let err = format!("Request error: {:?}", e);
+            mark_state(account, client_order_id, OrderState::Unknown, Some(err.clone()));
+            return Err(AppError::Exchange(err));
+        }
+    };
 
     if res.status().is_success() {
-        res.json::<OrderResponse>()
-            .await
-            .map_err(|e| format!("Error interpreting JSON: {:?}", e))
+        match res.json::<OrderResponse>().await {
+            Ok(order) => {
+                mark_response(account, client_order_id, &order);
+                Ok(order)
+            }
+            Err(e) => {
+                let err = format!("Error interpreting JSON: {:?}", e);
+                mark_state(account, client_order_id, OrderState::Unknown, Some(err.clone()));
+                Err(AppError::Exchange(err))
+            }
+        }
     } else {
-        let err = res.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        Err(format!("Binance Error: {}", err))
+        let status = res.status();
+        let body = res.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        let err = format!("Binance Error: {}", body);
+        mark_state(account, client_order_id, failed_order_state(status, &body), Some(err.clone()));
+        Err(AppError::Exchange(err))
     }
 }
 
-pub async fn close_all_positions(
+pub async fn query_order_by_client_id(
     settings: &BinanceSettings,
     symbol: &str,
-) -> Result<Vec<OrderResponse>, String> {
-    let credentials = get_credentials();
-    let api_key = &credentials.key;
-    let secret_key = &credentials.secret;
+    client_order_id: &str,
+) -> Result<Option<OrderResponse>, AppError> {
+    let credentials = binance_credentials(&settings.account)?;
 
     let offset = get_server_time_offset(settings).await.unwrap_or(0);
     let timestamp = (get_timestamp() as i64 + offset) as u64;
-    let query = format!("timestamp={}", timestamp);
-    let signature = sign_query(&query, secret_key);
-    let full_query = format!("{}&signature={}", query, signature);
-
-    let position_risk_url = format!("{}/positionRisk?{}", settings.future_url_v2, full_query);
-
-    let mut headers = HeaderMap::new();
-    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-    headers.insert("X-MBX-APIKEY", HeaderValue::from_str(api_key).unwrap());
+    let query = format!(
+        "symbol={}&origClientOrderId={}&recvWindow=10000&timestamp={}",
+        symbol, client_order_id, timestamp
+    );
+    let signature = sign_query(&query, &credentials.secret);
 
     let client = Client::new();
-
     let res = client
-        .get(&position_risk_url)
-        .headers(headers.clone())
+        .get(format!("{}/order?{}&signature={}", settings.future_url, query, signature))
+        .headers(signed_headers(&credentials.key))
         .send()
         .await
-        .map_err(|e| format!("Error when querying positions: {:?}", e))?;
-
-    let status = res.status();
-    if !status.is_success() {
-        let err_text = res.text().await.unwrap_or_default();
-        return Err(format!(
-            "HTTP error {} when querying positions: {}",
-            status,
-            err_text
-        ));
+        .map_err(|e| AppError::Exchange(format!("Error querying order: {:?}", e)))?;
+
+    if res.status().is_success() {
+        return res
+            .json::<OrderResponse>()
+            .await
+            .map(Some)
+            .map_err(|e| AppError::Exchange(format!("Error interpreting JSON: {:?}", e)));
     }
 
-    let positions: Vec<serde_json::Value> = res
-        .json()
+    let err = res.text().await.unwrap_or_default();
+
+    // -2013: "Order does not exist." - the request never reached the matching engine.
+    if err.contains("-2013") {
+        return Ok(None);
+    }
+
+    Err(AppError::Exchange(format!("Binance Error: {}", err)))
+}
+
+pub async fn reconcile_orders(settings: &BinanceSettings) -> Vec<OrderRecord> {
+    let account = settings.account.as_str();
+    let mut pending = get_orders_in_state(account, OrderState::Unknown);
+    pending.extend(get_orders_in_state(account, OrderState::Sent));
+    // Pending means the process stopped between recording an order and sending it; recent ones
+    // may still be on their way out.
+    pending.extend(
+        get_orders_in_state(account, OrderState::Pending)
+            .into_iter()
+            .filter(|o| get_timestamp().saturating_sub(o.updated_at) > PENDING_GRACE_MS),
+    );
+
+    for record in &pending {
+        match query_order_by_client_id(settings, &record.symbol, &record.client_order_id).await {
+            Ok(Some(order)) => mark_response(account, &record.client_order_id, &order),
+            Ok(None) => mark_state(
+                account,
+                &record.client_order_id,
+                OrderState::Rejected,
+                Some("Order not found on Binance".to_string()),
+            ),
+            Err(e) => eprintln!("Error reconciling order {}: {}", record.client_order_id, e),
+        }
+    }
+
+    get_order_records(account)
+        .into_iter()
+        .filter(|o| pending.iter().any(|p| p.client_order_id == o.client_order_id))
+        .collect()
+}
+
+async fn check_max_notional(
+    settings: &BinanceSettings,
+    symbol: &str,
+    notional: f64,
+) -> Result<(), AppError> {
+    let positions = get_position_risk(settings, Some(symbol)).await?;
+    let position = positions.iter().find(|p| p.symbol == symbol);
+
+    let leverage = position
+        .and_then(|p| p.leverage.parse::<u32>().ok())
+        .unwrap_or_else(|| settings.leverage_for(symbol));
+    let open_notional = position
+        .map(|p| {
+            let amt = p.position_amt.parse::<f64>().unwrap_or(0.0);
+            let mark = p.mark_price.parse::<f64>().unwrap_or(0.0);
+            (amt * mark).abs()
+        })
+        .unwrap_or(0.0);
+
+    let brackets = get_leverage_brackets(settings, symbol)
+        .await
+        .map_err(|e| e.context("Error querying leverage brackets"))?;
+
+    if let Some(max_notional) = max_notional_for(&brackets, leverage)
+        && open_notional + notional > max_notional
+    {
+        return Err(AppError::RiskRejected(format!(
+            "Total position value ({:.2} USDT) exceeds the maximum notional for {}x on {} ({:.2} USDT)",
+            open_notional + notional,
+            leverage,
+            symbol,
+            max_notional
+        )));
+    }
+
+    Ok(())
+}
+
+pub async fn close_all_positions(
+    settings: &BinanceSettings,
+    symbol: &str,
+    client_order_id: &str,
+) -> Result<Vec<OrderResponse>, AppError> {
+    let positions = get_position_risk(settings, Some(symbol))
         .await
-        .map_err(|e| format!("Error interpreting JSON response: {:?}", e))?;
+        .map_err(|e| e.context("Error when querying positions"))?;
 
     let mut results = Vec::new();
 
-    for position in positions.into_iter().filter(|p| p["symbol"] == symbol) {
-        let amt = position["positionAmt"].as_str().unwrap_or("0").parse::<f64>().unwrap_or(0.0);
-        let symbol = position["symbol"].as_str().unwrap_or("");
+    for (i, position) in positions.iter().filter(|p| p.symbol == symbol).enumerate() {
+        let amt = position.position_amt.parse::<f64>().unwrap_or(0.0);
 
         if amt.abs() < 1e-8 {
             continue;
@@ -194,50 +347,77 @@ pub async fn close_all_positions(
 
         let lot_size_info = get_lot_size_info(settings, symbol).await?;
         let quantity_rounded = round_quantity(quantity, lot_size_info.step_size);
-        let precision = (1.0 / lot_size_info.step_size).log10().round() as usize;
-        let quantity_str = format!("{:.*}", precision, quantity_rounded)
-            .trim_end_matches('0')
-            .trim_end_matches('.')
-            .to_string();
-
-        let order_url = format!("{}/order", settings.future_url);
-        let timestamp = get_timestamp();
-        let timestamp_str = timestamp.to_string();
-
-        let mut params = HashMap::new();
-        params.insert("symbol", symbol);
-        params.insert("side", side);
-        params.insert("type", "MARKET");
-        params.insert("reduceOnly", "true");
-        params.insert("quantity", &quantity_str);
-        params.insert("recvWindow", "10000");
-        params.insert("timestamp", &timestamp_str);
-
-        let query_string = form_urlencoded::Serializer::new(String::new())
-            .extend_pairs(&params)
-            .finish();
+        let quantity_str = format_quantity(quantity_rounded, lot_size_info.step_size);
 
-        let signature = sign_query(&query_string, secret_key);
-        let signed_query = format!("{}&signature={}", query_string, signature);
+        let order_id = leg_client_order_id(client_order_id, i);
 
-        let response = client
-            .post(format!("{}?{}", order_url, signed_query))
-            .headers(headers.clone())
-            .send()
+        let order = submit_order(settings, symbol, side, &quantity_str, true, &order_id)
             .await
-            .map_err(|e| format!("Error sending closing order: {:?}", e))?;
-
-        if response.status().is_success() {
-            let parsed = response
-                .json::<OrderResponse>()
-                .await
-                .map_err(|e| format!("Error interpreting order: {:?}", e))?;
-            results.push(parsed);
-        } else {
-            let err_text = response.text().await.unwrap_or_default();
-            return Err(format!("Error closing position {}: {}", symbol, err_text));
-        }
+            .map_err(|e| e.context(&format!("Error closing position {}", symbol)))?;
+        record_event(
+            &settings.account,
+            symbol,
+            BlockEvent::PositionClosed {
+                client_order_id: order_id,
+                quantity: quantity_str,
+                mark_price: position.mark_price.parse().unwrap_or(0.0),
+                realized_pnl: position.unrealized_profit.parse().unwrap_or(0.0),
+            },
+        );
+        results.push(order);
     }
 
     Ok(results)
 }
+
+pub async fn reduce_position(
+    settings: &BinanceSettings,
+    symbol: &str,
+    fraction: f64,
+    client_order_id: &str,
+) -> Result<OrderResponse, AppError> {
+    let positions = get_position_risk(settings, Some(symbol)).await?;
+    let amt = positions
+        .iter()
+        .find(|p| p.symbol == symbol)
+        .and_then(|p| p.position_amt.parse::<f64>().ok())
+        .unwrap_or(0.0);
+
+    if amt.abs() < 1e-8 {
+        return Err(AppError::NotFound(format!("No open position to reduce for {}", symbol)));
+    }
+
+    let side = if amt > 0.0 { "SELL" } else { "BUY" };
+
+    let lot_size_info = get_lot_size_info(settings, symbol).await?;
+    let quantity = round_quantity(amt.abs() * fraction.clamp(0.0, 1.0), lot_size_info.step_size);
+    if quantity <= 0.0 {
+        return Err(AppError::RiskRejected(format!(
+            "Reduction quantity for {} is below the lot size",
+            symbol
+        )));
+    }
+
+    let quantity_str = format_quantity(quantity, lot_size_info.step_size);
+
+    submit_order(settings, symbol, side, &quantity_str, true, client_order_id)
+        .await
+        .map_err(|e| e.context(&format!("Error reducing position {}", symbol)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_definite_client_errors_reject_an_order() {
+        let insufficient_margin = r#"{"code":-2019,"msg":"Margin is insufficient."}"#;
+        assert_eq!(failed_order_state(StatusCode::BAD_REQUEST, insufficient_margin), OrderState::Rejected);
+
+        let timeout = r#"{"code":-1007,"msg":"Timeout waiting for response from backend server. Send status unknown; execution status unknown."}"#;
+        assert_eq!(failed_order_state(StatusCode::BAD_REQUEST, timeout), OrderState::Unknown);
+        assert_eq!(failed_order_state(StatusCode::SERVICE_UNAVAILABLE, timeout), OrderState::Unknown);
+        assert_eq!(failed_order_state(StatusCode::INTERNAL_SERVER_ERROR, insufficient_margin), OrderState::Unknown);
+        assert_eq!(failed_order_state(StatusCode::BAD_GATEWAY, "<html>Bad Gateway</html>"), OrderState::Unknown);
+    }
+}
@@ -0,0 +1,284 @@
+use crate::blockchain::{get_chain_step_for, record_event, BlockEvent};
+use crate::config::DEFAULT_ACCOUNT;
+use crate::dto::OrderResponse;
+use crate::error::AppError;
+use crate::events::{publish, EventPayload};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+const ORDERS_FILE: &str = "data/orders.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub enum OrderState {
+    Pending,
+    Sent,
+    Filled,
+    PartiallyFilled,
+    Rejected,
+    Unknown,
+}
+
+impl OrderState {
+    pub fn from_binance_status(status: &str) -> Self {
+        match status {
+            "FILLED" => OrderState::Filled,
+            "PARTIALLY_FILLED" => OrderState::PartiallyFilled,
+            "NEW" => OrderState::Sent,
+            "REJECTED" | "EXPIRED" | "CANCELED" | "EXPIRED_IN_MATCH" => OrderState::Rejected,
+            _ => OrderState::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct OrderRecord {
+    #[serde(default = "default_account")]
+    pub account: String,
+    pub client_order_id: String,
+    pub symbol: String,
+    pub side: String,
+    pub quantity: String,
+    pub reduce_only: bool,
+    pub state: OrderState,
+    pub order_id: Option<u64>,
+    pub executed_qty: Option<String>,
+    pub error: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+fn default_account() -> String {
+    DEFAULT_ACCOUNT.to_string()
+}
+
+// Client order ids are only unique within one Binance account.
+fn order_key(account: &str, client_order_id: &str) -> String {
+    format!("{}/{}", account, client_order_id)
+}
+
+static ORDERS: Lazy<Mutex<HashMap<String, OrderRecord>>> = Lazy::new(|| {
+    let orders = fs::read_to_string(ORDERS_FILE)
+        .ok()
+        .and_then(|data| serde_json::from_str::<Vec<OrderRecord>>(&data).ok())
+        .unwrap_or_default();
+
+    Mutex::new(
+        orders
+            .into_iter()
+            .map(|o| (order_key(&o.account, &o.client_order_id), o))
+            .collect(),
+    )
+});
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+fn persist(orders: &HashMap<String, OrderRecord>) {
+    let mut records: Vec<&OrderRecord> = orders.values().collect();
+    records.sort_by_key(|o| o.created_at);
+
+    if let Some(dir) = Path::new(ORDERS_FILE).parent()
+        && let Err(e) = fs::create_dir_all(dir)
+    {
+        eprintln!("Erro ao criar diretorio de ordens: {}", e);
+        return;
+    }
+
+    match serde_json::to_string_pretty(&records) {
+        Ok(json) => {
+            if let Err(e) = fs::write(ORDERS_FILE, json) {
+                eprintln!("Erro ao persistir ordens: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Erro ao serializar ordens: {}", e),
+    }
+}
+
+// Binance accepts up to 36 characters from [.A-Z:/a-z0-9_-] as newClientOrderId.
+fn fit_client_order_id(id: String) -> String {
+    if id.len() <= 36 {
+        return id;
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(id.as_bytes());
+    format!("rkd-{}", &hex::encode(hasher.finalize())[..32])
+}
+
+fn client_order_id(symbol: &str, step: &str, action: &str) -> String {
+    fit_client_order_id(format!("rkd-{}-{}-{}", symbol, step, action))
+}
+
+// The id of each further order one action places, such as closing both sides of a hedged
+// position; leg 0 keeps the action's own id.
+pub fn leg_client_order_id(client_order_id: &str, leg: usize) -> String {
+    match leg {
+        0 => client_order_id.to_string(),
+        _ => fit_client_order_id(format!("{}-{}", client_order_id, leg)),
+    }
+}
+
+// A new id per call, for actions that aren't tied to one decision.
+pub fn timed_client_order_id(symbol: &str, action: &str) -> String {
+    client_order_id(symbol, &now_ms().to_string(), action)
+}
+
+pub fn manual_client_order_id(symbol: &str, action: &str) -> String {
+    timed_client_order_id(symbol, &format!("m{}", action))
+}
+
+// Tied to the decision step of the symbol's open chain (see `get_chain_step_for`), so acting twice
+// on one status change is refused as a duplicate, while the next status change or a later chain
+// gets a new id even though indexes restart. Positions without a chain get a new id per call.
+pub fn chain_client_order_id(account: &str, symbol: &str, action: &str) -> String {
+    match get_chain_step_for(account, symbol) {
+        Some(step) => client_order_id(symbol, &step, action),
+        None => timed_client_order_id(symbol, action),
+    }
+}
+
+pub fn begin_order(
+    account: &str,
+    client_order_id: &str,
+    symbol: &str,
+    side: &str,
+    quantity: &str,
+    reduce_only: bool,
+) -> Result<(), AppError> {
+    let mut orders = ORDERS.lock().unwrap();
+
+    if let Some(existing) = orders.get(&order_key(account, client_order_id))
+        && existing.state != OrderState::Rejected
+    {
+        return Err(AppError::Conflict(format!(
+            "Duplicate order {} refused (current state: {:?})",
+            client_order_id, existing.state
+        )));
+    }
+
+    let now = now_ms();
+    let record = OrderRecord {
+        account: account.to_string(),
+        client_order_id: client_order_id.to_string(),
+        symbol: symbol.to_string(),
+        side: side.to_string(),
+        quantity: quantity.to_string(),
+        reduce_only,
+        state: OrderState::Pending,
+        order_id: None,
+        executed_qty: None,
+        error: None,
+        created_at: now,
+        updated_at: now,
+    };
+    publish(account, EventPayload::Order(record.clone()));
+    orders.insert(order_key(account, client_order_id), record);
+    persist(&orders);
+
+    Ok(())
+}
+
+pub fn mark_state(account: &str, client_order_id: &str, state: OrderState, error: Option<String>) {
+    let updated = {
+        let mut orders = ORDERS.lock().unwrap();
+        let Some(record) = orders.get_mut(&order_key(account, client_order_id)) else {
+            return;
+        };
+        record.state = state;
+        record.error = error;
+        record.updated_at = now_ms();
+        let updated = record.clone();
+        persist(&orders);
+        updated
+    };
+    publish(account, EventPayload::Order(updated.clone()));
+    record_on_chain(&updated);
+}
+
+pub fn mark_response(account: &str, client_order_id: &str, response: &OrderResponse) {
+    let updated = {
+        let mut orders = ORDERS.lock().unwrap();
+        let Some(record) = orders.get_mut(&order_key(account, client_order_id)) else {
+            return;
+        };
+        record.state = OrderState::from_binance_status(&response.status);
+        record.order_id = Some(response.order_id);
+        record.executed_qty = Some(response.executed_qty.clone());
+        record.error = None;
+        record.updated_at = now_ms();
+        let updated = record.clone();
+        persist(&orders);
+        updated
+    };
+    publish(account, EventPayload::Order(updated.clone()));
+    record_on_chain(&updated);
+}
+
+// The moment an order goes out and every state it settles in become blocks of the symbol's chain.
+fn record_on_chain(record: &OrderRecord) {
+    let event = match record.state {
+        OrderState::Pending => return,
+        OrderState::Sent if record.order_id.is_none() => BlockEvent::OrderSent {
+            client_order_id: record.client_order_id.clone(),
+            side: record.side.clone(),
+            quantity: record.quantity.clone(),
+            reduce_only: record.reduce_only,
+        },
+        _ => BlockEvent::OrderResult {
+            client_order_id: record.client_order_id.clone(),
+            state: record.state.clone(),
+            order_id: record.order_id,
+            executed_qty: record.executed_qty.clone(),
+            error: record.error.clone(),
+        },
+    };
+    record_event(&record.account, &record.symbol, event);
+}
+
+pub fn get_order_records(account: &str) -> Vec<OrderRecord> {
+    let orders = ORDERS.lock().unwrap();
+    let mut records: Vec<OrderRecord> = orders
+        .values()
+        .filter(|o| o.account == account)
+        .cloned()
+        .collect();
+    records.sort_by_key(|o| o.created_at);
+    records
+}
+
+pub fn get_orders_in_state(account: &str, state: OrderState) -> Vec<OrderRecord> {
+    get_order_records(account)
+        .into_iter()
+        .filter(|o| o.state == state)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leg_ids_stay_within_the_binance_limit() {
+        let short = client_order_id("ETHUSDT", "7", "close");
+        assert_eq!(leg_client_order_id(&short, 0), short);
+        assert_eq!(leg_client_order_id(&short, 1), format!("{}-1", short));
+
+        let hashed = client_order_id("1000SHIBUSDT", "0123abcd-124", "mclose");
+        assert_eq!(hashed.len(), 36);
+        let legs: Vec<String> = (0..3).map(|leg| leg_client_order_id(&hashed, leg)).collect();
+        assert!(legs.iter().all(|id| id.len() <= 36), "{:?}", legs);
+        assert_ne!(legs[1], legs[2]);
+        assert_eq!(legs[1], leg_client_order_id(&hashed, 1));
+    }
+}
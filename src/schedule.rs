@@ -6,8 +6,13 @@ use once_cell::sync::Lazy;
 
 use crate::config::Settings;
 use crate::spy::spy_cryptos;
-use crate::monitor::monitor_cryptos;
+use crate::monitor::{monitor_cryptos, print_snapshot, store_snapshot};
+use crate::tui;
 use crate::crypto_candidate::{process_existing_cryptos, choose_candidate_cryptos};
+use crate::liquidation::monitor_liquidation;
+use crate::funding::update_funding_accruals;
+use crate::order::reconcile_orders;
+use crate::events::{publish, EventPayload};
 
 static SCHEDULER: Lazy<Arc<Mutex<Scheduler>>> = Lazy::new(|| Arc::new(Mutex::new(Scheduler::new())));
 
@@ -34,14 +39,16 @@ impl Scheduler {
         }
 
         self.active = true;
-        let settings = Settings::load();
 
         self.handle = Some(tokio::spawn(async move {
             let mut interval = interval(Duration::from_secs(50));
 
             loop {
                 interval.tick().await;
-                execute_trade(&settings).await;
+                let settings = Settings::current();
+                for account in settings.all_accounts() {
+                    execute_trade(&account).await;
+                }
             }
         }));
     }
@@ -59,16 +66,30 @@ pub fn get_scheduler() -> Arc<Mutex<Scheduler>> {
 }
 
 async fn execute_trade(settings: &Settings) {
-    
+    if settings.binance.decide {
+        reconcile_orders(&settings.binance).await;
+    }
+
     let trades = spy_cryptos(
+        &settings.binance.account,
         &settings.binance.base_url,
+        &settings.binance.future_url,
         &settings.binance.interval,
         settings.binance.limit,
         settings.cryptos.clone(),
     )
     .await;
 
-    monitor_cryptos(&trades, settings);
+    let snapshot = monitor_cryptos(&trades, settings);
+    if !tui::is_enabled() {
+        print_snapshot(&snapshot, settings.show_details_monitor);
+    }
+    store_snapshot(&settings.binance.account, snapshot.clone());
+    publish(&settings.binance.account, EventPayload::Monitor(snapshot));
     process_existing_cryptos(&trades, settings).await;
+
+    monitor_liquidation(settings).await;
+    update_funding_accruals(&trades, settings).await;
+
     choose_candidate_cryptos(trades, settings).await;
 }
@@ -0,0 +1,59 @@
+use crate::config::ServerSettings;
+use actix_cors::Cors;
+use actix_web::http::{header::HeaderName, Method};
+use rustls::{Certificate, PrivateKey, ServerConfig};
+use std::fs::File;
+use std::io::{self, BufReader};
+
+pub fn build_cors(settings: &ServerSettings) -> Cors {
+    let mut cors = Cors::default().supports_credentials().max_age(3600);
+
+    if settings.allowed_origins.iter().any(|o| o == "*") {
+        cors = cors.allow_any_origin();
+    } else {
+        for origin in &settings.allowed_origins {
+            cors = cors.allowed_origin(origin);
+        }
+    }
+
+    let methods: Vec<Method> = settings
+        .allowed_methods
+        .iter()
+        .filter_map(|m| Method::from_bytes(m.to_uppercase().as_bytes()).ok())
+        .collect();
+    let headers: Vec<HeaderName> = settings
+        .allowed_headers
+        .iter()
+        .filter_map(|h| HeaderName::from_bytes(h.to_lowercase().as_bytes()).ok())
+        .collect();
+
+    cors.allowed_methods(methods).allowed_headers(headers)
+}
+
+pub fn load_rustls_config(cert_path: &str, key_path: &str) -> io::Result<ServerConfig> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+    let certs: Vec<Certificate> = rustls_pemfile::certs(&mut BufReader::new(File::open(cert_path)?))?
+        .into_iter()
+        .map(Certificate)
+        .collect();
+    if certs.is_empty() {
+        return Err(invalid(format!("No certificates found in {}", cert_path)));
+    }
+
+    let mut keys = rustls_pemfile::pkcs8_private_keys(&mut BufReader::new(File::open(key_path)?))?;
+    if keys.is_empty() {
+        keys = rustls_pemfile::rsa_private_keys(&mut BufReader::new(File::open(key_path)?))?;
+    }
+    let key = keys
+        .into_iter()
+        .next()
+        .map(PrivateKey)
+        .ok_or_else(|| invalid(format!("No private key found in {}", key_path)))?;
+
+    ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| invalid(format!("Invalid TLS certificate/key: {}", e)))
+}
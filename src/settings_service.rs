@@ -0,0 +1,417 @@
+use crate::config::{Settings, SettingsSource};
+use crate::error::AppError;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use utoipa::ToSchema;
+
+// generate_trade needs 71 candles of offset plus a 200-candle window.
+const MIN_CANDLES: u32 = 271;
+const MAX_CANDLES: u32 = 1000;
+const MIN_NOTIONAL: f64 = 20.0;
+const MIN_BLOCKCHAIN_INTERVAL_SECS: u64 = 60;
+const RESTART_FIELDS: &[&str] = &["server.", "credentials.", "accounts", "blockchain.", "auth."];
+const WATCH_INTERVAL_SECS: u64 = 5;
+
+#[derive(Debug, Serialize, Clone, PartialEq, ToSchema)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct ValidationIssue {
+    pub field: String,
+    pub message: String,
+    pub severity: Severity,
+}
+
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct SettingChange {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+    pub applied: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SettingsUpdateResponse {
+    pub changes: Vec<SettingChange>,
+    pub issues: Vec<ValidationIssue>,
+}
+
+fn issue(field: &str, message: String, severity: Severity) -> ValidationIssue {
+    ValidationIssue {
+        field: field.to_string(),
+        message,
+        severity,
+    }
+}
+
+// Removes duplicate symbols and reports everything that would make the bot misbehave.
+pub fn validate(settings: &mut Settings) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+
+    let mut seen = HashSet::new();
+    let mut duplicates = Vec::new();
+    settings.cryptos.retain(|symbol| {
+        let first = seen.insert(symbol.clone());
+        if !first {
+            duplicates.push(symbol.clone());
+        }
+        first
+    });
+    if !duplicates.is_empty() {
+        issues.push(issue(
+            "cryptos",
+            format!("Duplicate symbols ignored: {}", duplicates.join(", ")),
+            Severity::Warning,
+        ));
+    }
+
+    if settings.binance.limit < MIN_CANDLES {
+        issues.push(issue(
+            "binance.limit",
+            format!(
+                "limit {} is below the {} candles required by the moving average and zone windows",
+                settings.binance.limit, MIN_CANDLES
+            ),
+            Severity::Error,
+        ));
+    }
+    if settings.binance.limit > MAX_CANDLES {
+        issues.push(issue(
+            "binance.limit",
+            format!("limit {} exceeds the Binance maximum of {}", settings.binance.limit, MAX_CANDLES),
+            Severity::Error,
+        ));
+    }
+
+    if settings.money < MIN_NOTIONAL {
+        issues.push(issue(
+            "money",
+            format!(
+                "money {:.2} is below the minimum order notional of {:.2} USDT",
+                settings.money, MIN_NOTIONAL
+            ),
+            Severity::Error,
+        ));
+    }
+
+    if settings.binance.leverage == 0 {
+        issues.push(issue("binance.leverage", "leverage must be at least 1".into(), Severity::Error));
+    }
+    if settings.binance.boost_leverage < settings.binance.leverage {
+        issues.push(issue(
+            "binance.boost_leverage",
+            "boost_leverage is lower than the base leverage".into(),
+            Severity::Warning,
+        ));
+    }
+
+    for symbol in &settings.binance.symbols {
+        if symbol.leverage == Some(0) || symbol.boost_leverage == Some(0) {
+            issues.push(issue(
+                "binance.symbols",
+                format!("leverage for {} must be at least 1", symbol.symbol),
+                Severity::Error,
+            ));
+        }
+    }
+
+    if settings.limit_operations == 0 {
+        issues.push(issue(
+            "limit_operations",
+            "limit_operations is 0, no new chain will be opened".into(),
+            Severity::Warning,
+        ));
+    }
+
+    let mut names = HashSet::new();
+    for account in &settings.accounts {
+        if !names.insert(account.name.clone()) {
+            issues.push(issue(
+                "accounts",
+                format!("Duplicate account name {}", account.name),
+                Severity::Error,
+            ));
+        }
+        if account.money.is_some_and(|money| money < MIN_NOTIONAL) {
+            issues.push(issue(
+                "accounts",
+                format!("money for account {} is below {:.2} USDT", account.name, MIN_NOTIONAL),
+                Severity::Error,
+            ));
+        }
+        if account.leverage == Some(0) || account.boost_leverage == Some(0) {
+            issues.push(issue(
+                "accounts",
+                format!("leverage for account {} must be at least 1", account.name),
+                Severity::Error,
+            ));
+        }
+        if let Some(symbol) = account.symbols.iter().find(|s| !settings.cryptos.contains(s)) {
+            issues.push(issue(
+                "accounts",
+                format!("{} of account {} is not listed in cryptos", symbol, account.name),
+                Severity::Warning,
+            ));
+        }
+    }
+
+    let fraction = settings.liquidation.reduce_fraction;
+    if fraction <= 0.0 || fraction > 1.0 {
+        issues.push(issue(
+            "liquidation.reduce_fraction",
+            format!("reduce_fraction {} must be in (0, 1]", fraction),
+            Severity::Error,
+        ));
+    }
+
+    if settings.history.enabled && settings.history.retention_days == 0 {
+        issues.push(issue(
+            "history.retention_days",
+            "retention_days must be at least 1 while history is enabled".to_string(),
+            Severity::Error,
+        ));
+    }
+
+    for (field, secs) in [
+        ("anchor_interval_secs", settings.blockchain.anchor_interval_secs),
+        ("checkpoint_interval_secs", settings.blockchain.checkpoint_interval_secs),
+    ] {
+        if secs < MIN_BLOCKCHAIN_INTERVAL_SECS {
+            issues.push(issue(
+                &format!("blockchain.{}", field),
+                format!(
+                    "{} {} is below the minimum of {} seconds",
+                    field, secs, MIN_BLOCKCHAIN_INTERVAL_SECS
+                ),
+                Severity::Error,
+            ));
+        }
+    }
+
+    issues
+}
+
+pub fn has_errors(issues: &[ValidationIssue]) -> bool {
+    issues.iter().any(|i| i.severity == Severity::Error)
+}
+
+pub fn print_issues(issues: &[ValidationIssue]) {
+    for i in issues {
+        eprintln!("[settings] {:?} {}: {}", i.severity, i.field, i.message);
+    }
+}
+
+// Reads and validates the settings without echoing them, for the one-shot CLI commands.
+pub fn load_settings() -> Result<Arc<Settings>, String> {
+    let mut settings = Settings::read_from_disk()?;
+    let issues = validate(&mut settings);
+    print_issues(&issues);
+
+    if has_errors(&issues) {
+        return Err("Invalid configuration, see the errors above".into());
+    }
+
+    Settings::replace(settings);
+    Ok(Settings::current())
+}
+
+pub fn init_settings() -> Result<Arc<Settings>, String> {
+    let settings = load_settings()?;
+
+    let source = SettingsSource::current();
+    println!(
+        "[settings] Loaded {} (profile: {}, env prefix: RKD__)",
+        source.files().join(" + "),
+        source.profile.as_deref().unwrap_or("none")
+    );
+    println!(
+        "[settings] Effective configuration:\n{}",
+        serde_json::to_string_pretty(&redacted(&settings)).unwrap_or_default()
+    );
+
+    Ok(settings)
+}
+
+// Effective settings of an account, the first one when none is given.
+pub fn account_settings(account: Option<&str>) -> Result<Settings, AppError> {
+    let settings = Settings::current();
+    let names = settings.account_names();
+    let name = account.unwrap_or(&names[0]);
+
+    settings
+        .for_account(name)
+        .ok_or_else(|| AppError::NotFound(format!("Account {} not found", name)))
+}
+
+pub fn redacted(settings: &Settings) -> Value {
+    let mut value = serde_json::to_value(settings).unwrap_or(Value::Null);
+
+    if let Some(clients) = value
+        .pointer_mut("/auth/clients")
+        .and_then(|c| c.as_array_mut())
+    {
+        for client in clients {
+            for key in ["token", "secret"] {
+                if let Some(field) = client.get_mut(key)
+                    && !field.is_null()
+                {
+                    *field = Value::String("***".into());
+                }
+            }
+        }
+    }
+
+    value
+}
+
+fn flatten(prefix: &str, value: &Value, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&path, child, out);
+            }
+        }
+        _ => {
+            out.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+fn flattened(value: &Value) -> BTreeMap<String, Value> {
+    let mut fields = BTreeMap::new();
+    flatten("", value, &mut fields);
+    fields
+}
+
+// Compares the raw values, so a rotated token or secret shows up as a change, and reports the
+// redacted ones.
+fn diff(old: &Settings, new: &Settings) -> Vec<SettingChange> {
+    let old_fields = flattened(&serde_json::to_value(old).unwrap_or(Value::Null));
+    let new_fields = flattened(&serde_json::to_value(new).unwrap_or(Value::Null));
+    let old_shown = flattened(&redacted(old));
+    let new_shown = flattened(&redacted(new));
+
+    let fields: HashSet<&String> = old_fields.keys().chain(new_fields.keys()).collect();
+    let mut changes: Vec<SettingChange> = fields
+        .into_iter()
+        .filter(|field| old_fields.get(*field) != new_fields.get(*field))
+        .map(|field| SettingChange {
+            field: field.clone(),
+            applied: !RESTART_FIELDS.iter().any(|p| field.starts_with(p)),
+            old: old_shown.get(field).cloned().unwrap_or(Value::Null),
+            new: new_shown.get(field).cloned().unwrap_or(Value::Null),
+        })
+        .collect();
+    changes.sort_by(|a, b| a.field.cmp(&b.field));
+    changes
+}
+
+fn apply(mut new: Settings) -> Result<SettingsUpdateResponse, Vec<ValidationIssue>> {
+    let issues = validate(&mut new);
+    if has_errors(&issues) {
+        return Err(issues);
+    }
+
+    let current = Settings::current();
+    let changes = diff(&current, &new);
+
+    // Fields that only take effect on startup keep their running values.
+    new.server = current.server.clone();
+    new.credentials = current.credentials.clone();
+    new.accounts = current.accounts.clone();
+    new.blockchain = current.blockchain.clone();
+    // Otherwise a trader could switch authentication off or raise their own role.
+    new.auth = current.auth.clone();
+
+    Settings::replace(new);
+
+    Ok(SettingsUpdateResponse { changes, issues })
+}
+
+pub fn reload_from_disk() -> Result<SettingsUpdateResponse, Vec<ValidationIssue>> {
+    let settings = Settings::read_from_disk()
+        .map_err(|e| vec![issue("", e, Severity::Error)])?;
+    apply(settings)
+}
+
+fn merge(target: &mut Value, patch: &Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge(target.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+        (target, patch) => *target = patch.clone(),
+    }
+}
+
+pub fn apply_patch(patch: &Value) -> Result<SettingsUpdateResponse, Vec<ValidationIssue>> {
+    if !patch.is_object() {
+        return Err(vec![issue("", "Body must be a JSON object".into(), Severity::Error)]);
+    }
+
+    let current = Settings::current();
+    let mut value = serde_json::to_value(current.as_ref()).unwrap_or(Value::Object(Map::new()));
+    merge(&mut value, patch);
+
+    let settings: Settings = serde_json::from_value(value)
+        .map_err(|e| vec![issue("", format!("Invalid settings: {}", e), Severity::Error)])?;
+
+    apply(settings)
+}
+
+fn modified_at() -> Vec<Option<SystemTime>> {
+    SettingsSource::current()
+        .files()
+        .iter()
+        .map(|file| fs::metadata(file).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+pub fn spawn_settings_watcher() {
+    tokio::spawn(async move {
+        let mut last_modified = modified_at();
+        let mut interval = tokio::time::interval(Duration::from_secs(WATCH_INTERVAL_SECS));
+
+        loop {
+            interval.tick().await;
+
+            let modified = modified_at();
+            if modified == last_modified {
+                continue;
+            }
+            last_modified = modified;
+
+            match reload_from_disk() {
+                Ok(response) => {
+                    print_issues(&response.issues);
+                    for change in &response.changes {
+                        println!(
+                            "[settings] {} changed: {} -> {}{}",
+                            change.field,
+                            change.old,
+                            change.new,
+                            if change.applied { "" } else { " (requires restart)" }
+                        );
+                    }
+                }
+                Err(issues) => {
+                    eprintln!("[settings] Reload rejected, keeping the current configuration");
+                    print_issues(&issues);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,76 @@
+use crate::credential::{check_permissions, open_private};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use once_cell::sync::OnceCell;
+use rand::RngCore;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+static SIGNING_KEY: OnceCell<SigningKey> = OnceCell::new();
+
+// Loads the key that signs every block and anchor, creating it on first start. The file holds
+// the hex-encoded 32-byte seed and, like the credentials keystore, must only be readable by its owner.
+pub fn init_signing_key(path: &str) -> Result<(), String> {
+    let key = if Path::new(path).exists() {
+        check_permissions(path)?;
+        let content = fs::read_to_string(path).map_err(|e| format!("Failed to read signing key {}: {}", path, e))?;
+        let seed: [u8; 32] = hex::decode(content.trim())
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| format!("Signing key {} must hold 64 hex characters", path))?;
+        SigningKey::from_bytes(&seed)
+    } else {
+        if let Some(dir) = Path::new(path).parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let mut seed = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut seed);
+        open_private(path, true)
+            .and_then(|mut file| file.write_all(hex::encode(seed).as_bytes()))
+            .map_err(|e| format!("Failed to write signing key {}: {}", path, e))?;
+        println!("[blockchain] New signing key written to {}", path);
+        SigningKey::from_bytes(&seed)
+    };
+
+    println!("[blockchain] Signing with public key {}", hex::encode(key.verifying_key().to_bytes()));
+    let _ = SIGNING_KEY.set(key);
+    Ok(())
+}
+
+// Hex signature of `message`; empty when no key was loaded, which `verify` never accepts.
+pub fn sign(message: &[u8]) -> String {
+    SIGNING_KEY
+        .get()
+        .map(|key| hex::encode(key.sign(message).to_bytes()))
+        .unwrap_or_default()
+}
+
+pub fn public_key() -> Option<VerifyingKey> {
+    SIGNING_KEY.get().map(|key| key.verifying_key())
+}
+
+pub fn parse_public_key(value: &str) -> Result<VerifyingKey, String> {
+    let bytes: [u8; 32] = hex::decode(value.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| "Public key must be 64 hex characters".to_string())?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| format!("Invalid public key: {}", e))
+}
+
+pub fn verify(key: &VerifyingKey, message: &[u8], signature: &str) -> bool {
+    let Some(bytes) = hex::decode(signature).ok().and_then(|b| <[u8; 64]>::try_from(b).ok()) else {
+        return false;
+    };
+    key.verify_strict(message, &Signature::from_bytes(&bytes)).is_ok()
+}
+
+// Tests that sign or check blocks share one key, kept in the temp directory for the run.
+#[cfg(test)]
+pub fn init_test_key() {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| {
+        let path = std::env::temp_dir().join(format!("rkd-signing-{}.key", std::process::id()));
+        let _ = fs::remove_file(&path);
+        init_signing_key(path.to_str().unwrap()).unwrap();
+    });
+}
@@ -1,10 +1,13 @@
-use crate::binance::get_candlesticks;
+use crate::binance::{get_candlesticks, get_funding_rates};
 use crate::trade::generate_trade;
 use crate::dto::Trade;
+use crate::error::AppError;
 use futures::future::join_all;
 
 pub async fn spy_cryptos(
+    account: &str,
     base_url: &str,
+    future_url: &str,
     interval: &str,
     limit: u32,
     symbols: Vec<String>,
@@ -13,19 +16,32 @@ pub async fn spy_cryptos(
         let base_url = base_url.to_string();
         let interval = interval.to_string();
         let symbol_clone = symbol.clone();
+        let account = account.to_string();
 
         tokio::spawn(async move {
             let candles = get_candlesticks(&base_url, &symbol_clone, &interval, limit).await?;
             let ref_data = get_candlesticks(&base_url, "BTCUSDT", &interval, limit).await?;
-            let trade = generate_trade(symbol_clone, candles, ref_data);
-            Ok::<_, String>(trade)
+            let trade = generate_trade(&account, symbol_clone, candles, ref_data);
+            Ok::<_, AppError>(trade)
         })
     });
 
     let results = join_all(tasks).await;
 
+    let funding_rates = get_funding_rates(future_url).await.unwrap_or_else(|e| {
+        eprintln!("Erro ao obter funding rates: {}", e);
+        Default::default()
+    });
+
     results
         .into_iter()
         .filter_map(|r| r.ok().and_then(|res| res.ok()))
+        .map(|mut trade| {
+            if let Some(funding) = funding_rates.get(&trade.symbol) {
+                trade.funding_rate = funding.last_funding_rate.clone();
+                trade.next_funding_time = funding.next_funding_time;
+            }
+            trade
+        })
         .collect()
 }
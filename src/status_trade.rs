@@ -43,6 +43,7 @@ pub fn update_status(mut trade: Trade, last: &Trade) -> Trade {
     trade
 }
 
+#[allow(clippy::too_many_arguments)]
 fn handle_bullish_status(
     trade: &mut Trade,
     current_price: f64,
@@ -89,6 +90,7 @@ fn handle_bullish_status(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn handle_bearish_status(
     trade: &mut Trade,
     current_price: f64,
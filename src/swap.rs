@@ -1,8 +1,10 @@
 use crate::dto::{Bias, Trade, TradeStatus};
 use crate::blockchain::{remove_blockchain, get_blockchain_for};
+use crate::order_state::chain_client_order_id;
+use crate::executor::run_serialized;
 use crate::config::{Settings, BinanceSettings};
 use crate::order::close_all_positions;
-use crate::credential::get_credentials;
+use crate::credential::binance_credentials;
 use crate::binance::get_unrealized_profit;
 
 pub async fn remove_if_out_of_zone(
@@ -10,7 +12,10 @@ pub async fn remove_if_out_of_zone(
     settings: &Settings,
     binance_settings: &BinanceSettings,
 ) {
-    let credentials = get_credentials();
+    let account = binance_settings.account.as_str();
+    let Ok(credentials) = binance_credentials(account) else {
+        return;
+    };
     if let Ok(Some(pnl)) = get_unrealized_profit(
         binance_settings,
         &trade.symbol,
@@ -18,21 +23,28 @@ pub async fn remove_if_out_of_zone(
         &credentials.secret,
     )
     .await
+        && pnl >= settings.gain
     {
-        if pnl >= settings.gain {
-            match close_all_positions(binance_settings, &trade.symbol).await {
-                Ok(_) => println!(
-                    "[{}] Lucro {:.2} ≥ alvo ({:.2}) - posição fechada para {}",
-                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-                    pnl,
-                    settings.gain,
-                    trade.symbol
-                ),
-                Err(e) => eprintln!("Erro ao fechar posição {}: {}", trade.symbol, e),
-            }
-            remove_blockchain(&trade.symbol);
-            return;
+        let order_id = chain_client_order_id(account, &trade.symbol, "gain");
+        let closed = run_serialized(
+            account,
+            &trade.symbol,
+            "close_on_gain",
+            close_all_positions(binance_settings, &trade.symbol, &order_id),
+        )
+        .await;
+        match closed {
+            Ok(_) => println!(
+                "[{}] Lucro {:.2} ≥ alvo ({:.2}) - posição fechada para {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                pnl,
+                settings.gain,
+                trade.symbol
+            ),
+            Err(e) => eprintln!("Erro ao fechar posição {}: {}", trade.symbol, e),
         }
+        remove_blockchain(account, &trade.symbol);
+        return;
     }
 
     use TradeStatus::*;
@@ -40,34 +52,30 @@ pub async fn remove_if_out_of_zone(
     if matches!(trade.bias, Bias::Bullish) && matches!(trade.status, Some(OutZone5))
         || matches!(trade.bias, Bias::Bearish) && matches!(trade.status, Some(OutZone3))
     {
-        remove_blockchain(&trade.symbol);
+        remove_blockchain(account, &trade.symbol);
         return;
     }
 
-    if let Some(blocks) = get_blockchain_for(&trade.symbol) {
-        if blocks.len() >= 2 {
-            let last_status = blocks[blocks.len() - 1].trade.status.clone();
-            let previous_status = blocks[blocks.len() - 2].trade.status.clone();
-
-            match trade.bias {
-                Bias::Bullish => {
-                    if (last_status == Some(PrepareZone1) && previous_status == Some(LongZone3))
-                        || (last_status == None && previous_status == Some(TargetZone7))
-                    {
-                        remove_blockchain(&trade.symbol);
-                        return;
-                    }
-                }
-                Bias::Bearish => {
-                    if (last_status == Some(PrepareZone7) && previous_status == Some(ShortZone5))
-                        || (last_status == None && previous_status == Some(TargetZone1))
-                    {
-                        remove_blockchain(&trade.symbol);
-                        return;
-                    }
-                }
-                _ => {}
+    // Order, leverage and intervention blocks sit between status changes; only the latter count here.
+    let blocks = get_blockchain_for(account, &trade.symbol).unwrap_or_default();
+    let mut statuses = blocks.iter().rev().filter_map(|b| b.trade()).map(|t| t.status.clone());
+    if let (Some(last_status), Some(previous_status)) = (statuses.next(), statuses.next()) {
+        match trade.bias {
+            Bias::Bullish
+                if (last_status == Some(PrepareZone1) && previous_status == Some(LongZone3))
+                    || (last_status.is_none() && previous_status == Some(TargetZone7)) =>
+            {
+                remove_blockchain(account, &trade.symbol);
+                return;
+            }
+            Bias::Bearish
+                if (last_status == Some(PrepareZone7) && previous_status == Some(ShortZone5))
+                    || (last_status.is_none() && previous_status == Some(TargetZone1)) =>
+            {
+                remove_blockchain(account, &trade.symbol);
+                return;
             }
+            _ => {}
         }
     }
 
@@ -77,7 +85,7 @@ pub async fn remove_if_out_of_zone(
         let z5 = parse(&trade.zone_5);
 
         if price > z4 && price <= z5 {
-            remove_blockchain(&trade.symbol);
+            remove_blockchain(account, &trade.symbol);
         }
     }
 }
@@ -3,7 +3,19 @@ use crate::dto::{Bias, Candlestick, Trade};
 use crate::status_trade::update_status;
 use chrono::{Local, Timelike};
 
-pub fn generate_trade(symbol: String, candlesticks: Vec<Candlestick>, reference_candles: Vec<Candlestick>) -> Trade {
+pub fn generate_trade(account: &str, symbol: String, candlesticks: Vec<Candlestick>, reference_candles: Vec<Candlestick>) -> Trade {
+    let last = get_last_trade_for(account, &symbol);
+    build_trade(symbol, candlesticks, reference_candles, last.as_ref())
+}
+
+// Same as `generate_trade` with the previous block's trade passed in, so the backtest can
+// replay candles without touching the live chains.
+pub fn build_trade(
+    symbol: String,
+    candlesticks: Vec<Candlestick>,
+    reference_candles: Vec<Candlestick>,
+    last: Option<&Trade>,
+) -> Trade {
     let of = candlesticks.len();
     let reference_of = reference_candles.len();
 
@@ -33,6 +45,8 @@ pub fn generate_trade(symbol: String, candlesticks: Vec<Candlestick>, reference_
             number_of_trades: "0.0".into(),
             taker_buy_base_asset_volume: "0.0".into(),
             taker_buy_quote_asset_volume: "0.0".into(),
+            funding_rate: "0.0".into(),
+            next_funding_time: 0,
         };
     }
 
@@ -123,10 +137,12 @@ pub fn generate_trade(symbol: String, candlesticks: Vec<Candlestick>, reference_
         number_of_trades,
         taker_buy_base_asset_volume,
         taker_buy_quote_asset_volume,
+        funding_rate: "0.0".into(),
+        next_funding_time: 0,
     };
 
-    match get_last_trade_for(&symbol) {
-        Some(ref last) => update_status(trade, last),
+    match last {
+        Some(last) => update_status(trade, last),
         None => trade,
     }
 }
@@ -166,10 +182,10 @@ fn calculate_performance_24(candles: &[Candlestick]) -> f64 {
 
     let candle_21h = &candles[index_21h];
 
-    if let Ok(open_21h) = candle_21h.open_price.parse::<f64>() {
-        if open_21h != 0.0 {
-            return ((close_now / open_21h) - 1.0) * 100.0;
-        }
+    if let Ok(open_21h) = candle_21h.open_price.parse::<f64>()
+        && open_21h != 0.0
+    {
+        return ((close_now / open_21h) - 1.0) * 100.0;
     }
 
     0.0
@@ -197,12 +213,13 @@ fn calculate_performance_btc_24(candles: &[Candlestick], altcoin_perf_24: f64) -
 
     let candle_21h = &candles[index_21h];
 
-    if let Ok(open_21h) = candle_21h.open_price.parse::<f64>() {
-        if open_21h != 0.0 && close_now != 0.0 {
-            let btc_perf_24 = ((close_now / open_21h) - 1.0) * 100.0;
-            let diff = altcoin_perf_24 - btc_perf_24;
-            return format!("{:.2}", diff);
-        }
+    if let Ok(open_21h) = candle_21h.open_price.parse::<f64>()
+        && open_21h != 0.0
+        && close_now != 0.0
+    {
+        let btc_perf_24 = ((close_now / open_21h) - 1.0) * 100.0;
+        let diff = altcoin_perf_24 - btc_perf_24;
+        return format!("{:.2}", diff);
     }
 
     "0.0".into()
@@ -0,0 +1,700 @@
+use crate::blockchain::{
+    get_blockchain_for, get_current_blockchain_symbols, is_blockchain_valid, record_manual_override,
+};
+use crate::config::Settings;
+use crate::credential::has_binance_credentials;
+use crate::dto::{LiquidationRisk, MonitorQuery, TradeMonitorItem};
+use crate::executor::run_serialized;
+use crate::liquidation::get_liquidation_risks;
+use crate::monitor::{last_snapshot, page_snapshot, SORT_COLUMNS};
+use crate::monitor_table::{build_table, zone_summary, Mark, MonitorCell};
+use crate::order::close_all_positions;
+use crate::order_state::{get_order_records, manual_client_order_id, OrderState};
+use crate::schedule::get_scheduler;
+use chrono::{Local, TimeZone};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use once_cell::sync::Lazy;
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Cell, List, ListItem, Paragraph, Row, Table, TableState};
+use ratatui::{Frame, Terminal};
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const LOG_LINES: usize = 500;
+const RECENT_ORDERS: usize = 50;
+const TICK: Duration = Duration::from_millis(250);
+const POSITIONS_REFRESH: Duration = Duration::from_secs(10);
+const ZONES: [&str; 8] = ["Z1", "Z2", "Z3", "Z4", "Z5", "Z6", "Z7", "Z8"];
+const BIASES: [&str; 3] = ["Bullish", "Bearish", "None"];
+
+// While the dashboard is up the scheduler stops printing its table; the monitor pane shows it.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static LOGS: Lazy<Mutex<VecDeque<String>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+fn push_log(line: String) {
+    let mut logs = LOGS.lock().unwrap();
+    if logs.len() == LOG_LINES {
+        logs.pop_front();
+    }
+    logs.push_back(line);
+}
+
+// Sends everything the bot prints to the logs pane instead of the screen the dashboard draws on.
+#[cfg(unix)]
+mod capture {
+    use std::fs::File;
+    use std::io::{BufRead, BufReader};
+    use std::os::fd::FromRawFd;
+
+    pub struct Captured {
+        stdout: i32,
+        stderr: i32,
+    }
+
+    pub fn start() -> Option<Captured> {
+        let mut fds = [0i32; 2];
+        // SAFETY: plain descriptor calls; every result is checked before it is used.
+        unsafe {
+            if libc::pipe(fds.as_mut_ptr()) != 0 {
+                return None;
+            }
+            let captured = Captured {
+                stdout: libc::dup(libc::STDOUT_FILENO),
+                stderr: libc::dup(libc::STDERR_FILENO),
+            };
+            libc::dup2(fds[1], libc::STDOUT_FILENO);
+            libc::dup2(fds[1], libc::STDERR_FILENO);
+            libc::close(fds[1]);
+
+            let reader = File::from_raw_fd(fds[0]);
+            std::thread::spawn(move || {
+                for line in BufReader::new(reader).lines().map_while(Result::ok) {
+                    if !line.trim().is_empty() {
+                        super::push_log(line);
+                    }
+                }
+            });
+            Some(captured)
+        }
+    }
+
+    impl Drop for Captured {
+        fn drop(&mut self) {
+            // SAFETY: restores the descriptors duplicated in `start`.
+            unsafe {
+                libc::dup2(self.stdout, libc::STDOUT_FILENO);
+                libc::dup2(self.stderr, libc::STDERR_FILENO);
+                libc::close(self.stdout);
+                libc::close(self.stderr);
+            }
+        }
+    }
+
+    // The dashboard draws on the controlling terminal, which stdout no longer points to.
+    pub fn terminal() -> std::io::Result<File> {
+        std::fs::OpenOptions::new().write(true).open("/dev/tty")
+    }
+}
+
+#[cfg(not(unix))]
+mod capture {
+    pub struct Captured;
+
+    pub fn start() -> Option<Captured> {
+        None
+    }
+
+    pub fn terminal() -> std::io::Result<std::io::Stdout> {
+        Ok(std::io::stdout())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Pane {
+    Monitor,
+    Chains,
+    Positions,
+    Orders,
+    Logs,
+}
+
+impl Pane {
+    const ALL: [Pane; 5] = [Pane::Monitor, Pane::Chains, Pane::Positions, Pane::Orders, Pane::Logs];
+
+    fn title(&self) -> &'static str {
+        match self {
+            Pane::Monitor => "Monitor",
+            Pane::Chains => "Cadeias ativas",
+            Pane::Positions => "Posicoes",
+            Pane::Orders => "Ordens recentes",
+            Pane::Logs => "Logs",
+        }
+    }
+
+    fn next(self, step: isize) -> Pane {
+        let i = Pane::ALL.iter().position(|p| *p == self).unwrap_or(0) as isize;
+        Pane::ALL[(i + step).rem_euclid(Pane::ALL.len() as isize) as usize]
+    }
+}
+
+enum Input {
+    Normal,
+    Search(String),
+    ConfirmClose(String),
+}
+
+#[derive(Default)]
+struct Positions {
+    account: String,
+    risks: Vec<LiquidationRisk>,
+    error: Option<String>,
+}
+
+struct Dashboard {
+    accounts: Vec<String>,
+    account: usize,
+    focus: Pane,
+    input: Input,
+    sort: Option<usize>,
+    ascending: bool,
+    zone: Option<usize>,
+    bias: Option<usize>,
+    active_only: bool,
+    details: bool,
+    search: String,
+    status: String,
+    tables: [TableState; 5],
+    log_scroll: usize,
+    positions: Arc<Mutex<Positions>>,
+    selected_account: Arc<Mutex<String>>,
+}
+
+impl Dashboard {
+    fn new(settings: &Settings) -> Self {
+        let accounts = settings.account_names();
+        let first = accounts.first().cloned().unwrap_or_default();
+        Dashboard {
+            accounts,
+            account: 0,
+            focus: Pane::Monitor,
+            input: Input::Normal,
+            sort: None,
+            ascending: false,
+            zone: None,
+            bias: None,
+            active_only: false,
+            details: settings.show_details_monitor,
+            search: String::new(),
+            status: String::new(),
+            tables: Default::default(),
+            log_scroll: 0,
+            positions: Arc::new(Mutex::new(Positions::default())),
+            selected_account: Arc::new(Mutex::new(first)),
+        }
+    }
+
+    fn account(&self) -> &str {
+        self.accounts.get(self.account).map(String::as_str).unwrap_or_default()
+    }
+
+    fn table(&mut self, pane: Pane) -> &mut TableState {
+        &mut self.tables[Pane::ALL.iter().position(|p| *p == pane).unwrap_or(0)]
+    }
+
+    fn monitor_query(&self) -> MonitorQuery {
+        MonitorQuery {
+            account: Some(self.account().to_string()),
+            sort: self.sort.map(|i| SORT_COLUMNS[i].to_string()),
+            order: Some(if self.ascending { "asc" } else { "desc" }.to_string()),
+            zone: self.zone.map(|i| ZONES[i].to_string()),
+            bias: self.bias.map(|i| BIASES[i].to_string()),
+            active_only: self.active_only,
+            page_size: Some(usize::MAX),
+            ..Default::default()
+        }
+    }
+
+    fn monitor_items(&self) -> (Vec<TradeMonitorItem>, String) {
+        let Some(snapshot) = last_snapshot(self.account()) else {
+            return (vec![], "Aguardando o primeiro ciclo do scheduler".to_string());
+        };
+        let distribution = zone_summary(&snapshot.zone_distribution);
+        match page_snapshot(snapshot, &self.monitor_query()) {
+            Ok(page) => {
+                let search = self.search.to_uppercase();
+                let items = page
+                    .trades
+                    .into_iter()
+                    .filter(|t| t.symbol.contains(&search))
+                    .collect();
+                (items, format!("{} | {}", page.timestamp, distribution))
+            }
+            Err(e) => (vec![], e.to_string()),
+        }
+    }
+
+    // Symbol under the cursor in the chains or positions pane, the ones a close applies to.
+    fn selected_symbol(&self) -> Option<String> {
+        let index = |pane: Pane| self.tables[Pane::ALL.iter().position(|p| *p == pane).unwrap()].selected();
+        match self.focus {
+            Pane::Chains => {
+                let symbols = get_current_blockchain_symbols(self.account());
+                index(Pane::Chains).and_then(|i| symbols.get(i).cloned())
+            }
+            Pane::Positions => {
+                let positions = self.positions.lock().unwrap();
+                index(Pane::Positions).and_then(|i| positions.risks.get(i).map(|r| r.symbol.clone()))
+            }
+            Pane::Monitor => {
+                let (items, _) = self.monitor_items();
+                index(Pane::Monitor).and_then(|i| items.get(i).map(|t| t.symbol.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    // Returns false when the dashboard should exit.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match std::mem::replace(&mut self.input, Input::Normal) {
+            Input::Search(mut text) => {
+                match key.code {
+                    KeyCode::Enter => self.search = text,
+                    KeyCode::Esc => {}
+                    KeyCode::Backspace => {
+                        text.pop();
+                        self.input = Input::Search(text);
+                    }
+                    KeyCode::Char(c) => {
+                        text.push(c);
+                        self.input = Input::Search(text);
+                    }
+                    _ => self.input = Input::Search(text),
+                }
+                return true;
+            }
+            Input::ConfirmClose(symbol) => {
+                if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                    self.close_position(symbol);
+                } else {
+                    self.status = "Fechamento cancelado".to_string();
+                }
+                return true;
+            }
+            Input::Normal => {}
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Tab => self.focus = self.focus.next(1),
+            KeyCode::BackTab => self.focus = self.focus.next(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_cursor(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_cursor(-1),
+            KeyCode::Char('s') => {
+                self.sort = match self.sort {
+                    None => Some(0),
+                    Some(i) if i + 1 < SORT_COLUMNS.len() => Some(i + 1),
+                    Some(_) => None,
+                }
+            }
+            KeyCode::Char('o') => self.ascending = !self.ascending,
+            KeyCode::Char('z') => self.zone = cycle(self.zone, ZONES.len()),
+            KeyCode::Char('b') => self.bias = cycle(self.bias, BIASES.len()),
+            KeyCode::Char('a') => self.active_only = !self.active_only,
+            KeyCode::Char('d') => self.details = !self.details,
+            KeyCode::Char('/') => self.input = Input::Search(self.search.clone()),
+            KeyCode::Char('n') if !self.accounts.is_empty() => {
+                self.account = (self.account + 1) % self.accounts.len();
+                *self.selected_account.lock().unwrap() = self.account().to_string();
+                self.tables = Default::default();
+            }
+            KeyCode::Char('S') => {
+                get_scheduler().lock().unwrap().start();
+                self.status = "Scheduler iniciado".to_string();
+            }
+            KeyCode::Char('X') => {
+                get_scheduler().lock().unwrap().stop();
+                self.status = "Scheduler parado".to_string();
+            }
+            KeyCode::Char('c') => match self.selected_symbol() {
+                Some(symbol) => self.input = Input::ConfirmClose(symbol),
+                None => self.status = "Selecione um simbolo no monitor, cadeias ou posicoes".to_string(),
+            },
+            _ => {}
+        }
+        true
+    }
+
+    fn move_cursor(&mut self, step: isize) {
+        if self.focus == Pane::Logs {
+            self.log_scroll = (self.log_scroll as isize - step).max(0) as usize;
+            return;
+        }
+        let focus = self.focus;
+        let table = self.table(focus);
+        let next = table.selected().map_or(0, |i| (i as isize + step).max(0) as usize);
+        table.select(Some(next));
+    }
+
+    fn close_position(&mut self, symbol: String) {
+        let account = self.account().to_string();
+        let Some(settings) = Settings::current().for_account(&account) else {
+            self.status = format!("Conta {} nao encontrada", account);
+            return;
+        };
+
+        self.status = format!("Fechando posicao de {} na conta {}", symbol, account);
+        tokio::spawn(async move {
+            let order_id = manual_client_order_id(&symbol, "close");
+            let result = run_serialized(
+                &account,
+                &symbol,
+                "manual_close",
+                close_all_positions(&settings.binance, &symbol, &order_id),
+            )
+            .await;
+            record_manual_override(&account, &symbol, "close", "tui", &result);
+            match result {
+                Ok(orders) => println!("Posicao de {} fechada ({} ordens)", symbol, orders.len()),
+                Err(e) => eprintln!("Erro ao fechar posicao de {}: {}", symbol, e),
+            }
+        });
+    }
+}
+
+fn cycle(current: Option<usize>, len: usize) -> Option<usize> {
+    match current {
+        None => Some(0),
+        Some(i) if i + 1 < len => Some(i + 1),
+        Some(_) => None,
+    }
+}
+
+// Positions come from Binance, so they are polled in the background instead of on every frame.
+fn spawn_positions_refresh(positions: Arc<Mutex<Positions>>, account: Arc<Mutex<String>>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(POSITIONS_REFRESH);
+        loop {
+            interval.tick().await;
+            let name = account.lock().unwrap().clone();
+            let Some(settings) = Settings::current().for_account(&name) else {
+                continue;
+            };
+
+            let (risks, error) = if !has_binance_credentials(&name) {
+                (vec![], Some("Sem credenciais Binance para esta conta".to_string()))
+            } else {
+                match get_liquidation_risks(&settings).await {
+                    Ok(risks) => (risks, None),
+                    Err(e) => (vec![], Some(e.to_string())),
+                }
+            };
+            *positions.lock().unwrap() = Positions { account: name, risks, error };
+        }
+    });
+}
+
+fn color_of(cell: &MonitorCell) -> Style {
+    match cell.mark {
+        Some(Mark::Max) => Style::default().fg(Color::Green),
+        Some(Mark::Min) => Style::default().fg(Color::Red),
+        Some(Mark::Active) => Style::default().fg(Color::Yellow),
+        Some(Mark::Highlight) => Style::default().fg(Color::Blue),
+        None => Style::default(),
+    }
+}
+
+fn pane_block(pane: Pane, focus: Pane, extra: String) -> Block<'static> {
+    let style = if pane == focus {
+        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    };
+    let title = if extra.is_empty() {
+        format!(" {} ", pane.title())
+    } else {
+        format!(" {} - {} ", pane.title(), extra)
+    };
+    Block::bordered().title(title).border_style(style)
+}
+
+fn header_row(headers: &[&str]) -> Row<'static> {
+    Row::new(headers.iter().map(|h| Cell::from(h.to_string())))
+        .style(Style::default().add_modifier(Modifier::BOLD))
+}
+
+fn draw_table(
+    frame: &mut Frame,
+    area: Rect,
+    block: Block<'static>,
+    headers: &[&str],
+    rows: Vec<Row<'static>>,
+    state: &mut TableState,
+) {
+    if let Some(selected) = state.selected()
+        && selected >= rows.len()
+    {
+        state.select(rows.len().checked_sub(1));
+    }
+    let widths = vec![Constraint::Fill(1); headers.len()];
+    let table = Table::new(rows, widths)
+        .header(header_row(headers))
+        .block(block)
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(table, area, state);
+}
+
+fn timestamp_secs(secs: u64) -> String {
+    Local
+        .timestamp_opt(secs as i64, 0)
+        .single()
+        .map(|t| t.format("%d/%m %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+fn draw(frame: &mut Frame, dashboard: &mut Dashboard) {
+    let [header, body, footer] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Fill(1),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [monitor_area, lower] =
+        Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(body);
+    let [left, right] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(lower);
+    let [chains_area, positions_area] =
+        Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(left);
+    let [orders_area, logs_area] =
+        Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(right);
+
+    let account = dashboard.account().to_string();
+    let focus = dashboard.focus;
+    let running = get_scheduler().lock().unwrap().is_active();
+
+    frame.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled(" RKD ", Style::default().add_modifier(Modifier::REVERSED)),
+            Span::raw(format!(" conta: {} | scheduler: ", account)),
+            if running {
+                Span::styled("ativo", Style::default().fg(Color::Green))
+            } else {
+                Span::styled("parado", Style::default().fg(Color::Red))
+            },
+            Span::raw(format!(
+                " | ordem: {} {} | zona: {} | bias: {} | abertas: {} | busca: {}",
+                dashboard.sort.map_or("-", |i| SORT_COLUMNS[i]),
+                if dashboard.ascending { "asc" } else { "desc" },
+                dashboard.zone.map_or("todas", |i| ZONES[i]),
+                dashboard.bias.map_or("todos", |i| BIASES[i]),
+                if dashboard.active_only { "sim" } else { "nao" },
+                if dashboard.search.is_empty() { "-" } else { &dashboard.search },
+            )),
+        ])),
+        header,
+    );
+
+    // Monitor
+    let (items, summary) = dashboard.monitor_items();
+    let table = build_table(&items, dashboard.details);
+    let rows = table
+        .rows
+        .iter()
+        .map(|row| Row::new(row.iter().map(|c| Cell::from(c.text.clone()).style(color_of(c)))))
+        .collect();
+    draw_table(
+        frame,
+        monitor_area,
+        pane_block(Pane::Monitor, focus, summary),
+        &table.headers,
+        rows,
+        dashboard.table(Pane::Monitor),
+    );
+
+    // Active chains and the FSM status of their last status change block
+    let rows = get_current_blockchain_symbols(&account)
+        .into_iter()
+        .map(|symbol| {
+            let blocks = get_blockchain_for(&account, &symbol).unwrap_or_default();
+            let trade = blocks.iter().rev().find_map(|b| b.trade());
+            let last = blocks.last();
+            let valid = is_blockchain_valid(&account, &symbol).unwrap_or(false);
+            Row::new(vec![
+                Cell::from(symbol.clone()),
+                Cell::from(
+                    trade.and_then(|t| t.status.as_ref())
+                        .map(|s| format!("{:?}", s))
+                        .unwrap_or_else(|| "-".to_string()),
+                ),
+                Cell::from(trade.map(|t| format!("{:?}", t.bias)).unwrap_or_default()),
+                Cell::from(trade.map(|t| t.current_price.clone()).unwrap_or_default()),
+                Cell::from(blocks.len().to_string()),
+                Cell::from(last.map(|b| timestamp_secs(b.timestamp)).unwrap_or_default()),
+                Cell::from(if valid { "ok" } else { "invalida" })
+                    .style(Style::default().fg(if valid { Color::Green } else { Color::Red })),
+            ])
+        })
+        .collect();
+    draw_table(
+        frame,
+        chains_area,
+        pane_block(Pane::Chains, focus, String::new()),
+        &["Symbol", "Status", "Bias", "Preco", "Blocos", "Ultimo", "Cadeia"],
+        rows,
+        dashboard.table(Pane::Chains),
+    );
+
+    // Open positions
+    let (rows, note) = {
+        let positions = dashboard.positions.lock().unwrap();
+        let rows: Vec<Row> = if positions.account == account {
+            positions
+                .risks
+                .iter()
+                .map(|r| {
+                    let style = if r.at_risk {
+                        Style::default().fg(Color::Red)
+                    } else {
+                        Style::default()
+                    };
+                    Row::new(vec![
+                        r.symbol.clone(),
+                        format!("{}", r.position_amt),
+                        format!("{:.4}", r.mark_price),
+                        r.liquidation_price.map(|p| format!("{:.4}", p)).unwrap_or_else(|| "-".into()),
+                        r.distance_pct.map(|d| format!("{:.2}%", d)).unwrap_or_else(|| "-".into()),
+                        format!("{}x", r.leverage),
+                    ])
+                    .style(style)
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+        let note = if positions.account != account {
+            "carregando".to_string()
+        } else {
+            positions.error.clone().unwrap_or_default()
+        };
+        (rows, note)
+    };
+    draw_table(
+        frame,
+        positions_area,
+        pane_block(Pane::Positions, focus, note),
+        &["Symbol", "Qtd", "Mark", "Liquidacao", "Distancia", "Alav."],
+        rows,
+        dashboard.table(Pane::Positions),
+    );
+
+    // Recent orders, newest first
+    let rows = get_order_records(&account)
+        .into_iter()
+        .rev()
+        .take(RECENT_ORDERS)
+        .map(|o| {
+            let color = match o.state {
+                OrderState::Filled => Color::Green,
+                OrderState::Rejected => Color::Red,
+                OrderState::Pending | OrderState::Sent | OrderState::PartiallyFilled => Color::Yellow,
+                OrderState::Unknown => Color::Gray,
+            };
+            Row::new(vec![
+                Cell::from(timestamp_secs(o.updated_at / 1000)),
+                Cell::from(o.symbol),
+                Cell::from(o.side),
+                Cell::from(o.quantity),
+                Cell::from(format!("{:?}", o.state)).style(Style::default().fg(color)),
+                Cell::from(o.error.unwrap_or_default()),
+            ])
+        })
+        .collect();
+    draw_table(
+        frame,
+        orders_area,
+        pane_block(Pane::Orders, focus, String::new()),
+        &["Hora", "Symbol", "Lado", "Qtd", "Estado", "Erro"],
+        rows,
+        dashboard.table(Pane::Orders),
+    );
+
+    // Logs, following the tail unless scrolled up
+    let height = logs_area.height.saturating_sub(2) as usize;
+    let logs = LOGS.lock().unwrap();
+    dashboard.log_scroll = dashboard.log_scroll.min(logs.len().saturating_sub(height));
+    let end = logs.len() - dashboard.log_scroll;
+    let lines: Vec<ListItem> = logs
+        .iter()
+        .take(end)
+        .skip(end.saturating_sub(height))
+        .map(|l| ListItem::new(l.clone()))
+        .collect();
+    drop(logs);
+    frame.render_widget(
+        List::new(lines).block(pane_block(Pane::Logs, focus, String::new())),
+        logs_area,
+    );
+
+    let help = match &dashboard.input {
+        Input::Search(text) => format!(" Buscar simbolo: {}_ (Enter aplica, Esc cancela)", text),
+        Input::ConfirmClose(symbol) => {
+            format!(" Fechar todas as posicoes de {} na conta {}? (y/n)", symbol, account)
+        }
+        Input::Normal if !dashboard.status.is_empty() => format!(" {}", dashboard.status),
+        Input::Normal => " q sair | Tab painel | ↑↓ selecionar | s ordenar | o asc/desc | z zona | b bias | a abertas | d detalhes | / buscar | n conta | S iniciar | X parar | c fechar posicao".to_string(),
+    };
+    frame.render_widget(
+        Paragraph::new(help).style(Style::default().add_modifier(Modifier::REVERSED)),
+        footer,
+    );
+}
+
+// Runs the dashboard until `q` is pressed. Must be called from a blocking task on the
+// server's runtime so scheduler and close commands can spawn onto it.
+pub fn run() -> io::Result<()> {
+    let settings = Settings::current();
+    let mut dashboard = Dashboard::new(&settings);
+    spawn_positions_refresh(dashboard.positions.clone(), dashboard.selected_account.clone());
+
+    let mut terminal = Terminal::new(CrosstermBackend::new(capture::terminal()?))?;
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+    ENABLED.store(true, Ordering::Relaxed);
+    let captured = capture::start();
+
+    let result = event_loop(&mut terminal, &mut dashboard);
+
+    drop(captured);
+    ENABLED.store(false, Ordering::Relaxed);
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+fn event_loop<W: Write>(terminal: &mut Terminal<CrosstermBackend<W>>, dashboard: &mut Dashboard) -> io::Result<()> {
+    loop {
+        terminal.draw(|frame| draw(frame, dashboard))?;
+
+        if event::poll(TICK)?
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+            && !dashboard.handle_key(key)
+        {
+            return Ok(());
+        }
+    }
+}